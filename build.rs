@@ -0,0 +1,34 @@
+use std::fs;
+use std::process::Command;
+
+fn main() {
+    let commit = Command::new("git")
+        .args(&["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_else(|| String::from("unknown"));
+    println!("cargo:rustc-env=OPENMPRDBC_GIT_COMMIT={}", commit);
+
+    let sequoia = fs::read_to_string("Cargo.lock")
+        .ok()
+        .and_then(|lock| locked_version(lock.as_str(), "sequoia-openpgp"))
+        .unwrap_or_else(|| String::from("unknown"));
+    println!("cargo:rustc-env=OPENMPRDBC_SEQUOIA_VERSION={}", sequoia);
+
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=Cargo.lock");
+}
+
+fn locked_version(lock: &str, package: &str) -> Option<String> {
+    let name = format!("name = \"{}\"", package);
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == name {
+            let version = lines.next()?.trim();
+            return version.strip_prefix("version = \"")?.strip_suffix('"').map(String::from)
+        }
+    }
+    None
+}
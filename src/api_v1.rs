@@ -29,6 +29,19 @@ pub enum RequestMethod {
     DELETE
 }
 
+impl RequestMethod {
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GET => "GET",
+            Self::PUT => "PUT",
+            Self::POST => "POST",
+            Self::DELETE => "DELETE",
+            Self::PATCH => "PATCH",
+        }
+    }
+}
+
 
 pub trait RequestInfo {
 
@@ -90,6 +103,102 @@ impl std::error::Error for ErrorResponse {
     
 }
 
+
+/**
+ * canonical form of signed content
+ *
+ * the bytes written here are what other servers verify, so the layout is versioned:
+ * one `key: value` line per field, in the order of the content's fields, floats in their
+ * shortest round-trip form. a value with a line break is rejected, as flattening or escaping
+ * it would sign something other than what was given.
+ * optional fields may only be appended and are omitted when unset.
+ */
+
+pub const CONTENT_VERSION: u32 = 1;
+
+pub trait CanonicalContent {
+
+    /// written through the content's staged writer, see `canonical_fields!`
+    fn write_fields(&self, w: CanonicalWriter<'_>) -> io::Result<Written>;
+}
+
+pub struct CanonicalWriter<'w> {
+    w: &'w mut dyn io::Write,
+}
+
+impl<'w> CanonicalWriter<'w> {
+
+    fn new(w: &'w mut dyn io::Write) -> Self {
+        CanonicalWriter { w }
+    }
+
+    fn field<T: Display>(&mut self, key: &'static str, value: T) -> io::Result<()> {
+        let value = value.to_string();
+        if value.contains(|c| c == '\n' || c == '\r') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} must be a single line", key)))
+        }
+        self.w.write_fmt(format_args!("{}: {}\n", key, value))
+    }
+}
+
+/// proof that the last field was written; only the final stage of a staged writer hands it out
+pub struct Written(());
+
+/**
+ * a staged writer per content type: stage `N` only has a method for the field at index `N`,
+ * and `finish` exists only after the last one, so a field out of order or left out does not compile
+ */
+macro_rules! canonical_fields {
+    ($name:ident { $($stage:literal => $field:ident: $kind:ident),+ $(,)? }) => {
+        pub struct $name<'w, const N: usize>(CanonicalWriter<'w>);
+
+        impl<'w> $name<'w, 0> {
+
+            pub const FIELDS: &'static [&'static str] = &[$(stringify!($field)),+];
+
+            pub fn new(w: CanonicalWriter<'w>) -> Self {
+                $name(w)
+            }
+        }
+
+        impl<'w> $name<'w, { [$($stage),+].len() }> {
+
+            pub fn finish(self) -> Written {
+                Written(())
+            }
+        }
+
+        $(
+            canonical_fields!(@$kind $name, $stage, $field);
+        )+
+    };
+    (@required $name:ident, $stage:literal, $field:ident) => {
+        impl<'w> $name<'w, $stage> {
+
+            pub fn $field<T: Display>(mut self, value: T) -> io::Result<$name<'w, { $stage + 1 }>> {
+                self.0.field(stringify!($field), value)?;
+                Ok($name(self.0))
+            }
+        }
+    };
+    (@optional $name:ident, $stage:literal, $field:ident) => {
+        impl<'w> $name<'w, $stage> {
+
+            pub fn $field<T: Display>(mut self, value: Option<T>) -> io::Result<$name<'w, { $stage + 1 }>> {
+                if let Some(value) = value {
+                    self.0.field(stringify!($field), value)?;
+                }
+                Ok($name(self.0))
+            }
+        }
+    };
+}
+
+pub fn write_canonical<C: CanonicalContent, W: io::Write>(content: &C, mut w: W) -> io::Result<()> {
+    content.write_fields(CanonicalWriter::new(&mut w))?;
+    Ok(())
+}
+
 /**
  * 
  */
@@ -100,12 +209,24 @@ pub struct RegisterContent {
     pub server_name: String
 }
 
+canonical_fields!(RegisterFields {
+    0 => server_name: required,
+});
+
+impl CanonicalContent for RegisterContent {
+
+    fn write_fields(&self, w: CanonicalWriter<'_>) -> io::Result<Written> {
+        Ok(RegisterFields::new(w)
+            .server_name(&self.server_name)?
+            .finish())
+    }
+}
+
 impl WriteTo for RegisterContent {
     type Error = io::Error;
 
-    fn write_to<W: io::Write + Sync + Send>(&self, mut w: W) -> Result<(), Self::Error> {
-        w.write_fmt(format_args!("server_name: {}\n", self.server_name))?;
-        Ok(())
+    fn write_to<W: io::Write + Sync + Send>(&self, w: W) -> Result<(), Self::Error> {
+        write_canonical(self, w)
     }
 }
 
@@ -201,13 +322,26 @@ pub struct UnregisterContent {
     pub comment: String,
 }
 
+canonical_fields!(UnregisterFields {
+    0 => timestamp: required,
+    1 => comment: required,
+});
+
+impl CanonicalContent for UnregisterContent {
+
+    fn write_fields(&self, w: CanonicalWriter<'_>) -> io::Result<Written> {
+        Ok(UnregisterFields::new(w)
+            .timestamp(self.timestamp)?
+            .comment(&self.comment)?
+            .finish())
+    }
+}
+
 impl WriteTo for UnregisterContent {
     type Error = io::Error;
 
-    fn write_to<W: io::Write + Sync + Send>(&self, mut w: W) -> Result<(), Self::Error> {
-        w.write_fmt(format_args!("timestamp: {}\n", self.timestamp))?;
-        w.write_fmt(format_args!("comment: {}\n", self.comment))?;
-        Ok(())
+    fn write_to<W: io::Write + Sync + Send>(&self, w: W) -> Result<(), Self::Error> {
+        write_canonical(self, w)
     }
 }
 
@@ -283,16 +417,32 @@ pub struct SubmitContent {
     pub comment: String,
 }
 
+canonical_fields!(SubmitFields {
+    0 => uuid: required,
+    1 => timestamp: required,
+    2 => player_uuid: required,
+    3 => points: required,
+    4 => comment: required,
+});
+
+impl CanonicalContent for SubmitContent {
+
+    fn write_fields(&self, w: CanonicalWriter<'_>) -> io::Result<Written> {
+        Ok(SubmitFields::new(w)
+            .uuid(self.uuid)?
+            .timestamp(self.timestamp)?
+            .player_uuid(self.player_uuid)?
+            .points(self.points)?
+            .comment(&self.comment)?
+            .finish())
+    }
+}
+
 impl WriteTo for SubmitContent {
     type Error = io::Error;
 
-    fn write_to<W: io::Write + Sync + Send>(&self, mut w: W) -> Result<(), Self::Error> {
-        w.write_fmt(format_args!("uuid: {}\n", self.uuid))?;
-        w.write_fmt(format_args!("timestamp: {}\n", self.timestamp))?;
-        w.write_fmt(format_args!("player_uuid: {}\n", self.player_uuid))?;
-        w.write_fmt(format_args!("points: {}\n", self.points))?;
-        w.write_fmt(format_args!("comment: {}\n", self.comment))?;
-        Ok(())
+    fn write_to<W: io::Write + Sync + Send>(&self, w: W) -> Result<(), Self::Error> {
+        write_canonical(self, w)
     }
 }
 
@@ -398,13 +548,26 @@ pub struct RecallContent {
     pub comment: String
 }
 
+canonical_fields!(RecallFields {
+    0 => timestamp: required,
+    1 => comment: required,
+});
+
+impl CanonicalContent for RecallContent {
+
+    fn write_fields(&self, w: CanonicalWriter<'_>) -> io::Result<Written> {
+        Ok(RecallFields::new(w)
+            .timestamp(self.timestamp)?
+            .comment(&self.comment)?
+            .finish())
+    }
+}
+
 impl WriteTo for RecallContent {
     type Error = io::Error;
 
-    fn write_to<W: io::Write + Sync + Send>(&self, mut w: W) -> Result<(), Self::Error> {
-        w.write_fmt(format_args!("timestamp: {}\n", self.timestamp))?;
-        w.write_fmt(format_args!("comment: {}\n", self.comment))?;
-        Ok(())
+    fn write_to<W: io::Write + Sync + Send>(&self, w: W) -> Result<(), Self::Error> {
+        write_canonical(self, w)
     }
 }
 
@@ -463,6 +626,44 @@ pub struct RecallResponse {
 
 
 
+/**
+ * `GET ping`; registries without the endpoint answer 404, which still shows they are reachable
+ */
+
+
+pub struct PingRequest;
+
+impl WriteTo for PingRequest {
+    type Error = anyhow::Error;
+
+    fn write_to<W: io::Write + Sync + Send>(&self, _w: W) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl RequestInfo for PingRequest {
+
+    fn method(&self) -> RequestMethod {
+        RequestMethod::GET
+    }
+
+    fn content_type(&self) -> &'static str {
+        ""
+    }
+
+    fn url<'b>(&self, base_url: &'b Url) -> Cow<'b, Url> {
+        Cow::Owned(base_url.join("ping").unwrap())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PingResponse {
+
+    pub status: Status,
+}
+
+
+
 /**
  * 
  */
@@ -535,6 +736,117 @@ pub struct ServerData {
 
 
 
+/**
+ * 
+ */
+
+
+pub struct GetServerRequest {
+    server_uuid: Uuid,
+}
+
+impl GetServerRequest {
+
+    pub fn new(server_uuid: Uuid) -> Self {
+        GetServerRequest {
+            server_uuid
+        }
+    }
+}
+
+impl WriteTo for GetServerRequest {
+    type Error = anyhow::Error;
+
+    fn write_to<W: io::Write + Sync + Send>(&self, _w: W) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl RequestInfo for GetServerRequest {
+
+    fn method(&self) -> RequestMethod {
+        RequestMethod::GET
+    }
+
+    fn content_type(&self) -> &'static str {
+        ""
+    }
+
+    fn url<'b>(&self, base_url: &'b Url) -> Cow<'b, Url> {
+        Cow::Owned(base_url.join(&format!("v1/server/uuid/{}", self.server_uuid.to_hyphenated_ref())).unwrap())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct GetServerResponse {
+
+    pub status: Status,
+
+    #[serde(flatten)]
+    pub server: ServerData,
+}
+
+
+
+/**
+ * 
+ */
+
+
+pub struct ServerInfoRequest {
+    server_uuid: Uuid,
+}
+
+impl ServerInfoRequest {
+
+    pub fn new(server_uuid: Uuid) -> Self {
+        ServerInfoRequest {
+            server_uuid
+        }
+    }
+}
+
+impl WriteTo for ServerInfoRequest {
+    type Error = anyhow::Error;
+
+    fn write_to<W: io::Write + Sync + Send>(&self, _w: W) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl RequestInfo for ServerInfoRequest {
+
+    fn method(&self) -> RequestMethod {
+        RequestMethod::GET
+    }
+
+    fn content_type(&self) -> &'static str {
+        ""
+    }
+
+    fn url<'b>(&self, base_url: &'b Url) -> Cow<'b, Url> {
+        Cow::Owned(base_url.join(&format!("v1/server/uuid/{}", self.server_uuid.to_hyphenated_ref())).unwrap())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ServerInfoResponse {
+
+    pub status: Status,
+
+    pub uuid: Uuid,
+
+    pub server_name: String,
+
+    #[serde(deserialize_with = "deserialize_fromstr")]
+    pub key_id: KeyID,
+
+    #[serde(deserialize_with = "deserialize_fromstr")]
+    pub public_key: Cert,
+}
+
+
+
 /**
  * 
  */
@@ -596,6 +908,7 @@ pub struct GetSubmitResponse {
  */
 
 
+#[derive(Clone)]
 pub enum ServerHandle {
     ServerUUID(Uuid),
     KeyID(KeyID)
@@ -619,15 +932,19 @@ pub struct GetServerSubmitRequest {
     handle: ServerHandle,
     limit: Option<usize>,
     after: Option<u64>,
+    before: Option<u64>,
+    player_uuid: Option<Uuid>,
 }
 
 impl GetServerSubmitRequest {
 
-    pub fn new(handle: ServerHandle, limit: Option<usize>, after: Option<u64>) -> Self {
+    pub fn new(handle: ServerHandle, limit: Option<usize>, after: Option<u64>, before: Option<u64>, player_uuid: Option<Uuid>) -> Self {
         GetServerSubmitRequest {
             handle,
             limit,
-            after
+            after,
+            before,
+            player_uuid
         }
     }
 }
@@ -668,12 +985,58 @@ impl RequestInfo for GetServerSubmitRequest {
             if let Some(after) = self.after {
                 pairs.append_pair("after", after.to_string().as_str());
             }
+            if let Some(before) = self.before {
+                pairs.append_pair("before", before.to_string().as_str());
+            }
+            if let Some(player_uuid) = self.player_uuid {
+                pairs.append_pair("player", player_uuid.to_hyphenated_ref().to_string().as_str());
+            }
         }
         Cow::Owned(url)
     }
 }
 
 
+/**
+ * submits about one player, from every server in the registry; answered with a `GetServerSubmitResponse`
+ */
+pub struct GetPlayerSubmitRequest {
+    player_uuid: Uuid,
+}
+
+impl GetPlayerSubmitRequest {
+
+    pub fn new(player_uuid: Uuid) -> Self {
+        GetPlayerSubmitRequest {
+            player_uuid
+        }
+    }
+}
+
+impl WriteTo for GetPlayerSubmitRequest {
+    type Error = anyhow::Error;
+
+    fn write_to<W: io::Write + Sync + Send>(&self, w: W) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl RequestInfo for GetPlayerSubmitRequest {
+
+    fn method(&self) -> RequestMethod {
+        RequestMethod::GET
+    }
+
+    fn content_type(&self) -> &'static str {
+        ""
+    }
+
+    fn url<'b>(&self, base_url: &'b Url) -> Cow<'b, Url> {
+        Cow::Owned(base_url.join(&format!("v1/submit/player/{}", self.player_uuid.to_hyphenated_ref())).unwrap())
+    }
+}
+
+
 #[derive(Deserialize)]
 pub struct GetServerSubmitResponse {
 
@@ -692,4 +1055,162 @@ pub struct GetServerSubmitResponseSingle {
     pub server_uuid: Uuid,
 
     pub content: String,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const SUBMIT_UUID: &str = "9d7e4a2c-1b3f-4c5d-8e6f-7a8b9c0d1e2f";
+    const PLAYER_UUID: &str = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+
+    fn canonical<C: CanonicalContent>(content: &C) -> String {
+        let mut buf = Vec::new();
+        write_canonical(content, &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn submit(points: f32, comment: &str) -> SubmitContent {
+        SubmitContent {
+            uuid: Uuid::from_str(SUBMIT_UUID).unwrap(),
+            timestamp: 1634000000,
+            player_uuid: Uuid::from_str(PLAYER_UUID).unwrap(),
+            points,
+            comment: String::from(comment),
+        }
+    }
+
+    #[test]
+    fn submit_golden() {
+        assert_eq!(
+            canonical(&submit(-0.3, "griefing")),
+            "uuid: 9d7e4a2c-1b3f-4c5d-8e6f-7a8b9c0d1e2f\n\
+             timestamp: 1634000000\n\
+             player_uuid: 069a79f4-44e9-4726-a5be-fca90e38aaf5\n\
+             points: -0.3\n\
+             comment: griefing\n"
+        );
+    }
+
+    #[test]
+    fn register_recall_unregister_golden() {
+        assert_eq!(canonical(&RegisterContent { server_name: String::from("survival") }), "server_name: survival\n");
+        assert_eq!(
+            canonical(&RecallContent { timestamp: 1634000001, comment: String::from("appealed") }),
+            "timestamp: 1634000001\ncomment: appealed\n"
+        );
+        assert_eq!(
+            canonical(&UnregisterContent { timestamp: 1634000002, comment: String::from("closing") }),
+            "timestamp: 1634000002\ncomment: closing\n"
+        );
+    }
+
+    /// what the client wrote before the canonical form was introduced; single-line values must keep these bytes
+    fn baseline_submit(c: &SubmitContent) -> String {
+        format!(
+            "uuid: {}\ntimestamp: {}\nplayer_uuid: {}\npoints: {}\ncomment: {}\n",
+            c.uuid, c.timestamp, c.player_uuid, c.points, c.comment
+        )
+    }
+
+    #[test]
+    fn same_bytes_as_baseline() {
+        for c in &[submit(-0.3, "griefing"), submit(1.0, ""), submit(0.125, "x-ray: caught twice"), submit(-1.0, "日本語 ok")] {
+            assert_eq!(canonical(c), baseline_submit(c));
+        }
+        assert_eq!(canonical(&RegisterContent { server_name: String::from("survival") }), format!("server_name: {}\n", "survival"));
+        let recall = RecallContent { timestamp: 1634000001, comment: String::from("appealed") };
+        assert_eq!(canonical(&recall), format!("timestamp: {}\ncomment: {}\n", recall.timestamp, recall.comment));
+    }
+
+    #[test]
+    fn multi_line_comment_is_rejected() {
+        for comment in &["first\nsecond", "first\r\nsecond", "first\rsecond", "trailing\n"] {
+            let mut buf = Vec::new();
+            let e = write_canonical(&submit(1.0, comment), &mut buf).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+            assert!(e.to_string().contains("comment"));
+        }
+        let mut buf = Vec::new();
+        assert!(write_canonical(&RecallContent { timestamp: 1, comment: String::from("a\nb") }, &mut buf).is_err());
+    }
+
+    #[test]
+    fn floats_in_shortest_form() {
+        assert!(canonical(&submit(0.1, "")).contains("points: 0.1\n"));
+        assert!(canonical(&submit(-1.0, "")).contains("points: -1\n"));
+        assert!(canonical(&submit(0.125, "")).contains("points: 0.125\n"));
+    }
+
+    #[test]
+    fn submit_round_trip() {
+        let text = canonical(&submit(-0.75, "x-ray: caught twice"));
+        let read = SubmitContent::read_from(text.as_bytes()).unwrap();
+        assert_eq!(read.uuid, Uuid::from_str(SUBMIT_UUID).unwrap());
+        assert_eq!(read.timestamp, 1634000000);
+        assert_eq!(read.player_uuid, Uuid::from_str(PLAYER_UUID).unwrap());
+        assert_eq!(read.points, -0.75);
+        assert_eq!(read.comment, "x-ray: caught twice");
+    }
+
+    struct Optional(Option<u32>);
+
+    canonical_fields!(OptionalFields {
+        0 => a: required,
+        1 => b: optional,
+    });
+
+    impl CanonicalContent for Optional {
+
+        fn write_fields(&self, w: CanonicalWriter<'_>) -> io::Result<Written> {
+            Ok(OptionalFields::new(w).a(1)?.b(self.0)?.finish())
+        }
+    }
+
+    #[test]
+    fn unset_optional_field_is_omitted() {
+        assert_eq!(canonical(&Optional(None)), "a: 1\n");
+        assert_eq!(canonical(&Optional(Some(2))), "a: 1\nb: 2\n");
+    }
+
+    #[test]
+    fn server_info_request_gets_one_server() {
+        let base = Url::parse("https://example.com/api/").unwrap();
+        let req = ServerInfoRequest::new(Uuid::from_str(SUBMIT_UUID).unwrap());
+        assert!(matches!(req.method(), RequestMethod::GET));
+        assert_eq!(req.url(&base).as_str(), format!("https://example.com/api/v1/server/uuid/{}", SUBMIT_UUID));
+        let mut body = Vec::new();
+        req.write_to(&mut body).unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn server_info_response_carries_status_and_name() {
+        let policy = crate::config::build_policy();
+        let (cert, key_id) = pgp::generate_dummy_cert(policy.as_ref()).unwrap();
+        let mut public_key = Vec::new();
+        pgp::export_publickey(&cert, &mut public_key).unwrap();
+        let body = serde_json::json!({
+            "status": "OK",
+            "id": 7,
+            "uuid": SUBMIT_UUID,
+            "server_name": "survival",
+            "key_id": key_id.to_hex(),
+            "public_key": String::from_utf8(public_key).unwrap(),
+        });
+
+        let r: ServerInfoResponse = serde_json::from_value(body).unwrap();
+        assert!(matches!(r.status, Status::OK));
+        assert_eq!(r.server_name, "survival");
+        assert_eq!(r.uuid, Uuid::from_str(SUBMIT_UUID).unwrap());
+        assert_eq!(r.key_id, key_id);
+    }
+
+    #[test]
+    fn staged_writer_lists_fields_in_order() {
+        assert_eq!(SubmitFields::FIELDS, &["uuid", "timestamp", "player_uuid", "points", "comment"]);
+        assert_eq!(RecallFields::FIELDS, &["timestamp", "comment"]);
+        assert_eq!(OptionalFields::FIELDS, &["a", "b"]);
+    }
+}
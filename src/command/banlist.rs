@@ -1,6 +1,13 @@
 use std::fmt;
+use std::io::Write;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::time::SystemTime;
 
+use anyhow::Result as GeneralResult;
 use uuid::Uuid;
 use chrono::NaiveDateTime;
 use serde::Deserialize;
@@ -86,6 +93,239 @@ pub struct BanListItem {
 pub type BanList = Vec<BanListItem>;
 
 
+/**
+ * entry of banned-ips.json; `name` is only present when written by some plugins
+ */
+#[derive(Deserialize)]
+pub struct IpBanListItem {
+
+    pub ip: String,
+
+    #[serde(default)]
+    pub name: Option<String>,
+
+    #[serde(deserialize_with = "deserialize_datetime")]
+    pub created: NaiveDateTime,
+
+    pub source: String,
+
+    pub expires: Expire,
+
+    pub reason: String,
+}
+
+pub enum BanEntry {
+    Player(BanListItem),
+    Ip(IpBanListItem),
+}
+
+/**
+ * load banlist entries one by one, so that an invalid entry does not hide the rest;
+ * entries of banned-players.json and banned-ips.json are both accepted
+ */
+pub fn load_banlist<P: AsRef<Path>>(path: P) -> GeneralResult<Vec<Result<BanEntry, String>>> {
+    let content = fs::read_to_string(path)?;
+    let values: Vec<serde_json::Value> = serde_json::from_str(content.as_str())?;
+    Ok(
+        values.into_iter()
+            .map(|v| {
+                if v.get("uuid").is_none() && v.get("ip").is_some() {
+                    serde_json::from_value(v).map(BanEntry::Ip)
+                } else {
+                    serde_json::from_value(v).map(BanEntry::Player)
+                }
+                .map_err(|e| e.to_string())
+            })
+            .collect()
+    )
+}
+
+
+#[derive(Deserialize)]
+struct UserCacheItem {
+
+    name: String,
+
+    uuid: Uuid,
+}
+
+/**
+ * player name (in lowercase) to uuid, from usercache.json
+ */
+pub struct UserCache(HashMap<String, Uuid>);
+
+impl UserCache {
+
+    pub fn load<P: AsRef<Path>>(path: P) -> GeneralResult<Self> {
+        let content = fs::read_to_string(path)?;
+        let items: Vec<UserCacheItem> = serde_json::from_str(content.as_str())?;
+        Ok(UserCache(items.into_iter().map(|item| (item.name.to_lowercase(), item.uuid)).collect()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Uuid> {
+        self.0.get(&name.to_lowercase())
+    }
+}
+
+
+#[derive(Default)]
+pub struct IpResolveStats {
+    pub resolved: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+impl IpResolveStats {
+
+    pub fn is_empty(&self) -> bool {
+        self.resolved == 0 && self.skipped == 0 && self.failed == 0
+    }
+}
+
+/**
+ * turn an ip entry into a player entry through the usercache; `Ok(None)` means it is skipped
+ */
+pub fn resolve_ip_entry(item: IpBanListItem, usercache: Option<&UserCache>) -> Result<Option<BanListItem>, String> {
+    let (usercache, name) = match (usercache, item.name) {
+        (Some(usercache), Some(name)) => (usercache, name),
+        _ => return Ok(None)
+    };
+    match usercache.get(name.as_str()) {
+        Some(uuid) => Ok(Some(BanListItem {
+            uuid: uuid.clone(),
+            name,
+            created: item.created,
+            source: item.source,
+            expires: item.expires,
+            reason: item.reason,
+        })),
+        None => Err(format!("ip {}: player {} not found in usercache", item.ip, name))
+    }
+}
+
+
+#[derive(Deserialize)]
+struct PlayerListItem {
+
+    uuid: Uuid,
+}
+
+/**
+ * load player uuids from ops.json, whitelist.json, or a plain list with one uuid per line
+ */
+pub fn load_player_list<P: AsRef<Path>>(path: P) -> GeneralResult<HashSet<Uuid>> {
+    let content = fs::read_to_string(path)?;
+    if content.trim_start().starts_with('[') {
+        let items: Vec<PlayerListItem> = serde_json::from_str(content.as_str())?;
+        Ok(items.into_iter().map(|item| item.uuid).collect())
+    } else {
+        let mut players = HashSet::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            players.insert(Uuid::from_str(line)?);
+        }
+        Ok(players)
+    }
+}
+
+
+/**
+ * which time is signed as the content timestamp of an imported record
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimestampPolicy {
+    Created,
+    Now,
+    Clamp(u64),
+}
+
+impl TimestampPolicy {
+
+    pub const EXPECTING: &'static str = "created|now|clamp:<days>";
+
+    pub fn timestamp(&self, created: u64, now: u64) -> u64 {
+        match self {
+            Self::Created => created,
+            Self::Now => now,
+            Self::Clamp(days) => created.max(now.saturating_sub(days.saturating_mul(86400))),
+        }
+    }
+
+    pub fn comment(&self, data: &BanListItem) -> String {
+        match self {
+            Self::Now => format!("{} (banned at {})", data.reason, data.created.format("%Y-%m-%d %H:%M:%S")),
+            _ => data.reason.clone(),
+        }
+    }
+}
+
+impl Default for TimestampPolicy {
+
+    fn default() -> Self {
+        Self::Created
+    }
+}
+
+impl FromStr for TimestampPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created" => Ok(Self::Created),
+            "now" => Ok(Self::Now),
+            _ => {
+                let days = s.strip_prefix("clamp:").ok_or(())?;
+                u64::from_str(days).map(Self::Clamp).map_err(|_| ())
+            }
+        }
+    }
+}
+
+impl fmt::Display for TimestampPolicy {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Created => f.write_str("created"),
+            Self::Now => f.write_str("now"),
+            Self::Clamp(days) => write!(f, "clamp:{}", days),
+        }
+    }
+}
+
+
+/**
+ * progress of an import: `<index> <player_uuid>` of the last successful submit
+ */
+pub struct ImportCheckpoint {
+    pub index: usize,
+    pub player_uuid: Uuid,
+}
+
+impl ImportCheckpoint {
+
+    pub fn load<P: AsRef<Path>>(path: P) -> GeneralResult<Option<Self>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into())
+        };
+        let mut it = content.split_whitespace();
+        let index = it.next().ok_or_else(|| anyhow::anyhow!("empty checkpoint"))?;
+        let player_uuid = it.next().ok_or_else(|| anyhow::anyhow!("checkpoint without player uuid"))?;
+        Ok(Some(ImportCheckpoint { index: usize::from_str(index)?, player_uuid: Uuid::from_str(player_uuid)? }))
+    }
+
+    /// replaced whole, so a crash while saving never leaves a checkpoint that can not be read
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> GeneralResult<()> {
+        crate::config::atomic_write(path, |ofile| writeln!(ofile, "{} {}", self.index, self.player_uuid))?;
+        Ok(())
+    }
+}
+
+
 pub trait GeneratePoints {
 
     fn generate(&self, data: &BanListItem) -> f32;
@@ -99,4 +339,68 @@ impl GeneratePoints for BasicGeneratePoints {
     fn generate(&self, data: &BanListItem) -> f32 {
         -1.0
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const DAY: u64 = 86400;
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn checkpoint_save_replaces_the_file() {
+        let path = std::env::temp_dir().join(format!("openmprdbc-banlist-{}-checkpoint", std::process::id()));
+        fs::write(&path, "a checkpoint of an older, longer run that must not shine through\n").unwrap();
+
+        let checkpoint = ImportCheckpoint { index: 7, player_uuid: Uuid::from_u128(1) };
+        checkpoint.save(&path).unwrap();
+        let loaded = ImportCheckpoint::load(&path).unwrap().unwrap();
+        assert_eq!((loaded.index, loaded.player_uuid), (7, Uuid::from_u128(1)));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "7 00000000-0000-0000-0000-000000000001\n");
+
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        assert!(!Path::new(&tmp).exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn clamp_keeps_recent_times() {
+        let policy = TimestampPolicy::Clamp(30);
+        assert_eq!(policy.timestamp(NOW - DAY, NOW), NOW - DAY);
+        assert_eq!(policy.timestamp(NOW - 30 * DAY, NOW), NOW - 30 * DAY);
+    }
+
+    #[test]
+    fn clamp_raises_old_times() {
+        let policy = TimestampPolicy::Clamp(30);
+        assert_eq!(policy.timestamp(NOW - 31 * DAY, NOW), NOW - 30 * DAY);
+        assert_eq!(policy.timestamp(0, NOW), NOW - 30 * DAY);
+        assert_eq!(TimestampPolicy::Clamp(0).timestamp(NOW - 1, NOW), NOW);
+    }
+
+    #[test]
+    fn clamp_does_not_overflow() {
+        assert_eq!(TimestampPolicy::Clamp(u64::MAX).timestamp(5, NOW), 5);
+        assert_eq!(TimestampPolicy::Clamp(30).timestamp(5, DAY), 5);
+    }
+
+    #[test]
+    fn created_and_now() {
+        assert_eq!(TimestampPolicy::Created.timestamp(5, NOW), 5);
+        assert_eq!(TimestampPolicy::Now.timestamp(5, NOW), NOW);
+    }
+
+    #[test]
+    fn policy_parse_round_trip() {
+        for s in &["created", "now", "clamp:0", "clamp:30"] {
+            assert_eq!(TimestampPolicy::from_str(s).unwrap().to_string(), *s);
+        }
+        assert_eq!(TimestampPolicy::from_str("clamp:30"), Ok(TimestampPolicy::Clamp(30)));
+        for s in &["", "clamp", "clamp:", "clamp:-1", "clamp:1d", "Now"] {
+            assert!(TimestampPolicy::from_str(s).is_err(), "{}", s);
+        }
+    }
+}
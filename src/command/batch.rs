@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Result as GeneralResult;
+use serde::Deserialize;
+use uuid::Uuid;
+
+
+/**
+ * one row of `submit --batch`
+ */
+
+pub struct BatchRow {
+    pub player_uuid: Uuid,
+    pub points: f32,
+    pub comment: String,
+    pub timestamp: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct BatchRowJson {
+    player_uuid: String,
+    points: f32,
+    #[serde(default)]
+    comment: String,
+    #[serde(default)]
+    timestamp: Option<u64>,
+}
+
+fn validate(n: usize, player_uuid: &str, points: f32, comment: String, timestamp: Option<u64>) -> GeneralResult<BatchRow> {
+    let player_uuid = Uuid::from_str(player_uuid.trim())
+        .map_err(|_| anyhow::anyhow!("row {}: invalid player_uuid `{}`", n, player_uuid))?;
+    if !(-1.0..=1.0).contains(&points) {
+        return Err(anyhow::anyhow!("row {}: points out of [-1,1]: {}", n, points))
+    }
+    Ok(BatchRow { player_uuid, points, comment, timestamp })
+}
+
+/**
+ * load & validate all rows from a json array or a csv file with header
+ * `player_uuid,points[,comment][,timestamp]`
+ */
+pub fn load_batch<P: AsRef<Path>>(path: P) -> GeneralResult<Vec<BatchRow>> {
+    let content = fs::read_to_string(path)?;
+    if content.trim_start().starts_with('[') {
+        let rows: Vec<BatchRowJson> = serde_json::from_str(content.as_str())?;
+        rows.into_iter()
+            .enumerate()
+            .map(|(i, r)| validate(i + 1, r.player_uuid.as_str(), r.points, r.comment, r.timestamp))
+            .collect()
+    } else {
+        let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+        let header = split_csv_line(lines.next().ok_or_else(|| anyhow::anyhow!("empty batch file"))?);
+        let column = |name: &str| header.iter().position(|h| h.trim() == name);
+        let i_player_uuid = column("player_uuid").ok_or_else(|| anyhow::anyhow!("missing column: player_uuid"))?;
+        let i_points = column("points").ok_or_else(|| anyhow::anyhow!("missing column: points"))?;
+        let i_comment = column("comment");
+        let i_timestamp = column("timestamp");
+
+        let mut rows = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let n = i + 1;
+            let fields = split_csv_line(line);
+            let get = |i: usize| fields.get(i).map(String::as_str).unwrap_or_default();
+            let points = f32::from_str(get(i_points).trim())
+                .map_err(|_| anyhow::anyhow!("row {}: invalid points `{}`", n, get(i_points)))?;
+            let comment = i_comment.map(get).unwrap_or_default().to_string();
+            let timestamp = match i_timestamp.map(get).map(str::trim) {
+                Some(s) if !s.is_empty() => {
+                    Some(u64::from_str(s).map_err(|_| anyhow::anyhow!("row {}: invalid timestamp `{}`", n, s))?)
+                }
+                _ => None
+            };
+            rows.push(validate(n, get(i_player_uuid), points, comment, timestamp)?);
+        }
+        Ok(rows)
+    }
+}
+
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
@@ -1,10 +1,13 @@
 use std::fmt;
 use std::error;
+use std::io;
 use std::str::FromStr;
 
 use clap::ArgMatches;
+use serde::Serialize;
 
 use crate::api_v1 as api;
+use crate::pgp;
 use super::http::RequsetError;
 
 #[derive(Debug)]
@@ -39,6 +42,14 @@ impl<'a> ArgsError<'a> {
         }
     }
 
+    pub fn missing(name: &'static str, expecting: &'static str) -> Self {
+        ArgsError {
+            name,
+            expecting,
+            value: None
+        }
+    }
+
     pub fn parse<T: FromStr>(arg: &'a str, name: &'static str, expecting: &'static str) -> Result<T, Self> {
         match T::from_str(arg) {
             Ok(v) => Ok(v),
@@ -81,12 +92,17 @@ impl<'a> ArgsError<'a> {
 #[derive(Debug)]
 pub struct ConfigMissing {
     name: &'static str,
+    cause: Option<String>,
 }
 
 impl fmt::Display for ConfigMissing {
 
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_fmt(format_args!("ConfigMissing({})", self.name))
+        if let Some(ref cause) = self.cause {
+            f.write_fmt(format_args!("ConfigMissing({}): can not be loaded: {}", self.name, cause))
+        } else {
+            f.write_fmt(format_args!("ConfigMissing({})", self.name))
+        }
     }
 }
 
@@ -96,11 +112,166 @@ impl error::Error for ConfigMissing {
 
 impl ConfigMissing {
 
+    pub fn new(name: &'static str) -> Self {
+        ConfigMissing { name, cause: None }
+    }
+
+    /// the whole config file is unusable, e.g. unreadable or not valid json
+    pub fn unloadable<E: fmt::Display>(name: &'static str, e: E) -> Self {
+        ConfigMissing { name, cause: Some(e.to_string()) }
+    }
+
     pub fn ok<T>(v: Option<T>, name: &'static str) -> Result<T, Self> {
-        v.ok_or_else(|| ConfigMissing { name } )
+        v.ok_or_else(|| ConfigMissing::new(name))
+    }
+}
+
+
+/**
+ * error catalog; codes are stable, a new kind of failure gets a new code
+ */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    InvalidArgument,
+    MissingConfig,
+    ErrorResponse,
+    Other,
+    NotFound,
+    Unavailable,
+    Transport,
+    BadResponse,
+    Unverified,
+    Io,
+}
+
+impl ErrorCode {
+
+    pub const ALL: [ErrorCode; 10] = [
+        ErrorCode::InvalidArgument,
+        ErrorCode::MissingConfig,
+        ErrorCode::ErrorResponse,
+        ErrorCode::Other,
+        ErrorCode::NotFound,
+        ErrorCode::Unavailable,
+        ErrorCode::Transport,
+        ErrorCode::BadResponse,
+        ErrorCode::Unverified,
+        ErrorCode::Io,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::InvalidArgument => "E001",
+            Self::MissingConfig => "E002",
+            Self::ErrorResponse => "E003",
+            Self::Other => "E004",
+            Self::NotFound => "E005",
+            Self::Unavailable => "E006",
+            Self::Transport => "E007",
+            Self::BadResponse => "E008",
+            Self::Unverified => "E009",
+            Self::Io => "E010",
+        }
+    }
+
+    pub fn summary(self) -> &'static str {
+        match self {
+            Self::InvalidArgument => "an argument is missing or can not be parsed",
+            Self::MissingConfig => "a required field of the client config is not set, or the config can not be loaded",
+            Self::ErrorResponse => "the remote OpenMPRDB rejected the request",
+            Self::Other => "unexpected failure, e.g. a refused operation or a pgp error",
+            Self::NotFound => "the remote OpenMPRDB does not know the server, submit or endpoint",
+            Self::Unavailable => "the remote OpenMPRDB is rate limiting or failing; the request may succeed later",
+            Self::Transport => "the remote OpenMPRDB can not be reached: dns, connection, tls or timeout",
+            Self::BadResponse => "the remote OpenMPRDB answered with a body that can not be parsed",
+            Self::Unverified => "a signed content can not be verified with the added servers' certs",
+            Self::Io => "a local file can not be read or written",
+        }
+    }
+
+    /// whether a command of the scope can fail with this code
+    pub fn affects(self, scope: CommandScope) -> bool {
+        match self {
+            Self::InvalidArgument | Self::Other | Self::Io => true,
+            Self::MissingConfig => scope != CommandScope::Local,
+            Self::ErrorResponse
+            | Self::NotFound
+            | Self::Unavailable
+            | Self::Transport
+            | Self::BadResponse
+            | Self::Unverified => scope == CommandScope::Remote,
+        }
+    }
+
+    fn of_response(r: &api::ErrorResponse) -> Self {
+        match r.code {
+            404 => Self::NotFound,
+            429 | 500..=599 => Self::Unavailable,
+            _ => Self::ErrorResponse,
+        }
+    }
+
+    fn of_other(e: &anyhow::Error) -> Self {
+        if e.downcast_ref::<ureq::Transport>().is_some() {
+            Self::Transport
+        } else if e.downcast_ref::<serde_json::Error>().is_some() {
+            Self::BadResponse
+        } else if e.downcast_ref::<pgp::VerifyError>().is_some() {
+            Self::Unverified
+        } else if e.downcast_ref::<io::Error>().is_some() {
+            Self::Io
+        } else {
+            Self::Other
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
     }
 }
 
+/**
+ * what a subcommand touches: nothing but its arguments, the client config, or also the remote OpenMPRDB
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandScope {
+    Local,
+    Config,
+    Remote,
+}
+
+/// `commands` are the subcommands of the cli with their scope, so the affected commands follow the cli
+pub fn write_catalog<W: io::Write>(mut w: W, json: bool, commands: &[(&str, CommandScope)]) -> io::Result<()> {
+
+    #[derive(Serialize)]
+    struct CatalogEntry<'a> {
+        code: &'static str,
+        summary: &'static str,
+        commands: Vec<&'a str>,
+    }
+
+    let affected = |c: ErrorCode| -> Vec<&str> {
+        commands.iter().filter(|(_, scope)| c.affects(*scope)).map(|(name, _)| *name).collect()
+    };
+
+    if json {
+        let entries: Vec<_> = ErrorCode::ALL.iter()
+            .map(|c| CatalogEntry { code: c.code(), summary: c.summary(), commands: affected(*c) })
+            .collect();
+        serde_json::to_writer_pretty(&mut w, &entries)?;
+        writeln!(w)?;
+    } else {
+        for c in ErrorCode::ALL.iter() {
+            writeln!(w, "{}  {}\n      commands: {}", c.code(), c.summary(), affected(*c).join(", "))?;
+        }
+    }
+    Ok(())
+}
+
 
 pub enum AppError<'a> {
     Args(ArgsError<'a>),
@@ -111,6 +282,15 @@ pub enum AppError<'a> {
 
 impl<'a> AppError<'a> {
 
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Args(_) => ErrorCode::InvalidArgument,
+            Self::Config(_) => ErrorCode::MissingConfig,
+            Self::Response(r) => ErrorCode::of_response(r),
+            Self::Other(e) => ErrorCode::of_other(e),
+        }
+    }
+
     pub fn new_other<E: error::Error + Send + Sync + 'static>(e: E) -> Self {
         Self::Other(anyhow::Error::from(e))
     }
@@ -147,4 +327,75 @@ impl<'a> From<RequsetError> for AppError<'a> {
             RequsetError::Transport(e) => Self::Other(e),
         }
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn response(code: u16) -> api::ErrorResponse {
+        api::ErrorResponse { status: api::Status::NG, reason: String::new(), code }
+    }
+
+    #[test]
+    fn catalog_is_documented() {
+        let mut codes = HashSet::new();
+        for c in ErrorCode::ALL.iter() {
+            let code = c.code();
+            assert!(code.len() == 4 && code.starts_with('E') && code[1..].chars().all(|c| c.is_ascii_digit()), "{}", code);
+            assert!(codes.insert(code), "{} is used twice", code);
+            assert!(!c.summary().is_empty(), "{} has no summary", code);
+        }
+    }
+
+    #[test]
+    fn catalog_is_reachable() {
+        let transport = match ureq::get("not a url").call() {
+            Err(ureq::Error::Transport(t)) => t,
+            _ => panic!("expecting a transport error"),
+        };
+        let errors: Vec<AppError> = vec![
+            ArgsError::missing("limit", "unsigned integer").into(),
+            ConfigMissing::new("client.api_url").into(),
+            AppError::Response(response(400)),
+            AppError::Response(response(404)),
+            AppError::Response(response(429)),
+            AppError::Response(response(503)),
+            anyhow::anyhow!("player is protected").into(),
+            AppError::new_other(transport),
+            AppError::new_other(serde_json::from_str::<u32>("{").unwrap_err()),
+            AppError::new_other(pgp::VerifyError::new(pgp::UnverifiedReason::BadSignature, anyhow::anyhow!("bad"))),
+            AppError::new_other(io::Error::new(io::ErrorKind::NotFound, "record file")),
+        ];
+        let reached: HashSet<_> = errors.iter().map(|e| e.code()).map(ErrorCode::code).collect();
+        let catalog: HashSet<_> = ErrorCode::ALL.iter().map(|c| c.code()).collect();
+        assert_eq!(reached, catalog);
+    }
+
+    #[test]
+    fn catalog_lists_affected_commands() {
+        let commands = [("examples", CommandScope::Local), ("debug", CommandScope::Config), ("submit", CommandScope::Remote)];
+        let mut text = Vec::new();
+        write_catalog(&mut text, false, &commands).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("E001  an argument is missing or can not be parsed\n      commands: examples, debug, submit\n"));
+        assert!(text.contains("\n      commands: debug, submit\n"));
+        assert!(text.contains("E005  the remote OpenMPRDB does not know the server, submit or endpoint\n      commands: submit\n"));
+
+        let mut json = Vec::new();
+        write_catalog(&mut json, true, &commands).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json[1]["code"], "E002");
+        assert_eq!(json[1]["commands"], serde_json::json!(["debug", "submit"]));
+    }
+
+    #[test]
+    fn context_keeps_the_code() {
+        let e = anyhow::Error::from(io::Error::new(io::ErrorKind::PermissionDenied, "config")).context("saving config");
+        assert_eq!(AppError::from(e).code(), ErrorCode::Io);
+    }
+}
@@ -0,0 +1,129 @@
+use std::io;
+
+/**
+ * a realistic invocation shown in `--help`; `args` start with the subcommand name
+ */
+pub struct Example {
+    pub description: &'static str,
+    pub args: &'static [&'static str],
+}
+
+const SERVER_UUID: &str = "3f2b4a9e-7c1d-4e8a-9b6f-0d5c2e1a8b74";
+const PLAYER_UUID: &str = "8d4e1c7a-2b9f-4a3e-b6d5-1f0c9e8a7b62";
+const RECORD_UUID: &str = "c5a1e8f4-6d3b-4f2a-8e9c-7b0d1a2f3e45";
+const KEY_ID: &str = "0123456789ABCDEF";
+
+pub const EXAMPLES: &[Example] = &[
+    Example { description: "use the generated certification and its signing subkey", args: &["config", "--cert-file", "server.pgp", "--key-id", KEY_ID] },
+    Example { description: "set the api url of OpenMPRDB", args: &["config", "--api-url", "https://openmprdb.example.com/api/"] },
+    Example { description: "print the full configuration", args: &["config"] },
+    Example { description: "check the configuration, including the key registered in OpenMPRDB", args: &["config", "--validate", "--online"] },
+    Example { description: "generate a certification protected by a password", args: &["generate", "--user-id", "Server <admin@example.com>", "--with-password", "-o", "server.pgp"] },
+    Example { description: "generate a certification valid for one year", args: &["generate", "--user-id", "Server <admin@example.com>", "--expires", "1y", "-o", "server.pgp"] },
+    Example { description: "list keys in the configured certification", args: &["keyring"] },
+    Example { description: "list keys in another certification file", args: &["keyring", "--cert-file", "other.pgp"] },
+    Example { description: "add a passphrase to the keys of the configured certification", args: &["keyring", "--protect"] },
+    Example { description: "list only the keys that can be selected with --key-id", args: &["keyring", "--signing-only"] },
+    Example { description: "register this server", args: &["register", "--server-name", "my-server"] },
+    Example { description: "show the signed registration without sending it", args: &["register", "--server-name", "my-server", "--dry-run"] },
+    Example { description: "unregister this server", args: &["unregister", "--comment", "server closed"] },
+    Example { description: "switch to a new signing subkey of the same certification", args: &["rotate-key", "--key-id", "FEDCBA9876543210"] },
+    Example { description: "submit a record of a player", args: &["submit", "-p", PLAYER_UUID, "-s", "-0.5", "--comment", "griefing"] },
+    Example { description: "submit a record of a player known by name, looked up in the usercache", args: &["submit", "--player-name", "Steve", "--usercache", "usercache.json", "-s", "-0.5"] },
+    Example { description: "submit the same record for players caught together", args: &["submit", "-p", PLAYER_UUID, "-p", "1a2b3c4d-5e6f-4a8b-9c0d-e1f2a3b4c5d6", "-s", "-1", "--comment", "xray"] },
+    Example { description: "submit a ban that happened earlier", args: &["submit", "-p", PLAYER_UUID, "-s", "-0.5", "--timestamp", "2021-08-23 16:29:12"] },
+    Example { description: "submit every row of a csv file, one per second", args: &["submit", "--batch", "bans.csv", "--interval", "1000"] },
+    Example { description: "recall a record by its uuid", args: &["recall", "-r", RECORD_UUID] },
+    Example { description: "recall the record of a player", args: &["recall", "-p", PLAYER_UUID, "--comment", "unbanned"] },
+    Example { description: "recall every record listed in a file, one per second", args: &["recall", "--from-file", "wrong-points.txt", "--interval", "1000", "--comment", "wrong points"] },
+    Example { description: "add the public key of another server, pasted from console", args: &["cert", "--add", "--server-uuid", SERVER_UUID, "--name", "peer", "--key-id", KEY_ID, "--trust", "3"] },
+    Example { description: "add the public key of another server from a file", args: &["cert", "--add", "--server-uuid", SERVER_UUID, "--name", "peer", "--key-id", KEY_ID, "--trust", "3", "--file", "peer.asc"] },
+    Example { description: "add the key another server registered, after checking its fingerprint", args: &["cert", "--add", "--fetch", "--server-uuid", SERVER_UUID, "--trust", "3"] },
+    Example { description: "add every server listed in OpenMPRDB at trust level 1, asking for each", args: &["cert", "--import-from-api", "--trust", "1"] },
+    Example { description: "remove an added server by its local name", args: &["cert", "--remove", "--name", "peer"] },
+    Example { description: "list added servers in json", args: &["cert", "--list", "--json"] },
+    Example { description: "list only servers trusted at level 4 or above", args: &["cert", "--list", "--min-trust", "4"] },
+    Example { description: "export all added public keys", args: &["cert", "--export", "-o", "servers.asc"] },
+    Example { description: "list servers registered in OpenMPRDB", args: &["server", "--limit", "10"] },
+    Example { description: "list servers in json", args: &["server", "--format", "json"] },
+    Example { description: "find servers by part of their name, sorted by name", args: &["server", "--filter", "survival", "--sort", "name"] },
+    Example { description: "check that this server is still registered", args: &["server", "--my"] },
+    Example { description: "show one server and whether its key matches the local cert", args: &["server", "--uuid", SERVER_UUID] },
+    Example { description: "show verified submits of another server, 20 per request", args: &["record", "--server-uuid", SERVER_UUID, "--limit", "20"] },
+    Example { description: "show at most 500 submits of another server", args: &["record", "--server-uuid", SERVER_UUID, "--limit", "100", "--max", "500"] },
+    Example { description: "save verified submits of another server as json", args: &["record", "--server-uuid", SERVER_UUID, "-o", "submits.json"] },
+    Example { description: "show submits of another server about one player", args: &["record", "--server-uuid", SERVER_UUID, "--player", PLAYER_UUID] },
+    Example { description: "show what every server in the registry says about one player", args: &["record", "--all-servers", "--player", PLAYER_UUID] },
+    Example { description: "merge submits of all added servers into a table", args: &["record", "--auto", "-o", "table.csv"] },
+    Example { description: "merge submits of servers trusted at level 3 or above", args: &["record", "--auto", "--min-trust", "3", "-o", "table.csv"] },
+    Example { description: "verify a saved submit content without network access", args: &["record", "--verify-file", "content.asc"] },
+    Example { description: "count active records submitted by this server", args: &["records", "--count"] },
+    Example { description: "list records of a player, including recalled ones", args: &["records", "-p", PLAYER_UUID, "--include-recalled"] },
+    Example { description: "list active records of a player as csv", args: &["records", "--player-uuid", PLAYER_UUID, "--format", "csv"] },
+    Example { description: "drop recalled submits from the record file", args: &["records", "compact"] },
+    Example { description: "export active records to a csv file", args: &["records", "export", "-o", "records.csv"] },
+    Example { description: "show what submit sends, signed with a throwaway key", args: &["debug", "request", "submit", "--player-uuid", PLAYER_UUID, "--points", "-0.5"] },
+    Example { description: "check configuration, registration and the connection to OpenMPRDB", args: &["status"] },
+    Example { description: "check that the configured registry answers", args: &["ping"] },
+    Example { description: "check another registry before switching to it", args: &["ping", "--api-url", "https://openmprdb.example.com/api/"] },
+    Example { description: "print build details in json", args: &["version", "--format", "json"] },
+    Example { description: "preview an import without submitting", args: &["import", "banned-players.json", "--dry-run"] },
+    Example { description: "import a banlist, one request per second", args: &["import", "banned-players.json", "--interval", "1000"] },
+];
+
+/**
+ * steps from a new certification to the first record, printed by `examples`
+ */
+pub const WALKTHROUGH: &[Example] = &[
+    Example { description: "generate a certification with a signing subkey", args: &["generate", "--user-id", "Server <admin@example.com>", "-o", "server.pgp"] },
+    Example { description: "find the key id of the signing subkey", args: &["keyring", "--cert-file", "server.pgp"] },
+    Example { description: "use the certification and the signing subkey", args: &["config", "--cert-file", "server.pgp", "--key-id", KEY_ID] },
+    Example { description: "set the api url of OpenMPRDB", args: &["config", "--api-url", "https://openmprdb.example.com/api/"] },
+    Example { description: "register this server; server_uuid is saved to config", args: &["register", "--server-name", "my-server"] },
+    Example { description: "submit a record of a player", args: &["submit", "-p", PLAYER_UUID, "-s", "-0.5", "--comment", "griefing"] },
+    Example { description: "trust another server", args: &["cert", "--add", "--server-uuid", SERVER_UUID, "--name", "peer", "--key-id", KEY_ID, "--trust", "3"] },
+    Example { description: "fetch and verify its records", args: &["record", "--server-uuid", SERVER_UUID] },
+];
+
+
+impl Example {
+
+    pub fn command_line(&self) -> String {
+        let mut line = String::from(env!("CARGO_PKG_NAME"));
+        for arg in self.args {
+            line.push(' ');
+            if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || "<>\"'$&|;".contains(c)) {
+                line.push('"');
+                line.push_str(arg.replace('"', "\\\"").as_str());
+                line.push('"');
+            } else {
+                line.push_str(arg);
+            }
+        }
+        line
+    }
+}
+
+/**
+ * `after_help` text of the subcommand; clap keeps borrowed help strings for the whole run
+ */
+pub fn after_help(subcommand: &str) -> &'static str {
+    after_help_with(subcommand, "")
+}
+
+pub fn after_help_with(subcommand: &str, prefix: &str) -> &'static str {
+    let mut text = String::from(prefix);
+    text.push_str("EXAMPLES:");
+    for example in EXAMPLES.iter().filter(|e| e.args.first() == Some(&subcommand)) {
+        text.push_str(format!("\n    # {}\n    {}\n", example.description, example.command_line()).as_str());
+    }
+    Box::leak(text.into_boxed_str())
+}
+
+pub fn write_walkthrough<W: io::Write>(mut w: W) -> io::Result<()> {
+    for (i, step) in WALKTHROUGH.iter().enumerate() {
+        writeln!(w, "# {}. {}", i + 1, step.description)?;
+        writeln!(w, "{}\n", step.command_line())?;
+    }
+    Ok(())
+}
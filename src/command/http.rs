@@ -1,5 +1,12 @@
+use std::fmt;
+use std::io;
+use std::io::Write as _;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::path::Path;
 use std::error;
 use std::time::Duration;
+use std::thread;
 use std::env;
 use std::borrow::Borrow;
 
@@ -12,7 +19,6 @@ use url::Url;
 use crate::api_v1 as api;
 use api::WriteTo;
 use api::RequestInfo;
-use api::RequestMethod;
 
 
 pub enum RequsetError {
@@ -24,6 +30,26 @@ impl RequsetError {
     fn as_transport_error<E: error::Error + Send + Sync + 'static>(e: E) -> Self {
         Self::Transport(e.into())
     }
+
+    /**
+     * whether the same request may succeed later: transport failures, 429 and 5xx
+     */
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Transport(_) => true,
+            Self::Response(r) => r.code == 429 || (500..600).contains(&r.code),
+        }
+    }
+}
+
+impl fmt::Display for RequsetError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(e) => e.fmt(f),
+            Self::Response(r) => r.fmt(f),
+        }
+    }
 }
 
 impl From<anyhow::Error> for RequsetError {
@@ -39,13 +65,47 @@ impl From<anyhow::Error> for RequsetError {
 
 
 
-pub struct Client {
-    agent: Agent,
+pub struct ClientBuilder {
+    max_retries: u32,
+    retry_delay: Duration,
+    log_file: Option<File>,
+    user_agent: Option<String>,
 }
 
-impl Client {
+impl ClientBuilder {
+
+    pub fn new() -> Self {
+        ClientBuilder {
+            max_retries: 0,
+            retry_delay: Duration::from_millis(1000),
+            log_file: None,
+            user_agent: None,
+        }
+    }
 
-    pub fn new() -> GeneralResult<Self> {
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// append requests and responses to the file; it is opened here so a bad path fails before any request
+    pub fn log_file(mut self, path: &Path) -> io::Result<Self> {
+        self.log_file = Some(OpenOptions::new().append(true).create(true).open(path)?);
+        Ok(self)
+    }
+
+    /// replaces `openmprdb-client-cli/<version>`
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(String::from(user_agent));
+        self
+    }
+
+    pub fn build(&self) -> GeneralResult<Client> {
         use ureq::AgentBuilder;
         use ureq::Error;
         use ureq::Proxy;
@@ -71,13 +131,93 @@ impl Client {
             }
         }
         let agent = ab.build();
+        let log_file = match self.log_file {
+            Some(ref file) => Some(file.try_clone()?),
+            None => None,
+        };
     
         Ok(
             Client {
-                agent
+                agent,
+                max_retries: self.max_retries,
+                retry_delay: self.retry_delay,
+                log_file,
+                user_agent: self.user_agent.clone().unwrap_or_else(|| format!("openmprdb-client-cli/{}", env!("CARGO_PKG_VERSION"))),
             }
         )
     }
+}
+
+
+/**
+ * print what `Client::request` would send: method, url, headers and body
+ */
+pub fn describe<I>(api_url: &Url, req: &I, out: &mut dyn io::Write) -> GeneralResult<()>
+where 
+    I: WriteTo<Error = anyhow::Error> + RequestInfo
+{
+    writeln!(out, "{} {}", req.method().as_str(), req.url(api_url))?;
+    if let Some(buf) = render_body(req)? {
+        writeln!(out, "Content-Type: {}", req.content_type())?;
+        writeln!(out, "Content-Length: {}", buf.len())?;
+        writeln!(out)?;
+        out.write_all(buf.as_slice())?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/**
+ * the body `Client::request` would send, signed as it would be; `None` for requests without a body
+ */
+pub fn render_body<I>(req: &I) -> GeneralResult<Option<Vec<u8>>>
+where 
+    I: WriteTo<Error = anyhow::Error> + RequestInfo
+{
+    if req.content_type().is_empty() {
+        return Ok(None)
+    }
+    let mut buf = Vec::new();
+    req.write_to(&mut buf)?;
+    Ok(Some(buf))
+}
+
+
+pub struct Client {
+    agent: Agent,
+    max_retries: u32,
+    retry_delay: Duration,
+    log_file: Option<File>,
+    user_agent: String,
+}
+
+/// bodies longer than this are cut in the `--http-log` file
+const LOG_BODY_LIMIT: usize = 4 * 1024;
+
+impl Client {
+
+    /// a failed write to the log is reported but never fails the request
+    fn log(&self, head: fmt::Arguments, body: Option<&[u8]>, end: &str) {
+        if let Some(ref file) = self.log_file {
+            let mut w = file;
+            let mut write = || -> io::Result<()> {
+                writeln!(w, "[{}] {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), head)?;
+                if let Some(body) = body {
+                    if body.len() > LOG_BODY_LIMIT {
+                        w.write_all(&body[..LOG_BODY_LIMIT])?;
+                        writeln!(w, "\n... ({} bytes)", body.len())?;
+                    } else {
+                        w.write_all(body)?;
+                        writeln!(w)?;
+                    }
+                }
+                w.write_all(end.as_bytes())
+            };
+            if let Err(e) = write() {
+                log_info!("warning: can not write http log: {}", e);
+            }
+        }
+    }
 
     pub fn request<I, O>(&self, api_url: &Url, req: I) -> Result<O, RequsetError> 
     where 
@@ -85,48 +225,71 @@ impl Client {
         O: DeserializeOwned
     {
         
-        let method = match req.method() {
-            RequestMethod::GET => "GET",
-            RequestMethod::PUT => "PUT",
-            RequestMethod::POST => "POST",
-            RequestMethod::DELETE => "DELETE",
-            RequestMethod::PATCH => "PATCH",
-        };
+        let method = req.method().as_str();
         let url = req.url(api_url);
 
-        let request = self.agent.request_url(method, url.borrow());
-
-        let response = if !req.content_type().is_empty() {
+        let body = if !req.content_type().is_empty() {
 
             let mut buf = Vec::with_capacity(256 * 1024);
             req.write_to(&mut buf)?;
 
-            #[cfg(debug_assertions)]
-            {
-                println!("{} {} ({})\n\n{}", &method, &url, req.content_type(), String::from_utf8_lossy(buf.as_slice()));
-            }
+            log_debug!("{} {} ({})", &method, &url, req.content_type());
+            log_trace!("{}\n", String::from_utf8_lossy(buf.as_slice()));
 
-            request.set("Content-Type", req.content_type())
-                    .send(buf.as_slice())
+            Some(buf)
         } else {
 
-            #[cfg(debug_assertions)]
-            {
-                println!("{} {} ({})\n\n", &method, &url, req.content_type());
-            }
+            log_debug!("{} {}", &method, &url);
 
-            request.call()
-        }; 
+            None
+        };
+        self.log(format_args!("REQUEST {} {}", &method, &url), body.as_deref(), "");
+
+        let mut attempt = 0;
+        let response = loop {
+            let request = self.agent.request_url(method, url.borrow()).set("User-Agent", self.user_agent.as_str());
+            let response = match body {
+                Some(ref buf) => request.set("Content-Type", req.content_type()).send(buf.as_slice()),
+                None => request.call(),
+            };
+            // only transport failures are retried; a status means the server did handle the request
+            match response {
+                Err(ureq::Error::Transport(transport)) => {
+                    let e = RequsetError::as_transport_error(transport);
+                    if attempt < self.max_retries && e.is_transient() {
+                        attempt += 1;
+                        log_info!("{}; retry {}/{} in {}ms", &e, attempt, self.max_retries, self.retry_delay.as_millis());
+                        thread::sleep(self.retry_delay);
+                        continue;
+                    }
+                    self.log(format_args!("RESPONSE error: {}", &e), None, "---\n");
+                    return Err(e)
+                }
+                response => break response,
+            }
+        };
             
         match response {
             Ok(response) => {
-                let rdr = response.into_reader();
-                Ok(serde_json::from_reader(rdr).map_err(RequsetError::as_transport_error)?)
+                log_debug!("<- {} {}", response.status(), response.status_text());
+                if crate::logger::enabled(crate::logger::Level::Trace) || self.log_file.is_some() {
+                    let status = response.status();
+                    let s = response.into_string().map_err(RequsetError::as_transport_error)?;
+                    log_trace!("{}\n", &s);
+                    self.log(format_args!("RESPONSE {}", status), Some(s.as_bytes()), "---\n");
+                    Ok(serde_json::from_str(s.as_str()).map_err(RequsetError::as_transport_error)?)
+                } else {
+                    let rdr = response.into_reader();
+                    Ok(serde_json::from_reader(rdr).map_err(RequsetError::as_transport_error)?)
+                }
             },
             Err(e) => {
                 match e {
                     ureq::Error::Status(code, response) => {
                         let s = response.into_string().map_err(RequsetError::as_transport_error)?;
+                        log_debug!("<- {}", code);
+                        log_trace!("{}\n", &s);
+                        self.log(format_args!("RESPONSE {}", code), Some(s.as_bytes()), "---\n");
                         let deserialized: Result<api::ErrorResponse, _> = serde_json::from_str(s.as_str());
                         let resp = match deserialized {
                             Ok(mut resp) => { resp.code = code; resp },
@@ -0,0 +1,78 @@
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+
+/**
+ * build & runtime environment, shared by `version` and bug reports
+ */
+#[derive(Serialize)]
+pub struct BuildInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    pub sequoia_openpgp: &'static str,
+    pub crypto_backend: &'static str,
+    pub tls_backend: &'static str,
+    pub data_dir: Option<PathBuf>,
+    pub config_file: Option<PathBuf>,
+}
+
+impl BuildInfo {
+
+    pub fn collect(base_dir: &Path) -> Self {
+        BuildInfo {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: option_env!("OPENMPRDBC_GIT_COMMIT").unwrap_or("unknown"),
+            sequoia_openpgp: option_env!("OPENMPRDBC_SEQUOIA_VERSION").unwrap_or("unknown"),
+            crypto_backend: crypto_backend(),
+            tls_backend: "rustls",
+            data_dir: Some(base_dir.to_path_buf()),
+            config_file: Some(base_dir.join("config")),
+        }
+    }
+}
+
+fn crypto_backend() -> &'static str {
+    if cfg!(feature = "cng") {
+        "cng"
+    } else if cfg!(feature = "nettle") {
+        "nettle"
+    } else {
+        "default"
+    }
+}
+
+impl fmt::Display for BuildInfo {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = |p: &Option<PathBuf>| p.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| String::from("<unknown>"));
+        writeln!(f, "{} {}", self.name, self.version)?;
+        writeln!(f, "git_commit = {}", self.git_commit)?;
+        writeln!(f, "sequoia_openpgp = {} ({})", self.sequoia_openpgp, self.crypto_backend)?;
+        writeln!(f, "tls_backend = {}", self.tls_backend)?;
+        writeln!(f, "data_dir = {}", path(&self.data_dir))?;
+        write!(f, "config_file = {}", path(&self.config_file))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn version_json_keys() {
+        let info = BuildInfo::collect(Path::new("/data"));
+        let value = serde_json::to_value(&info).unwrap();
+        let keys: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+        let mut expected = vec!["name", "version", "git_commit", "sequoia_openpgp", "crypto_backend", "tls_backend", "data_dir", "config_file"];
+        expected.sort();
+        assert_eq!(keys, expected);
+        assert_eq!(value["config_file"], serde_json::json!(Path::new("/data").join("config")));
+    }
+}
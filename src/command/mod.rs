@@ -1,30 +1,64 @@
 pub mod error;
 pub mod http;
 pub mod banlist;
+pub mod batch;
+pub mod examples;
+pub mod info;
+pub mod mojang;
+pub mod namecache;
+pub mod observer;
+pub mod output;
+pub mod registry;
+pub mod sync;
 
 use std::fmt;
+use std::str::FromStr as _;
+use std::fs;
 use std::fs::File;
 use std::io;
+use std::io::Write as _;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
 use std::time::Duration;
 use std::time::SystemTime;
 use std::time::SystemTimeError;
 use std::thread;
 use std::rc::Rc;
+use std::cell::RefCell;
 use std::borrow::Borrow as _;
 
 use anyhow::Result as GeneralResult;
+use clap::ArgMatches;
 use uuid::Uuid;
+use url::Url;
 use sequoia_openpgp::KeyID;
 use sequoia_openpgp::Cert;
 use sequoia_openpgp::policy::Policy;
+use sequoia_openpgp::Fingerprint;
+use sequoia_openpgp::KeyHandle;
 use sequoia_openpgp::crypto::KeyPair;
+use sequoia_openpgp::crypto::Password;
 
 use crate::pgp;
 use crate::api_v1 as api;
 use crate::config::client::ClientConfig;
+use crate::config::client::ClientField;
 use crate::config::servers::ServersConfig;
 use crate::config::servers::ServerData;
 use crate::config::records::RecordConfig;
+use crate::config::records::RecordHeader;
+use output::OutputFormat;
+use output::ServerDataJson;
+use output::SubmitRecordJson;
+use output::CertEntryJson;
+use output::StatusJson;
+use observer::Observer;
+use observer::Item;
+use observer::ItemKind;
+use observer::Outcome;
 
 
 fn timestamp(now: SystemTime) -> Result<u64, SystemTimeError> {
@@ -32,10 +66,35 @@ fn timestamp(now: SystemTime) -> Result<u64, SystemTimeError> {
 }
 
 
+/**
+ * keeps the last decrypted key pair; requests borrow it and clone it for each signature,
+ * so one decryption serves every submit of an import
+ */
 pub struct SigningKeyPairGenerator<'a> {
     password: &'a dyn pgp::PasswordProvider,
     policy: &'a dyn Policy,
-    cache: Option<(KeyID, KeyPair)>,
+    cache: KeyCache<KeyPair>,
+    cache_password: Option<Password>,
+}
+
+/// a value kept for one key of one cert; a reloaded cert may carry the same key id with other key material
+struct KeyCache<V>(Option<(KeyID, Fingerprint, V)>);
+
+impl<V> KeyCache<V> {
+
+    fn get_or_try_insert_with<F>(&mut self, key_id: &KeyID, fingerprint: Fingerprint, f: F) -> GeneralResult<&V>
+    where
+        F: FnOnce() -> GeneralResult<V>,
+    {
+        let hit = match &self.0 {
+            Some((cached_key_id, cached_fingerprint, _)) => cached_key_id == key_id && cached_fingerprint == &fingerprint,
+            None => false,
+        };
+        if !hit {
+            self.0 = Some((key_id.clone(), fingerprint, f()?));
+        }
+        Ok(&self.0.as_ref().unwrap().2)
+    }
 }
 
 impl<'a> SigningKeyPairGenerator<'a> {
@@ -44,37 +103,230 @@ impl<'a> SigningKeyPairGenerator<'a> {
         SigningKeyPairGenerator {
             password,
             policy,
-            cache: None
+            cache: KeyCache(None),
+            cache_password: None,
         }
     }
 
     pub fn generate(&mut self, cert: &Cert, key_id: &KeyID, timestamp: Option<SystemTime>) -> GeneralResult<&KeyPair> {
         
-        if let Some((cached_key_id, cached_keypair)) = &self.cache {
-            if cached_key_id == key_id {
-                
-            } else {
-                let keypair = pgp::get_signing_key(cert, self.policy, timestamp, key_id, self.password)?;
-                self.cache = Some((key_id.clone(), keypair));
+        let (password, policy, cache_password) = (self.password, self.policy, &mut self.cache_password);
+        self.cache.get_or_try_insert_with(key_id, cert.fingerprint(), || Self::decrypt(password, policy, cache_password, cert, key_id, timestamp))
+    }
+
+    /// try the password entered before first, so regenerating the pair does not prompt again
+    fn decrypt(
+        password: &dyn pgp::PasswordProvider,
+        policy: &dyn Policy,
+        cache_password: &mut Option<Password>,
+        cert: &Cert,
+        key_id: &KeyID,
+        timestamp: Option<SystemTime>,
+    ) -> GeneralResult<KeyPair> {
+        if let Some(password) = cache_password.as_ref() {
+            if let Ok(keypair) = pgp::get_signing_key(cert, policy, timestamp, key_id, &CachedPassword(password)) {
+                return Ok(keypair);
             }
-        } else {
-            let keypair = pgp::get_signing_key(cert, self.policy, timestamp, key_id, self.password)?;
-            self.cache = Some((key_id.clone(), keypair));
         }
-        
-        Ok(&self.cache.as_ref().unwrap().1)
+        let recorder = RecordPassword {
+            inner: password,
+            provided: RefCell::new(None),
+        };
+        let keypair = pgp::get_signing_key(cert, policy, timestamp, key_id, &recorder)?;
+        if let Some(password) = recorder.provided.into_inner() {
+            *cache_password = Some(password);
+        }
+        Ok(keypair)
+    }
+}
+
+struct CachedPassword<'a>(&'a Password);
+
+impl<'a> pgp::PasswordProvider for CachedPassword<'a> {
+
+    fn provide(&self, _cert: Fingerprint, _key: Fingerprint) -> io::Result<String> {
+        Ok(self.0.map(|p| String::from_utf8_lossy(p).into_owned()))
+    }
+
+    fn source(&self) -> &'static str {
+        "previous entry"
+    }
+}
+
+struct RecordPassword<'a> {
+    inner: &'a dyn pgp::PasswordProvider,
+    provided: RefCell<Option<Password>>,
+}
+
+impl<'a> pgp::PasswordProvider for RecordPassword<'a> {
+
+    fn provide(&self, cert: Fingerprint, key: Fingerprint) -> io::Result<String> {
+        let password = self.inner.provide(cert, key)?;
+        *self.provided.borrow_mut() = Some(password.as_str().into());
+        Ok(password)
+    }
+
+    fn source(&self) -> &'static str {
+        self.inner.source()
+    }
+}
+
+
+
+pub fn command_generate<'a>(
+    user_id: &'a str,
+    cipher: Option<&'a str>,
+    expires: Option<&'a str>,
+    with_password: bool,
+    output: Option<&'a str>,
+) -> error::AppResult<'a> {
+
+    let mut gen = pgp::GenerateConfig::new(user_id);
+    if let Some(s) = cipher {
+        gen.set_cipher_suite(s).map_err(|_| error::ArgsError::new("cipher", "rsa2048|rsa3072|rsa4096|cv25519", s))?;
     }
+    if let Some(s) = expires {
+        gen.set_expires(s).map_err(|_| error::ArgsError::new("expires", "<n>d|<n>w|<n>m|<n>y", s))?;
+    }
+    gen.set_with_password(with_password)?;
+    if let Some(s) = output {
+        gen.set_output(s).map_err(|e| anyhow::anyhow!("{}: {}", s, e))?;
+    }
+
+    pgp::generate(gen)?;
+
+    if let Some(s) = output {
+        if !s.is_empty() && s != "-" {
+            eprintln!("succeed\n+ cert: {}\n+ revocation: {}.rev", s, s);
+        }
+    }
+
+    Ok(())
+}
+
+
+pub fn command_config_unset<'a>(
+    cfg: &mut ClientConfig,
+    fields: Vec<&'a str>,
+    all: bool,
+) -> error::AppResult<'a> {
+
+    if all {
+        cfg.unset_all();
+        eprintln!("unset config: all");
+        return Ok(())
+    }
+
+    let mut parsed = Vec::with_capacity(fields.len());
+    for s in fields {
+        let field: ClientField = error::ArgsError::parse(s, "unset", ClientField::NAMES)?;
+        parsed.push(field);
+    }
+
+    for field in parsed {
+        if cfg.unset(field) {
+            eprintln!("unset config: {}", field);
+        }
+    }
+
+    Ok(())
+}
+
+
+pub fn command_keyring<'a>(
+    cfg: &ClientConfig,
+    cert_file: Option<&'a str>,
+    key_id: Option<&'a str>,
+    signing_only: bool,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+    
+    let key_id: Option<KeyID> = if let Some(s) = key_id {
+        Some(error::ArgsError::parse(s, "key_id", "hex")?)
+    } else {
+        None
+    };
+
+    let cfg_data = cfg.get_data();
+    let certs = if let Some(s) = cert_file {
+        pgp::load_keyring(s)?
+    } else {
+        let cert_file = error::ConfigMissing::ok(cfg_data.cert_file.as_ref(), "client.cert_file")?;
+        pgp::load_keyring(cert_file)?
+    };
+    let key_id = key_id.as_ref().or(cfg_data.key_id.as_ref());
+
+    let info = pgp::CertsInfo::new(certs.as_slice(), key_id, cfg.policy()).signing_only(signing_only);
+    writeln!(out, "\n{}", info).map_err(error::AppError::new_other)?;
+
+    Ok(())
 }
 
 
+/**
+ * add a passphrase to every unprotected secret key in the cert file, rewriting it in place; prints nothing to stdout
+ */
+pub fn command_keyring_protect<'a>(
+    cfg: &ClientConfig,
+    cert_file: Option<&'a str>,
+) -> error::AppResult<'a> {
+
+    let cfg_data = cfg.get_data();
+    let path = match cert_file {
+        Some(s) => Path::new(s),
+        None => error::ConfigMissing::ok(cfg_data.cert_file.as_ref(), "client.cert_file")?.as_path(),
+    };
+    let cert = pgp::load_cert(path)?;
+    if cert.keys().unencrypted_secret().next().is_none() {
+        log_info!("all secret keys in {} are already protected", path.display());
+        return Ok(())
+    }
+
+    let password = pgp::read_new_password()?;
+    let (cert, n) = pgp::protect_secret_keys(cert, &password)?;
+    crate::config::atomic_write(path, |ofile| {
+        pgp::export_secret_key(&cert, ofile).map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+    })
+    .map_err(error::AppError::new_other)?;
+    log_info!("{} secret key(s) in {} protected", n, path.display());
+
+    Ok(())
+}
+
 
 pub fn command_register<'a>(
     cfg: &mut ClientConfig, 
     httpc: &http::Client,
     kpg: &mut SigningKeyPairGenerator,
     server_name: &'a str,
+    force: bool,
+    unregister_old: bool,
+    previous_key: Option<(&Cert, &KeyID)>,
+    dry_run: bool,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
 ) -> error::AppResult<'a> {
+    cfg.require_cert()?;
     let cfg_data = cfg.get_data();
+
+    // a second registration leaves the first one, and every record submitted under it, behind in the registry
+    let old_uuid = cfg_data.server_uuid;
+    if let (Some(old_uuid), false, false) = (old_uuid, force, dry_run) {
+        return Err(anyhow::anyhow!(
+            "already registered as server {}; registering again creates a second server in the registry and \
+            leaves the old one with its records, no longer managed by this client. Run `unregister` first, or pass \
+            --force (and --unregister-old to unregister the old server afterwards)",
+            old_uuid
+        ).into())
+    }
+    // the old server stays bound to the key it was registered with, which `--cert-file`/`--key-id` may have replaced
+    if let (Some(old_uuid), true, None, false) = (old_uuid, unregister_old, previous_key, dry_run) {
+        return Err(anyhow::anyhow!(
+            "--unregister-old needs the key the old server {} was registered with, but the previous cert_file or key_id \
+            can not be loaded; run `unregister` before changing the key",
+            old_uuid
+        ).into())
+    }
     
     let cert = error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?;
     let key_id = error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?;
@@ -87,9 +339,54 @@ pub fn command_register<'a>(
         &keypair
     );
 
+    if dry_run {
+        use api::RequestInfo as _;
+
+        let body = http::render_body(&req)?.unwrap_or_default();
+        let body: serde_json::Value = serde_json::from_slice(body.as_slice()).map_err(error::AppError::new_other)?;
+        let v = output::RequestJson { method: req.method().as_str(), url: req.url(api_url).to_string(), body };
+        match format {
+            OutputFormat::Json => output::write_json(out, &StatusJson::dry_run(v)),
+            _ => writeln!(out, "[dry-run] would send {} {}", v.method, v.url).and_then(|_| output::write_json(out, &v.body)),
+        }
+        .map_err(error::AppError::new_other)?;
+        return Ok(())
+    }
+
     let s = httpc.request::<api::RegisterRequest, api::RegisterResponse>(api_url, req)?;
+
+    if let Some(old_uuid) = old_uuid {
+        eprintln!("old server_uuid: {}\nnew server_uuid: {}", old_uuid, s.uuid);
+        if let (true, Some((old_cert, old_key_id))) = (unregister_old, previous_key) {
+            let unregistered = kpg.generate(old_cert, old_key_id, None)
+                .map_err(|e| anyhow::anyhow!("the old key {} can not be used: {}", old_key_id, e))
+                .and_then(|old_keypair| {
+                    let req = api::UnregisterRequest::new(
+                        api::UnregisterContent{ 
+                            timestamp: timestamp(SystemTime::now()).unwrap(),
+                            comment: format!("replaced by {}", s.uuid),
+                        },
+                        old_keypair,
+                        old_uuid
+                    );
+                    httpc.request::<api::UnregisterRequest, api::UnregisterResponse>(api_url, req).map_err(|e| anyhow::anyhow!("{}", e))
+                });
+            match unregistered {
+                Ok(_) => eprintln!("- server_uuid: {}", old_uuid),
+                Err(e) => eprintln!("warning: can not unregister the old server {}: {}; unregister it manually", old_uuid, e),
+            }
+        } else {
+            eprintln!("the old server is still registered; unregister it manually if it is no longer used");
+        }
+    }
     
-    println!("succeed\n+ server_uuid: {}", s.uuid);
+    match format {
+        OutputFormat::Json => {
+            let v = StatusJson::ok(output::ServerUuidJson { server_uuid: s.uuid.clone() });
+            output::write_json(out, &v).map_err(error::AppError::new_other)?;
+        }
+        _ => eprintln!("succeed\n+ server_uuid: {}", s.uuid),
+    }
 
     cfg.get_data_mut().server_uuid = Some(s.uuid);
 
@@ -97,21 +394,73 @@ pub fn command_register<'a>(
 }
 
 
+/**
+ * options of commands that can not be undone
+ */
+#[derive(Clone, Copy, Default)]
+pub struct DestructiveOptions {
+    /// proceed without asking; the target is still printed
+    pub yes: bool,
+    /// proceed even if the api url misses the marker in `confirm_registry`
+    pub override_registry_guard: bool,
+}
+
+/// `Err` tells which marker the api url misses
+pub fn registry_guard(api_url: &Url, confirm_registry: Option<&str>) -> Result<(), String> {
+    match confirm_registry {
+        Some(marker) if !api_url.as_str().contains(marker) => {
+            Err(format!("api_url {} does not contain \"{}\" required by confirm_registry", api_url, marker))
+        }
+        _ => Ok(())
+    }
+}
+
+fn confirm_destructive(
+    action: &str,
+    api_url: &Url,
+    server_uuid: &Uuid,
+    server_name: Option<&str>,
+    confirm_registry: Option<&str>,
+    options: DestructiveOptions,
+) -> GeneralResult<()> {
+    eprintln!("==== {} ====", action);
+    eprintln!("  api_url:       {}", api_url);
+    eprintln!("  server_uuid:   {}", server_uuid);
+    eprintln!("  registry name: {}", server_name.unwrap_or("(unknown, run `server` to cache names)"));
+    if let Err(e) = registry_guard(api_url, confirm_registry) {
+        if options.override_registry_guard {
+            eprintln!("warning: {}", e);
+        } else {
+            return Err(anyhow::anyhow!("{}; pass --override-registry-guard to proceed anyway", e))
+        }
+    }
+    if options.yes || confirm_word(format!("{} on {}?", action, api_url).as_str(), "yes")? {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("{} cancelled", action))
+    }
+}
+
+
 pub fn command_unregister<'a>(
     cfg: &mut ClientConfig,
+    names: &registry::RegistryNames,
     httpc: &http::Client,
     kpg: &mut SigningKeyPairGenerator,
     comment: &'a str,
+    options: DestructiveOptions,
 ) -> error::AppResult<'a> {
     
     let comment = comment.to_owned();
 
+    cfg.require_cert()?;
     let cfg_data = cfg.get_data();
     let server_uuid = error::ConfigMissing::ok(cfg_data.server_uuid.as_ref(), "client.server_uuid")?.clone();
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
+    confirm_destructive("unregister", api_url, &server_uuid, names.get(&server_uuid), cfg_data.confirm_registry.as_deref(), options)?;
     let cert = error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?;
     let key_id = error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?;
     let keypair = kpg.generate(cert, key_id, None)?;
-    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
 
     let req = api::UnregisterRequest::new(
         api::UnregisterContent{ 
@@ -122,27 +471,238 @@ pub fn command_unregister<'a>(
         server_uuid
     );
 
-    let s = httpc.request::<api::UnregisterRequest, api::UnregisterResponse>(api_url, req)?;
+    // server_uuid is only cleared once the registry confirms the unregister
+    let s = match httpc.request::<api::UnregisterRequest, api::UnregisterResponse>(api_url, req) {
+        Ok(s) => s,
+        Err(http::RequsetError::Response(resp)) if resp.code == 404 => {
+            eprintln!("server {} is not registered in {}", &server_uuid, api_url);
+            let prompt = format!("remove server_uuid {} from the local config?", &server_uuid);
+            if options.yes || confirm(prompt.as_str()).map_err(error::AppError::new_other)? {
+                cfg.get_data_mut().server_uuid = None;
+                eprintln!("unset config: server_uuid");
+                return Ok(())
+            }
+            return Err(http::RequsetError::Response(resp).into())
+        }
+        Err(e) => return Err(e.into()),
+    };
     
-    println!("succeed\n- server_uuid: {}", s.uuid);
+    eprintln!("succeed\n- server_uuid: {}", s.uuid);
     
     cfg.get_data_mut().server_uuid = None;
        
     Ok(())
 }
 
+/**
+ * switch to a new signing key: register with the new key, then unregister the old server with the old key,
+ * as the registry has no way to change the key of a server. config is only changed once the new registration
+ * exists; if the old server can not be unregistered it is left to do by hand
+ */
+pub fn command_rotate_key<'a>(
+    cfg: &mut ClientConfig,
+    names: &registry::RegistryNames,
+    httpc: &http::Client,
+    kpg: &mut SigningKeyPairGenerator,
+    base_dir: &Path,
+    cert_file: Option<&'a str>,
+    key_id: &'a str,
+    server_name: Option<&'a str>,
+    options: DestructiveOptions,
+) -> error::AppResult<'a> {
+
+    let new_key_id: KeyID = error::ArgsError::parse(key_id, "key_id", "hex")?;
+
+    cfg.require_cert()?;
+    let cfg_data = cfg.get_data();
+    let old_uuid = error::ConfigMissing::ok(cfg_data.server_uuid, "client.server_uuid")?;
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?.clone();
+    let old_cert = error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?.clone();
+    let old_key_id = error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?.clone();
+    let new_cert_file = match cert_file {
+        Some(s) => PathBuf::from(s),
+        None => error::ConfigMissing::ok(cfg_data.cert_file.as_ref(), "client.cert_file")?.clone(),
+    };
+
+    let new_cert = pgp::load_cert(new_cert_file.as_path())?;
+    if new_key_id == old_key_id && new_cert.fingerprint() == old_cert.fingerprint() {
+        return Err(error::ArgsError::new("key_id", "a key other than the configured one", key_id).into())
+    }
+    if !pgp::check_key(&new_cert, cfg.policy(), None, &new_key_id) || !pgp::check_secret_key(&new_cert, cfg.policy(), None, &new_key_id) {
+        return Err(anyhow::anyhow!("key {} in {} is not a valid signing key with a secret key", new_key_id, new_cert_file.display()).into())
+    }
+
+    let server_name = match server_name {
+        Some(s) => s.to_owned(),
+        None => {
+            let req = api::GetServerRequest::new(old_uuid);
+            httpc.request::<api::GetServerRequest, api::GetServerResponse>(&api_url, req)
+                .map_err(|e| anyhow::anyhow!("can not fetch the name of server {}: {}; pass --server-name", old_uuid, e))?
+                .server.server_name
+        }
+    };
+
+    let action = format!("rotate key {} -> {}, unregistering the old server", old_key_id, new_key_id);
+    confirm_destructive(action.as_str(), &api_url, &old_uuid, names.get(&old_uuid), cfg_data.confirm_registry.as_deref(), options)?;
+
+    // 1. the new registration; nothing is changed if it fails
+    let keypair = kpg.generate(&new_cert, &new_key_id, None)?;
+    let req = api::RegisterRequest::new(
+        api::RegisterContent{ server_name: server_name.clone() }, 
+        &new_cert, 
+        keypair
+    );
+    let new_uuid = httpc.request::<api::RegisterRequest, api::RegisterResponse>(&api_url, req)?.uuid;
+    eprintln!("+ server_uuid: {} ({}, key {})", new_uuid, server_name, new_key_id);
+
+    // 2. config follows the new registration; it is written when the command ends
+    if !cfg.set_cert_file(new_cert_file.to_string_lossy().as_ref()) {
+        eprintln!("warning: can not set cert_file {}", new_cert_file.display());
+    }
+    cfg.get_data_mut().key_id = None;
+    if !cfg.set_key_id(key_id) {
+        eprintln!("warning: can not set key_id {}", new_key_id);
+    }
+    cfg.get_data_mut().server_uuid = Some(new_uuid);
+
+    // 3. the records of the old identity are kept under the new one
+    let from = base_dir.join(format!("record-{}", old_uuid));
+    let to = base_dir.join(format!("record-{}", new_uuid));
+    let header = RecordHeader::new(Some(api_url.clone()), Some(new_uuid));
+    match crate::config::records::move_records(from.as_path(), to.as_path(), &header) {
+        Ok(true) => eprintln!("records moved to {}", to.display()),
+        Ok(false) => {}
+        Err(e) => eprintln!("warning: can not move records {} to {}: {}", from.display(), to.display(), e),
+    }
+
+    // 4. the old server, signed with the old key
+    let unregistered = match kpg.generate(&old_cert, &old_key_id, None) {
+        Ok(keypair) => {
+            let req = api::UnregisterRequest::new(
+                api::UnregisterContent{ 
+                    timestamp: timestamp(SystemTime::now()).unwrap(),
+                    comment: format!("key rotated, replaced by {}", new_uuid),
+                },
+                keypair,
+                old_uuid
+            );
+            httpc.request::<api::UnregisterRequest, api::UnregisterResponse>(&api_url, req).map(|_| ()).map_err(|e| e.to_string())
+        }
+        Err(e) => Err(e.to_string()),
+    };
+    match unregistered {
+        Ok(_) => eprintln!("- server_uuid: {} (key {})", old_uuid, old_key_id),
+        Err(e) => eprintln!("warning: can not unregister the old server {} (key {}): {}; unregister it by hand with the old key", old_uuid, old_key_id, e),
+    }
+
+    eprintln!("key rotated: {} -> {}, server_uuid {} -> {}", old_key_id, new_key_id, old_uuid, new_uuid);
+
+    Ok(())
+}
+
+
+fn load_protected_players(cfg: &ClientConfig, protect_from: &[&str]) -> GeneralResult<HashSet<Uuid>> {
+    let mut players = HashSet::new();
+    if let Some(ref path) = cfg.get_data().protected_players_file {
+        players.extend(banlist::load_player_list(path)?);
+    }
+    for path in protect_from {
+        players.extend(banlist::load_player_list(path)?);
+    }
+    Ok(players)
+}
+
+fn confirm(prompt: &str) -> io::Result<bool> {
+    use std::io::IsTerminal;
+
+    if !io::stdin().is_terminal() {
+        return Ok(false)
+    }
+    eprint!("{} [y/N] ", prompt);
+    io::stderr().flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    let line = line.trim();
+    Ok(line.eq_ignore_ascii_case("y") || line.eq_ignore_ascii_case("yes"))
+}
+
+/// ask on the console until `parse` accepts the line; end of input is an error
+fn prompt_value<T, F>(prompt: &str, parse: F) -> io::Result<T>
+where
+    F: Fn(&str) -> Result<T, String>
+{
+    loop {
+        eprint!("{}: ", prompt);
+        io::stderr().flush()?;
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("no input for {}", prompt)))
+        }
+        match parse(line.trim()) {
+            Ok(v) => return Ok(v),
+            Err(e) => eprintln!("invalid {}: {}", prompt, e),
+        }
+    }
+}
+
+fn parse_points(s: &str) -> Result<f32, String> {
+    match f32::from_str(s) {
+        Ok(v) if (-1.0..=1.0).contains(&v) => Ok(v),
+        _ => Err("expecting float in [-1, 1]".to_owned()),
+    }
+}
+
+/// like `confirm`, but only the full word is accepted
+fn confirm_word(prompt: &str, word: &str) -> io::Result<bool> {
+    use std::io::IsTerminal;
+
+    if !io::stdin().is_terminal() {
+        return Ok(false)
+    }
+    eprint!("{} type \"{}\" to continue: ", prompt, word);
+    io::stderr().flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim() == word)
+}
+
 
 pub fn command_submit<'a>(
     cfg: &mut ClientConfig,
     records: &mut RecordConfig,
     httpc: &http::Client,
     kpg: &mut SigningKeyPairGenerator,
-    player_uuid: &'a str, 
-    points: &'a str, 
-    comment: &'a str,
+    player_uuid: Option<&'a str>, 
+    player_name: Option<(&'a str, NameSource<'a, '_>)>,
+    points: Option<&'a str>, 
+    comment: Option<&'a str>,
+    event_time: Option<&'a str>,
     force: bool,
+    dry_run: bool,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
 ) -> error::AppResult<'a> {
-    let player_uuid = error::ArgsError::parse(player_uuid, "player_uuid", "uuid")?;
+    use std::io::IsTerminal;
+
+    let event_time = parse_past_timestamp(event_time, "timestamp")?;
+    let resolved = match player_name {
+        Some((name, source)) => {
+            let uuid = resolve_player_name(httpc, name, source)?;
+            eprintln!("player {} is {}", name, uuid);
+            Some((name, uuid))
+        }
+        None => None,
+    };
+
+    // fields left out are asked on a terminal; scripts keep failing fast
+    let interactive = ((player_uuid.is_none() && resolved.is_none()) || points.is_none()) && io::stdin().is_terminal();
+    let player_uuid: Uuid = match player_uuid {
+        _ if resolved.is_some() => resolved.unwrap().1,
+        Some(s) => error::ArgsError::parse(s, "player_uuid", "uuid")?,
+        None if interactive => prompt_value("player_uuid", |s| Uuid::from_str(s).map_err(|_| "expecting uuid".to_owned()))
+            .map_err(error::AppError::new_other)?,
+        None => return Err(error::ArgsError::missing("player_uuid", "uuid").into()),
+    };
     if let Some(record_uuid) = records.check_player_uuid(&player_uuid) {
         if force {
 
@@ -150,17 +710,49 @@ pub fn command_submit<'a>(
             return Err(error::AppError::Other(anyhow::anyhow!("submit existed: {}", record_uuid)))
         }
     }
-    let points = error::ArgsError::parse(points, "points", "float$[-1,1]")?;
-    let comment = comment.to_owned();
+    let points: f32 = match points {
+        Some(s) => error::ArgsError::parse(s, "points", "float$[-1,1]")?,
+        None if interactive => prompt_value("points", parse_points).map_err(error::AppError::new_other)?,
+        None => return Err(error::ArgsError::missing("points", "float$[-1,1]").into()),
+    };
+    let comment = match comment {
+        Some(s) => s.to_owned(),
+        None if interactive => prompt_value("comment (optional)", |s| Ok(s.to_owned())).map_err(error::AppError::new_other)?,
+        None => String::new(),
+    };
+    // a name resolved to the wrong player is only caught by a look at the uuid
+    if interactive || (resolved.is_some() && io::stdin().is_terminal()) {
+        eprintln!("==== submit ====");
+        if let Some((name, _)) = resolved {
+            eprintln!("  player_name: {}", name);
+        }
+        eprintln!("  player_uuid: {}", player_uuid);
+        eprintln!("  points:      {}", points);
+        eprintln!("  comment:     {}", comment);
+        if let Some(t) = event_time {
+            eprintln!("  timestamp:   {}", TimestampDisplay(t));
+        }
+        if !confirm("sign and submit this record?").map_err(error::AppError::new_other)? {
+            return Err(anyhow::anyhow!("submit cancelled").into())
+        }
+    }
+    if load_protected_players(cfg, &[])?.contains(&player_uuid) {
+        let prompt = format!("player #{} is protected, submit anyway?", &player_uuid);
+        if !confirm(prompt.as_str()).map_err(error::AppError::new_other)? {
+            return Err(error::AppError::Other(anyhow::anyhow!("player is protected: {}", &player_uuid)))
+        }
+    }
     
+    cfg.require_cert()?;
     let cfg_data = cfg.get_data();
     let server_uuid = error::ConfigMissing::ok(cfg_data.server_uuid.as_ref(), "client.server_uuid")?.clone();
     let cert = error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?;
     let key_id = error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?;
+    warn_key_expiring(cert, cfg.policy(), key_id);
     let keypair = kpg.generate(cert, key_id, None)?;
     let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
 
-    let timestamp = timestamp(SystemTime::now()).unwrap();
+    let timestamp = event_time.unwrap_or_else(|| timestamp(SystemTime::now()).unwrap());
     let req = api::SubmitRequest::new(
         api::SubmitContent{ 
             uuid: server_uuid,
@@ -172,25 +764,184 @@ pub fn command_submit<'a>(
         &keypair
     );
 
+    if dry_run {
+        api::WriteTo::write_to(&req, io::sink())?;
+        match format {
+            OutputFormat::Json => {
+                let v = StatusJson::dry_run(output::SubmittedJson { record_uuid: None, player_uuid, points, timestamp });
+                output::write_json(out, &v).map_err(error::AppError::new_other)?;
+            }
+            _ => writeln!(out, "[dry-run] would submit player={} points={}", player_uuid, points).map_err(error::AppError::new_other)?,
+        }
+        return Ok(())
+    }
+
     let s = httpc.request::<api::SubmitRequest, api::SubmitResponse>(api_url, req)?;
-    println!("succeed\n+ record_uuid: {}", s.uuid);
+    match format {
+        OutputFormat::Json => {
+            let v = StatusJson::ok(output::SubmittedJson { record_uuid: Some(s.uuid.clone()), player_uuid, points, timestamp });
+            output::write_json(out, &v).map_err(error::AppError::new_other)?;
+        }
+        _ => eprintln!("succeed\n+ record_uuid: {}", s.uuid),
+    }
 
     records.new_submit(s.uuid, timestamp, player_uuid);
 
     Ok(())
 }
 
+/**
+ * `submit -p a -p b ...`: one record per player with the same points and comment, signed with one keypair;
+ * a player that fails does not stop the others
+ */
+pub fn command_submit_many<'a>(
+    cfg: &mut ClientConfig,
+    records: &mut RecordConfig,
+    httpc: &http::Client,
+    kpg: &mut SigningKeyPairGenerator,
+    player_uuids: &[&'a str], 
+    points: Option<&'a str>, 
+    comment: Option<&'a str>,
+    event_time: Option<&'a str>,
+    force: bool,
+    dry_run: bool,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let event_time = parse_past_timestamp(event_time, "timestamp")?;
+    let mut players: Vec<Uuid> = Vec::with_capacity(player_uuids.len());
+    for s in player_uuids {
+        players.push(error::ArgsError::parse(s, "player_uuid", "uuid")?);
+    }
+    let points: f32 = match points {
+        Some(s) => error::ArgsError::parse(s, "points", "float$[-1,1]")?,
+        None => return Err(error::ArgsError::missing("points", "float$[-1,1]").into()),
+    };
+    let comment = comment.unwrap_or_default();
+    let protected = load_protected_players(cfg, &[])?;
+
+    cfg.require_cert()?;
+    let cfg_data = cfg.get_data();
+    let server_uuid = error::ConfigMissing::ok(cfg_data.server_uuid.as_ref(), "client.server_uuid")?.clone();
+    let cert = error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?;
+    let key_id = error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?;
+    warn_key_expiring(cert, cfg.policy(), key_id);
+    let keypair = kpg.generate(cert, key_id, None)?;
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
+
+    let timestamp = event_time.unwrap_or_else(|| timestamp(SystemTime::now()).unwrap());
+    let mut summary = output::SubmitManyJson { points, timestamp, players: Vec::with_capacity(players.len()) };
+    for player_uuid in players {
+        let outcome = |result: Result<Option<Uuid>, String>| match result {
+            Ok(record_uuid) => output::PlayerSubmitJson { player_uuid, record_uuid, error: None },
+            Err(e) => output::PlayerSubmitJson { player_uuid, record_uuid: None, error: Some(e) },
+        };
+        if let Some(record_uuid) = records.check_player_uuid(&player_uuid) {
+            if !force {
+                summary.players.push(outcome(Err(format!("submit existed: {}", record_uuid))));
+                continue;
+            }
+        }
+        if protected.contains(&player_uuid) {
+            let prompt = format!("player #{} is protected, submit anyway?", &player_uuid);
+            if !confirm(prompt.as_str()).map_err(error::AppError::new_other)? {
+                summary.players.push(outcome(Err(String::from("player is protected"))));
+                continue;
+            }
+        }
+
+        let req = api::SubmitRequest::new(
+            api::SubmitContent{ 
+                uuid: server_uuid,
+                timestamp,
+                player_uuid,
+                points,
+                comment: comment.to_owned(),
+            },
+            &keypair
+        );
+        if dry_run {
+            api::WriteTo::write_to(&req, io::sink())?;
+            if format != OutputFormat::Json {
+                writeln!(out, "[dry-run] would submit player={} points={}", player_uuid, points).map_err(error::AppError::new_other)?;
+            }
+            summary.players.push(outcome(Ok(None)));
+            continue;
+        }
+        match httpc.request::<api::SubmitRequest, api::SubmitResponse>(api_url, req) {
+            Ok(s) => {
+                records.new_submit(s.uuid, timestamp, player_uuid);
+                summary.players.push(outcome(Ok(Some(s.uuid))));
+            }
+            Err(e) => {
+                eprintln!("failed {}: {}", player_uuid, e);
+                summary.players.push(outcome(Err(e.to_string())));
+            }
+        }
+    }
+
+    let failed = summary.players.iter().filter(|p| p.error.is_some()).count();
+    match format {
+        OutputFormat::Json => {
+            let v = if dry_run { StatusJson::dry_run(summary) } else { StatusJson::ok(summary) };
+            output::write_json(out, &v).map_err(error::AppError::new_other)?;
+        }
+        _ => {
+            for p in summary.players.iter() {
+                match (&p.record_uuid, &p.error) {
+                    (Some(r), _) => writeln!(out, "{} -> {}", p.player_uuid, r),
+                    (None, Some(e)) => writeln!(out, "{} -> failed: {}", p.player_uuid, e),
+                    (None, None) => continue,
+                }
+                .map_err(error::AppError::new_other)?;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} of {} submit(s) failed", failed, player_uuids.len()).into())
+    }
+
+    Ok(())
+}
+
 
 pub fn command_recall<'a>(
     cfg: &mut ClientConfig,
     records: &mut RecordConfig,
+    names: &registry::RegistryNames,
     httpc: &http::Client,
     kpg: &mut SigningKeyPairGenerator,
-    record_uuid: &'a str, 
+    handle: RecallHandleWrap<'a>, 
     comment: &'a str,
     force: bool,
+    dry_run: bool,
+    options: DestructiveOptions,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
 ) -> error::AppResult<'a> {
-    let record_uuid = error::ArgsError::parse(record_uuid, "record_uuid", "uuid")?;
+    let record_uuid = match handle {
+        RecallHandleWrap::RecordUUID(s) => {
+            error::ArgsError::parse(s, "record_uuid", "uuid")?
+        }
+        RecallHandleWrap::PlayerUUID(s) => {
+            let player_uuid: Uuid = error::ArgsError::parse(s, "player_uuid", "uuid")?;
+            match records.check_player_uuid(&player_uuid) {
+                Some(record_uuid) => record_uuid.clone(),
+                None => {
+                    let nearest: Vec<String> = nearest_players(records, &player_uuid, 5)
+                        .into_iter()
+                        .map(|(p, r)| format!("\n  {} (record: {})", p, r))
+                        .collect();
+                    return Err(error::AppError::Other(anyhow::anyhow!(
+                        "no local record for player: {}; nearest matches:{}", 
+                        &player_uuid,
+                        if nearest.is_empty() { String::from(" (none)") } else { nearest.concat() }
+                    )))
+                }
+            }
+        }
+    };
     if let Some(player_uuid) = records.check_record_uuid(&record_uuid) {
         
     } else {
@@ -202,12 +953,17 @@ pub fn command_recall<'a>(
     }
     let comment = comment.to_owned();
     
+    cfg.require_cert()?;
     let cfg_data = cfg.get_data();
     let server_uuid = error::ConfigMissing::ok(cfg_data.server_uuid.as_ref(), "client.server_uuid")?.clone();
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
+    if force && !dry_run {
+        let action = format!("recall {}", &record_uuid);
+        confirm_destructive(action.as_str(), api_url, &server_uuid, names.get(&server_uuid), cfg_data.confirm_registry.as_deref(), options)?;
+    }
     let cert = error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?;
     let key_id = error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?;
     let keypair = kpg.generate(cert, key_id, None)?;
-    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
 
     let timestamp = timestamp(SystemTime::now()).unwrap();    
     let req = api::RecallRequest::new(
@@ -219,8 +975,26 @@ pub fn command_recall<'a>(
         &keypair
     );
 
+    if dry_run {
+        api::WriteTo::write_to(&req, io::sink())?;
+        match format {
+            OutputFormat::Json => {
+                let v = StatusJson::dry_run(output::RecalledJson { record_uuid });
+                output::write_json(out, &v).map_err(error::AppError::new_other)?;
+            }
+            _ => writeln!(out, "[dry-run] would recall record={}", record_uuid).map_err(error::AppError::new_other)?,
+        }
+        return Ok(())
+    }
+
     let s = httpc.request::<api::RecallRequest, api::RecallResponse>(api_url, req)?;
-    println!("succeed\n- record_uuid: {} ", &s.uuid);
+    match format {
+        OutputFormat::Json => {
+            let v = StatusJson::ok(output::RecalledJson { record_uuid: s.uuid.clone() });
+            output::write_json(out, &v).map_err(error::AppError::new_other)?;
+        }
+        _ => eprintln!("succeed\n- record_uuid: {} ", &s.uuid),
+    }
     
     records.new_recall(s.uuid, timestamp);
 
@@ -228,318 +1002,2786 @@ pub fn command_recall<'a>(
 }
 
 
-pub fn command_cert_add<'a>(
-    cfg: &mut ServersConfig, 
-    server_uuid: &'a str, 
-    name: &'a str, 
-    key_id: &'a str, 
-    trust: &'a str
+/**
+ * recall every record uuid listed in the file, one per line, with one decrypted key pair;
+ * records missing from the local record file are skipped unless `force`
+ */
+pub fn command_recall_batch<'a>(
+    cfg: &mut ClientConfig,
+    records: &mut RecordConfig,
+    names: &registry::RegistryNames,
+    httpc: &http::Client,
+    kpg: &mut SigningKeyPairGenerator,
+    file: &'a str,
+    interval: Option<&'a str>,
+    comment: &'a str,
+    force: bool,
+    dry_run: bool,
+    options: DestructiveOptions,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+    use std::io::BufRead;
+
+    let interval = match interval {
+        Some(s) => Duration::from_millis(error::ArgsError::parse(s, "interval", "integer of milliseconds")?),
+        None => Duration::ZERO
+    };
+    let ifile = File::open(file).map_err(error::AppError::new_other)?;
+    let mut record_uuids: Vec<Uuid> = Vec::new();
+    for (i, line) in io::BufReader::new(ifile).lines().enumerate() {
+        let line = line.map_err(error::AppError::new_other)?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let record_uuid = Uuid::from_str(line).map_err(|_| anyhow::anyhow!("{} line {}: invalid record uuid `{}`", file, i + 1, line))?;
+        record_uuids.push(record_uuid);
+    }
+
+    let mut summary = output::RecallBatchJson::default();
+    let unknown: Vec<&Uuid> = record_uuids.iter().filter(|u| records.check_record_uuid(u).is_none()).collect();
+    for record_uuid in unknown.iter() {
+        eprintln!("{}: {}", if force { "not in the local record file" } else { "skip, not in the local record file" }, record_uuid);
+    }
+
+    cfg.require_cert()?;
+    let cfg_data = cfg.get_data();
+    let server_uuid = error::ConfigMissing::ok(cfg_data.server_uuid.as_ref(), "client.server_uuid")?.clone();
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
+    if force && !dry_run && !unknown.is_empty() {
+        let action = format!("recall {} record(s), {} not in the local record file", record_uuids.len(), unknown.len());
+        confirm_destructive(action.as_str(), api_url, &server_uuid, names.get(&server_uuid), cfg_data.confirm_registry.as_deref(), options)?;
+    }
+    let cert = error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?;
+    let key_id = error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?;
+    let keypair = kpg.generate(cert, key_id, None)?;
+
+    let mut last = SystemTime::UNIX_EPOCH;
+    for record_uuid in record_uuids {
+        if !force && records.check_record_uuid(&record_uuid).is_none() {
+            summary.skipped.push(record_uuid);
+            continue;
+        }
+        if !dry_run {
+            sleep_interval(interval, last);
+        }
+
+        let timestamp = timestamp(SystemTime::now()).unwrap();
+        let req = api::RecallRequest::new(
+            record_uuid,
+            api::RecallContent{
+                timestamp,
+                comment: comment.to_owned(),
+            },
+            &keypair
+        );
+
+        if dry_run {
+            api::WriteTo::write_to(&req, io::sink())?;
+            if format != OutputFormat::Json {
+                writeln!(out, "[dry-run] would recall record={}", record_uuid).map_err(error::AppError::new_other)?;
+            }
+            summary.recalled.push(record_uuid);
+            continue;
+        }
+
+        last = SystemTime::now();
+        match httpc.request::<api::RecallRequest, api::RecallResponse>(api_url, req) {
+            Ok(s) => {
+                log_info!("- record_uuid: {}", &s.uuid);
+                records.new_recall(s.uuid, timestamp);
+                summary.recalled.push(s.uuid);
+            }
+            Err(e) => {
+                eprintln!("failed {}: {}", record_uuid, e);
+                summary.failed.push(record_uuid);
+            }
+        }
+    }
+
+    match format {
+        OutputFormat::Json => {
+            let failed = summary.failed.len();
+            let v = if dry_run { StatusJson::dry_run(summary) } else { StatusJson::ok(summary) };
+            output::write_json(out, &v).map_err(error::AppError::new_other)?;
+            if failed > 0 {
+                return Err(anyhow::anyhow!("{} recall(s) failed", failed).into())
+            }
+        }
+        _ => {
+            writeln!(out, "recalled: {}, skipped: {}, failed: {}", summary.recalled.len(), summary.skipped.len(), summary.failed.len())
+                .map_err(error::AppError::new_other)?;
+            for record_uuid in summary.failed.iter() {
+                writeln!(out, "  failed: {}", record_uuid).map_err(error::AppError::new_other)?;
+            }
+            if !summary.failed.is_empty() {
+                return Err(anyhow::anyhow!("{} recall(s) failed", summary.failed.len()).into())
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+pub enum RecallHandleWrap<'a> {
+    RecordUUID(&'a str),
+    PlayerUUID(&'a str),
+}
+
+fn nearest_players<'r>(records: &'r RecordConfig, player_uuid: &Uuid, n: usize) -> Vec<(&'r Uuid, &'r Uuid)> {
+    let target = player_uuid.to_simple().to_string();
+    let mut candidates: Vec<(usize, &Uuid)> = records.players()
+        .map(|p| {
+            let common = p.to_simple().to_string().chars()
+                .zip(target.chars())
+                .take_while(|(a, b)| a == b)
+                .count();
+            (common, p)
+        })
+        .filter(|(common, _)| *common > 0)
+        .collect();
+    candidates.sort_by(|a, b| b.0.cmp(&a.0));
+    candidates.into_iter()
+        .take(n)
+        .map(|(_, p)| (p, records.check_player_uuid(p).unwrap()))
+        .collect()
+}
+
+
+fn parse_api_url<'a>(s: &'a str, name: &'static str) -> Result<Url, error::ArgsError<'a>> {
+    let mut url: Url = error::ArgsError::parse(s, name, "url")?;
+    let path = url.path();
+    if !path.ends_with('/') {
+        let path = path.to_owned() + "/";
+        url.set_path(path.as_str());
+    }
+    Ok(url)
+}
+
+
+fn parse_trust<'a>(s: &'a str) -> Result<u32, error::ArgsError<'a>> {
+    let trust: u32 = error::ArgsError::parse(s, "trust", "integer$(1,2,3,4,5)")?;
+    if (1..=5).contains(&trust) {
+        Ok(trust)
+    } else {
+        Err(error::ArgsError::new("trust", "integer$(1,2,3,4,5)", s))
+    }
+}
+
+
+pub fn command_cert_add<'a>(
+    cfg: &mut ServersConfig, 
+    server_uuid: &'a str, 
+    name: &'a str, 
+    key_id: &'a str, 
+    trust: &'a str,
+    api_url: Option<&'a str>,
+    allow_shared_key: bool,
+    file: Option<&'a str>,
 ) -> error::AppResult<'a> {
 
     let server_uuid: Uuid = error::ArgsError::parse(server_uuid, "server_uuid", "uuid")?;
     let key_id: KeyID = error::ArgsError::parse(key_id, "key_id", "hex")?;
+    check_shared_key(cfg, &server_uuid, &key_id, allow_shared_key)?;
     let name = name.to_owned();
-    let trust: u32 = error::ArgsError::parse(trust, "trust", "integer$(1,2,3,4,5)")?;
+    let trust = parse_trust(trust)?;
+    let api_url = if let Some(s) = api_url {
+        Some(parse_api_url(s, "api_url")?)
+    } else {
+        None
+    };
+
+    let mut server_data = ServerData::new(name, key_id, trust);
+    server_data.api_url = api_url;
+
+    let success = cfg.add(server_uuid, server_data, |_, data| {
+        match file {
+            Some(path) => {
+                // a keyring may hold several certs; take the one with the given key
+                let handle = KeyHandle::KeyID(data.key_id.clone());
+                pgp::load_cert_from_keyring(path, Some(&handle))?
+                    .map(Rc::new)
+                    .ok_or_else(|| anyhow::anyhow!("no cert in {} contains key {}", path, data.key_id.to_hex()))
+            }
+            None => {
+                eprintln!("==== please input pgp text, end with ctrl-Z ===");
+                pgp::read_cert_from_console().map(Rc::new)
+            }
+        }
+    })?;
+
+    if success {
+        eprintln!("success.")
+    } else {
+        eprintln!("existed.")
+    }
+
+    Ok(())
+}
+
+
+/**
+ * add the cert a server registered in OpenMPRDB, after its fingerprint is accepted
+ * on the console or matches `expected_fingerprint`
+ */
+pub fn command_cert_fetch<'a>(
+    cfg: &mut ServersConfig,
+    client: &ClientConfig,
+    httpc: &http::Client,
+    server_uuid: &'a str,
+    name: Option<&'a str>,
+    trust: &'a str,
+    api_url: Option<&'a str>,
+    allow_shared_key: bool,
+    expected_fingerprint: Option<&'a str>,
+) -> error::AppResult<'a> {
+
+    let server_uuid: Uuid = error::ArgsError::parse(server_uuid, "server_uuid", "uuid")?;
+    let trust = parse_trust(trust)?;
+    let expected_fingerprint: Option<Fingerprint> = match expected_fingerprint {
+        Some(s) => Some(error::ArgsError::parse(s, "fingerprint", "hex")?),
+        None => None
+    };
+    let api_url = if let Some(s) = api_url {
+        Some(parse_api_url(s, "api_url")?)
+    } else {
+        None
+    };
+    let registry = match api_url.as_ref() {
+        Some(url) => url,
+        None => error::ConfigMissing::ok(client.get_data().api_url.as_ref(), "client.api_url")?,
+    };
+
+    let req = api::GetServerRequest::new(server_uuid);
+    let registered = httpc.request::<api::GetServerRequest, api::GetServerResponse>(registry, req)?.server;
+    if !pgp::check_key(&registered.public_key, cfg.policy(), None, &registered.key_id) {
+        return Err(anyhow::anyhow!("registered key {} of server {} is not valid", &registered.key_id, &server_uuid).into())
+    }
+    check_shared_key(cfg, &server_uuid, &registered.key_id, allow_shared_key)?;
+
+    let fingerprint = registered.public_key.fingerprint();
+    eprintln!("server_name: {}", registered.server_name);
+    eprintln!("key_id:      {}", registered.key_id);
+    eprintln!("fingerprint: {}", fingerprint);
+    match expected_fingerprint {
+        Some(expected) => {
+            if expected != fingerprint {
+                return Err(anyhow::anyhow!("fingerprint of the registered key is {}, expected {}", fingerprint, expected).into())
+            }
+        }
+        None => {
+            // compare it with one obtained out of band; the registry alone is not a reason to trust the key
+            if !confirm("is this the fingerprint of the server?").map_err(error::AppError::new_other)? {
+                return Err(anyhow::anyhow!("fingerprint not accepted; pass --fingerprint to verify it non-interactively").into())
+            }
+        }
+    }
+
+    let name = name.map(str::to_owned).unwrap_or(registered.server_name);
+    let mut server_data = ServerData::new(name, registered.key_id, trust);
+    server_data.api_url = api_url;
+
+    let cert = Rc::new(registered.public_key);
+    let success = cfg.add(server_uuid, server_data, |_, _| Ok(cert))?;
+
+    if success {
+        eprintln!("success.")
+    } else {
+        eprintln!("existed.")
+    }
+
+    Ok(())
+}
+
+
+/**
+ * add servers of the registry list not yet in the servers config, each confirmed on the console unless `yes`;
+ * the key the server registered is used as is
+ */
+pub fn command_cert_import_from_api<'a>(
+    cfg: &mut ServersConfig,
+    client: &ClientConfig,
+    httpc: &http::Client,
+    trust: Option<&'a str>,
+    yes: bool,
+) -> error::AppResult<'a> {
+
+    let trust = trust.ok_or_else(|| error::ArgsError::missing("trust", "integer$(1,2,3,4,5)"))?;
+    let trust = parse_trust(trust)?;
+    let client_data = client.get_data();
+    let api_url = error::ConfigMissing::ok(client_data.api_url.as_ref(), "client.api_url")?;
+
+    let req = api::ServerListRequest::new(None);
+    let list = httpc.request::<api::ServerListRequest, api::ServerListResponse>(api_url, req)?;
+
+    let mut added = 0;
+    let mut known = 0;
+    let mut declined = 0;
+    for registered in list.servers {
+        if cfg.get_data().contains_key(&registered.uuid) || client_data.server_uuid == Some(registered.uuid) {
+            known += 1;
+            continue;
+        }
+        if !pgp::check_key(&registered.public_key, cfg.policy(), None, &registered.key_id) {
+            eprintln!("warning: registered key {} of server {} is not valid; skipped", &registered.key_id, &registered.uuid);
+            declined += 1;
+            continue;
+        }
+        if let Err(e) = check_shared_key(cfg, &registered.uuid, &registered.key_id, false) {
+            eprintln!("warning: {}; skipped {}", e, &registered.uuid);
+            declined += 1;
+            continue;
+        }
+        let prompt = format!("Import {} [{}]?", registered.server_name, registered.uuid.to_hyphenated_ref());
+        if !yes && !confirm(prompt.as_str()).map_err(error::AppError::new_other)? {
+            declined += 1;
+            continue;
+        }
+
+        let server_data = ServerData::new(registered.server_name, registered.key_id, trust);
+        let cert = Rc::new(registered.public_key);
+        if cfg.add(registered.uuid, server_data, |_, _| Ok(cert))? {
+            added += 1;
+        }
+    }
+
+    eprintln!("added {} servers, skipped {} (already known)", added, known);
+    if declined > 0 {
+        eprintln!("not imported: {}", declined);
+    }
+
+    Ok(())
+}
+
+
+/// warn before the signing key expires in the middle of a run
+fn warn_key_expiring(cert: &Cert, p: &dyn Policy, key_id: &KeyID) {
+    for (id, left) in pgp::expiring_signing_keys(cert, p, SystemTime::now(), pgp::EXPIRY_WARNING) {
+        if id == *key_id {
+            pgp::warn_expiring_key(cert, &id, left);
+        }
+    }
+}
+
+
+fn check_shared_key(cfg: &ServersConfig, server_uuid: &Uuid, key_id: &KeyID, allow_shared_key: bool) -> GeneralResult<()> {
+    let shared: Vec<String> = cfg.find_by_key_id(key_id)
+        .into_iter()
+        .filter(|(uuid, _)| *uuid != server_uuid)
+        .map(|(uuid, d)| format!("{} ({})", uuid, d.name))
+        .collect();
+    if !shared.is_empty() {
+        eprintln!("warning: key_id {} is already used by: {}", key_id.to_hex(), shared.join(", "));
+        if !allow_shared_key {
+            return Err(anyhow::anyhow!("key_id {} is already used; use --allow-shared-key to add anyway", key_id.to_hex()))
+        }
+    }
+    Ok(())
+}
+
+
+pub fn command_cert_list<'a>(
+    cfg: &ServersConfig,
+    min_trust: Option<&'a str>,
+    json: bool,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let min_trust = match min_trust {
+        Some(s) => parse_trust(s)?,
+        None => 0,
+    };
+    let mut entries: Vec<_> = cfg.get_data().iter().filter(|(_, d)| d.trust >= min_trust).collect();
+    entries.sort_by(|a, b| a.1.name.cmp(&b.1.name).then(a.0.cmp(b.0)));
+    let expires = |d: &ServerData| {
+        pgp::key_expiration_time(d.get_cert(), cfg.policy(), &d.key_id)
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+    };
+
+    if json {
+        let v: Vec<_> = entries.iter()
+            .map(|(uuid, d)| CertEntryJson {
+                server_uuid: uuid,
+                name: d.name.as_str(),
+                key_id: d.key_id.to_hex(),
+                trust: d.trust,
+                fingerprint: d.get_cert().fingerprint().to_hex(),
+                api_url: d.api_url.as_ref().map(|u| u.as_str()),
+                valid: pgp::check_key(d.get_cert(), cfg.policy(), None, &d.key_id),
+                expires: expires(d),
+            })
+            .collect();
+        serde_json::to_writer_pretty(&mut *out, &v).map_err(error::AppError::new_other)?;
+        writeln!(out).map_err(error::AppError::new_other)?;
+    } else {
+        for (uuid, d) in entries {
+            write!(out, "{}", ServerDataDisplay(d, uuid)).map_err(error::AppError::new_other)?;
+            writeln!(out, "   fingerprint: {}", d.get_cert().fingerprint()).map_err(error::AppError::new_other)?;
+            match expires(d) {
+                Some(t) => writeln!(out, "   expires: {}", TimestampDisplay(t)),
+                None => writeln!(out, "   expires: never"),
+            }
+            .map_err(error::AppError::new_other)?;
+            if let Some(ref api_url) = d.api_url {
+                writeln!(out, "   api_url: {}", api_url).map_err(error::AppError::new_other)?;
+            }
+            if !pgp::check_key(d.get_cert(), cfg.policy(), None, &d.key_id) {
+                writeln!(out, "   ! key is not valid under current policy (expired or revoked)").map_err(error::AppError::new_other)?;
+            }
+            writeln!(out).map_err(error::AppError::new_other)?;
+        }
+    }
+
+    Ok(())
+}
+
+
+pub fn command_cert_export<'a>(
+    cfg: &ServersConfig, 
+    server_uuid: Option<&'a str>, 
+    output: Option<&'a str>,
+) -> error::AppResult<'a> {
+
+    // exported in memory first, so a failed lookup leaves no output file behind
+    let mut buf = Vec::new();
+    if let Some(s) = server_uuid {
+        let server_uuid: Uuid = error::ArgsError::parse(s, "server_uuid", "uuid")?;
+        let (cert, _) = cfg.get_ref(&server_uuid)
+            .ok_or_else(|| anyhow::anyhow!("can not find cert for server:{}", &server_uuid))?;
+        pgp::export_publickey(cert, &mut buf)?;
+    } else {
+        pgp::export_keyring(cfg.get_data().values().map(ServerData::get_cert), &mut buf)?;
+    }
+
+    match output {
+        Some(s) if !s.is_empty() && s != "-" => {
+            crate::config::atomic_write(s, |ofile| ofile.write_all(buf.as_slice())).map_err(error::AppError::new_other)?;
+        }
+        _ => io::stdout().write_all(buf.as_slice()).map_err(error::AppError::new_other)?,
+    }
+
+    Ok(())
+}
+
+
+pub fn command_cert_set_trust<'a>(
+    cfg: &mut ServersConfig, 
+    server_uuid: &'a str, 
+    trust: Option<&'a str>,
+    name: Option<&'a str>,
+) -> error::AppResult<'a> {
+
+    let server_uuid: Uuid = error::ArgsError::parse(server_uuid, "server_uuid", "uuid")?;
+    let trust = trust.ok_or_else(|| error::ArgsError::missing("trust", "integer$(1,2,3,4,5)"))?;
+    let trust = parse_trust(trust)?;
+    let name = name.map(ToOwned::to_owned);
+
+    if cfg.set_trust(&server_uuid, trust, name) {
+        eprintln!("success.")
+    } else {
+        eprintln!("not existed.")
+    }
+
+    Ok(())
+}
+
+
+pub fn command_cert_update<'a>(
+    cfg: &mut ServersConfig, 
+    server_uuid: &'a str, 
+    api_url: Option<&'a str>,
+) -> error::AppResult<'a> {
+    
+    let server_uuid: Uuid = error::ArgsError::parse(server_uuid, "server_uuid", "uuid")?;
+    // an empty value clears the override
+    let api_url = match api_url {
+        Some(s) if !s.is_empty() => Some(parse_api_url(s, "api_url")?),
+        _ => None,
+    };
+
+    if !cfg.get_data().contains_key(&server_uuid) {
+        eprintln!("not existed.");
+        return Ok(())
+    }
+
+    if cfg.set_api_url(&server_uuid, api_url) {
+        eprintln!("success.")
+    } else {
+        eprintln!("unchanged.")
+    }
+
+    Ok(())
+}
+
+
+/**
+ * the server to remove is given by exactly one of its uuid, key id or local name
+ */
+pub fn command_cert_remove<'a>(
+    cfg: &mut ServersConfig, 
+    server_uuid: Option<&'a str>, 
+    key_id: Option<&'a str>, 
+    name: Option<&'a str>, 
+) -> error::AppResult<'a> {
+    
+    let server_uuid: Uuid = match (server_uuid, key_id, name) {
+        (Some(s), None, None) => error::ArgsError::parse(s, "server_uuid", "uuid")?,
+        (None, Some(s), None) => {
+            let key_id: KeyID = error::ArgsError::parse(s, "key_id", "hex")?;
+            let candidates = cfg.find_by_key_id(&key_id);
+            single_server(candidates.as_slice(), "key id", s)?
+        }
+        (None, None, Some(s)) => {
+            let candidates: Vec<(&Uuid, &ServerData)> = cfg.get_data().iter().filter(|(_, d)| d.name == s).collect();
+            single_server(candidates.as_slice(), "name", s)?
+        }
+        _ => return Err(error::ArgsError::missing("server_uuid|key_id|name", "exactly one of --server-uuid, --key-id and --name").into())
+    };
+
+    let success = cfg.remove(&server_uuid);
+
+    if success {
+        eprintln!("success.")
+    } else {
+        eprintln!("not existed.")
+    }
+
+    Ok(())
+}
+
+fn single_server(candidates: &[(&Uuid, &ServerData)], by: &str, value: &str) -> GeneralResult<Uuid> {
+    match candidates {
+        [] => Err(anyhow::anyhow!("no added server has {} {}", by, value)),
+        [(server_uuid, _)] => Ok(**server_uuid),
+        _ => {
+            let list: Vec<String> = candidates.iter()
+                .map(|(server_uuid, d)| format!("  {} {} {}", server_uuid.to_hyphenated_ref(), d.key_id.to_hex(), d.name))
+                .collect();
+            Err(anyhow::anyhow!("ambiguous {} {}, specify --server-uuid of one of:\n{}", by, value, list.join("\n")))
+        }
+    }
+}
+
+
+pub fn command_server_list<'a>(
+    cfg: &ClientConfig,
+    names: &mut registry::RegistryNames,
+    httpc: &http::Client,
+    limit: Option<&'a str>,
+    filter: Option<&'a str>,
+    key_id: Option<&'a str>,
+    sort: Option<&'a str>,
+    show_keys: bool,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+      
+    let limit = if let Some(s) = limit {
+        Some(error::ArgsError::parse(s, "limit", "unsigned integer")?)
+    } else {
+        None
+    };
+    let by_name = match sort {
+        Some("name") => Some(true),
+        Some("id") => Some(false),
+        Some(s) => return Err(error::ArgsError::new("sort", "name|id", s).into()),
+        None => None,
+    };
+    let key_id: Option<KeyID> = match key_id {
+        Some(s) => Some(error::ArgsError::parse(s, "key_id", "hex")?),
+        None => None,
+    };
+
+    let cfg_data = cfg.get_data();
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url" )?;
+
+    let req = api::ServerListRequest::new(limit);
+    let s = httpc.request::<api::ServerListRequest, api::ServerListResponse>(api_url, req)?;
+    names.update(s.servers.as_slice());
+
+    // done here rather than by query parameters, which registries may not support
+    let filter = filter.map(str::to_lowercase);
+    let mut servers: Vec<&api::ServerData> = s.servers.iter()
+        .filter(|d| filter.as_ref().map_or(true, |f| d.server_name.to_lowercase().contains(f.as_str())))
+        .filter(|d| key_id.as_ref().map_or(true, |k| &d.key_id == k))
+        .collect();
+    if filter.is_some() || key_id.is_some() {
+        eprintln!("Showing {} of {} total servers", servers.len(), s.servers.len());
+    }
+    match by_name {
+        Some(true) => servers.sort_by(|a, b| a.server_name.to_lowercase().cmp(&b.server_name.to_lowercase()).then(a.id.cmp(&b.id))),
+        Some(false) => servers.sort_by_key(|d| d.id),
+        None => {}
+    }
+    
+    struct ServerDataDisplay<'a>(&'a api::ServerData, bool);
+
+    impl<'a> fmt::Display for ServerDataDisplay<'a> {
+        
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let data = self.0;  
+            f.write_fmt(format_args!("server_name:{}\nserver_uuid: {}\nkey_id:{}\n", data.server_name.as_str(), data.uuid.to_hyphenated_ref(), data.key_id))?;
+            if self.1 {
+                let mut buf = Vec::new();
+                if let Ok(_) = pgp::export_publickey(&data.public_key, &mut buf) {
+                    f.write_str("\n")?;
+                    f.write_str(String::from_utf8_lossy(buf.as_slice()).borrow())?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    match format {
+        OutputFormat::Plain => {
+            for d in servers.iter() {
+                writeln!(out, "====================\n{}", ServerDataDisplay(d, show_keys)).map_err(error::AppError::new_other)?;
+            }
+        }
+        OutputFormat::Json => {
+            let v: Vec<_> = servers.iter()
+                .map(|d| {
+                    let mut json = ServerDataJson::from(*d);
+                    if !show_keys {
+                        json.public_key.clear();
+                    }
+                    json
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut *out, &v).map_err(error::AppError::new_other)?;
+            writeln!(out).map_err(error::AppError::new_other)?;
+        }
+        OutputFormat::Csv => {
+            writeln!(out, "{}", ServerDataJson::CSV_HEADER).map_err(error::AppError::new_other)?;
+            for d in servers.iter() {
+                writeln!(out, "{}", ServerDataJson::from(*d).csv_row()).map_err(error::AppError::new_other)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn command_server_get<'a>(
+    cfg: &ClientConfig,
+    servers: &ServersConfig,
+    names: &mut registry::RegistryNames,
+    httpc: &http::Client,
+    server_uuid: &'a str,
+    show_key: bool,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let server_uuid: Uuid = error::ArgsError::parse(server_uuid, "uuid", "uuid")?;
+
+    let cfg_data = cfg.get_data();
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url" )?;
+
+    let req = api::GetServerRequest::new(server_uuid);
+    let s = httpc.request::<api::GetServerRequest, api::GetServerResponse>(api_url, req)?.server;
+    names.update(std::slice::from_ref(&s));
+
+    let fingerprint = s.public_key.fingerprint();
+    let local = servers.get_data().get(&s.uuid).and_then(|d| d.cert().map(|c| (d.name.clone(), c.fingerprint())));
+
+    match format {
+        OutputFormat::Plain => {
+            writeln!(out, "server_name: {}", s.server_name).map_err(error::AppError::new_other)?;
+            writeln!(out, "server_uuid: {}", s.uuid.to_hyphenated_ref()).map_err(error::AppError::new_other)?;
+            writeln!(out, "key_id: {}", s.key_id).map_err(error::AppError::new_other)?;
+            writeln!(out, "fingerprint: {}", fingerprint).map_err(error::AppError::new_other)?;
+            match local {
+                Some((ref name, ref local_fingerprint)) if local_fingerprint == &fingerprint => {
+                    writeln!(out, "local cert: {} (matches)", name).map_err(error::AppError::new_other)?;
+                }
+                Some((ref name, ref local_fingerprint)) => {
+                    writeln!(out, "local cert: {} (DIFFERS, stored fingerprint {})", name, local_fingerprint).map_err(error::AppError::new_other)?;
+                }
+                None => {
+                    writeln!(out, "local cert: none").map_err(error::AppError::new_other)?;
+                }
+            }
+            if show_key {
+                let mut buf = Vec::new();
+                pgp::export_publickey(&s.public_key, &mut buf)?;
+                writeln!(out, "\n{}", String::from_utf8_lossy(buf.as_slice())).map_err(error::AppError::new_other)?;
+            }
+        }
+        OutputFormat::Json => {
+            let mut server = ServerDataJson::from(&s);
+            if !show_key {
+                server.public_key.clear();
+            }
+            let v = output::ServerGetJson {
+                server,
+                fingerprint: fingerprint.to_hex(),
+                matches_local_cert: local.as_ref().map(|(_, f)| f == &fingerprint),
+            };
+            output::write_json(out, &v).map_err(error::AppError::new_other)?;
+        }
+        OutputFormat::Csv => {
+            writeln!(out, "{}", ServerDataJson::CSV_HEADER).map_err(error::AppError::new_other)?;
+            writeln!(out, "{}", ServerDataJson::from(&s).csv_row()).map_err(error::AppError::new_other)?;
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * look up the configured server_uuid in the registry, to tell whether the registration is still there
+ */
+pub fn command_server_info<'a>(
+    cfg: &ClientConfig,
+    names: &mut registry::RegistryNames,
+    httpc: &http::Client,
+    show_key: bool,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let cfg_data = cfg.get_data();
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url" )?;
+    let server_uuid = error::ConfigMissing::ok(cfg_data.server_uuid, "client.server_uuid")?;
+
+    let req = api::ServerInfoRequest::new(server_uuid);
+    let s = match httpc.request::<api::ServerInfoRequest, api::ServerInfoResponse>(api_url, req) {
+        Ok(res) => res,
+        Err(http::RequsetError::Response(r)) if r.code == 404 => {
+            eprintln!("server not registered or was unregistered");
+            return Err(error::ConfigMissing::new("client.server_uuid").into())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    names.insert(&s.uuid, &s.server_name);
+
+    let fingerprint = s.public_key.fingerprint();
+    let matches_config_key = cfg_data.key_id.as_ref() == Some(&s.key_id)
+        && cfg_data.get_cert().map(Cert::fingerprint).as_ref() == Some(&fingerprint);
+
+    let mut public_key = Vec::new();
+    if show_key {
+        pgp::export_publickey(&s.public_key, &mut public_key)?;
+    }
+
+    match format {
+        OutputFormat::Plain => {
+            writeln!(out, "server_name: {}", s.server_name).map_err(error::AppError::new_other)?;
+            writeln!(out, "server_uuid: {}", s.uuid.to_hyphenated_ref()).map_err(error::AppError::new_other)?;
+            writeln!(out, "key_id: {}", s.key_id).map_err(error::AppError::new_other)?;
+            writeln!(out, "fingerprint: {}", fingerprint).map_err(error::AppError::new_other)?;
+            if matches_config_key {
+                writeln!(out, "status: registered with the configured key").map_err(error::AppError::new_other)?;
+            } else {
+                writeln!(out, "status: registered, but NOT with the configured key").map_err(error::AppError::new_other)?;
+            }
+            if show_key {
+                writeln!(out, "\n{}", String::from_utf8_lossy(public_key.as_slice())).map_err(error::AppError::new_other)?;
+            }
+        }
+        OutputFormat::Json | OutputFormat::Csv => {
+            let v = output::ServerInfoJson {
+                server_name: s.server_name.as_str(),
+                uuid: &s.uuid,
+                key_id: s.key_id.to_hex(),
+                public_key: String::from_utf8_lossy(public_key.as_slice()).into_owned(),
+                fingerprint: fingerprint.to_hex(),
+                registered: true,
+                matches_config_key,
+            };
+            if let OutputFormat::Json = format {
+                output::write_json(out, &v).map_err(error::AppError::new_other)?;
+            } else {
+                writeln!(out, "{}", output::ServerInfoJson::CSV_HEADER).map_err(error::AppError::new_other)?;
+                writeln!(out, "{}", v.csv_row()).map_err(error::AppError::new_other)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn transfer(r: &mut dyn io::Read) -> anyhow::Result<api::SubmitContent> {
+    api::ReadFrom::read_from(r)
+}
+
+pub fn command_get_submit<'a> (
+    cfg: &ClientConfig,
+    servers: &ServersConfig,
+    names: &mut registry::RegistryNames,
+    resolve_names: bool,
+    httpc: &http::Client,
+    record_uuid: &'a str,
+    format: OutputFormat,
+    output: Option<&'a str>,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let record_uuid: Uuid = error::ArgsError::parse(record_uuid, "record_uuid", "uuid")?;
+    let mut buf = Vec::new();
+    let out: &mut dyn io::Write = if output.is_some() { &mut buf } else { out };
+    
+    let cfg_data = cfg.get_data();
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
+
+    let req = api::GetSubmitRequest::new(record_uuid);
+    let s = httpc.request::<api::GetSubmitRequest, api::GetSubmitResponse>(api_url, req)?;
+
+    let trust = servers.get_data().get(&s.server_uuid).map(|d| d.trust);
+    let v = if let Some((cert, key_id)) = servers.get_ref(&s.server_uuid) {
+        pgp::verify_detailed(cert, key_id, servers.policy(), None, s.content.as_bytes(), transfer)
+    } else {
+        Err(pgp::VerifyError::new(
+            pgp::UnverifiedReason::MissingCert,
+            anyhow::anyhow!("can not find cert for server:{}", &s.server_uuid)
+        ))
+    };
+
+    let registry_name = names.resolve(&s.server_uuid, if resolve_names { Some((httpc, api_url)) } else { None });
+    let mut record = match v {
+        Ok(ref d) => SubmitRecordJson::verified(&s.uuid, d, trust),
+        Err(ref e) => SubmitRecordJson::unverified(&s.uuid, &s.server_uuid, trust, e.reason),
+    };
+    record.registry_name = registry_name;
+    if output.is_some() && v.is_err() {
+        record.raw_content = Some(s.content.as_str());
+    }
+    match format {
+        OutputFormat::Plain => {
+            if let Ok(ref d) = v {
+                writeln!(out, "+ Verified Message").map_err(error::AppError::new_other)?;
+                writeln!(out, "{}", ServerDataDisplay(servers.get_data().get(&s.server_uuid).unwrap(), &s.server_uuid)).map_err(error::AppError::new_other)?;
+                if let Some(name) = registry_name {
+                    writeln!(out, "registry name: {}\n", name).map_err(error::AppError::new_other)?;
+                }
+                writeln!(out, "{:#?}", &d.value).map_err(error::AppError::new_other)?;
+            } else if output.is_some() {
+                write_unverified(out, &s.uuid, &s.server_uuid, &s.content).map_err(error::AppError::new_other)?;
+            }
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, &record).map_err(error::AppError::new_other)?;
+            writeln!(out).map_err(error::AppError::new_other)?;
+        }
+        OutputFormat::Csv => {
+            writeln!(out, "{}", SubmitRecordJson::CSV_HEADER).map_err(error::AppError::new_other)?;
+            writeln!(out, "{}", record.csv_row()).map_err(error::AppError::new_other)?;
+        }
+    }
+    if let Some(path) = output {
+        write_report(path, buf.as_slice())?;
+    }
+
+    if let Err(e) = v {
+        match registry_name {
+            Some(name) => eprintln!("server_uuid: {} (registry name: {})\n", s.server_uuid.to_hyphenated_ref(), name),
+            None => eprintln!("server_uuid: {}\n", s.server_uuid.to_hyphenated_ref()),
+        }
+        eprintln!("{}\n", &s.content);
+        return Err(anyhow::Error::from(e).into())
+    }
+
+    Ok(())
+}
+
+/**
+ * submits about one player from every server in the registry, each verified against the added server whose key signed it;
+ * submits of servers that are not added are listed as unverified
+ */
+pub fn command_get_player_submit<'a>(
+    cfg: &ClientConfig,
+    servers: &ServersConfig,
+    names: &mut registry::RegistryNames,
+    resolve_names: bool,
+    httpc: &http::Client,
+    player_uuid: &'a str,
+    format: OutputFormat,
+    output: Option<&'a str>,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let player_uuid: Uuid = error::ArgsError::parse(player_uuid, "player", "uuid")?;
+    let mut buf = Vec::new();
+    let out: &mut dyn io::Write = if output.is_some() { &mut buf } else { out };
+
+    let cfg_data = cfg.get_data();
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
+
+    let req = api::GetPlayerSubmitRequest::new(player_uuid);
+    let sc = httpc.request::<api::GetPlayerSubmitRequest, api::GetServerSubmitResponse>(api_url, req)?;
+
+    // the server_uuid of a response is only a claim; the signing key decides which added server vouches for a submit
+    let verify = |content: &str| -> Result<(&Uuid, pgp::Verified<api::SubmitContent>), pgp::VerifyError> {
+        let issuers = pgp::signature_issuers(content.as_bytes(), servers.policy())
+            .map_err(|e| pgp::VerifyError::new(pgp::UnverifiedReason::MissingCert, e))?;
+        let mut last_err = None;
+        for (server_uuid, d) in issuers.iter().flat_map(|key_id| servers.find_by_key_id(key_id)) {
+            match pgp::verify_detailed(d.get_cert(), &d.key_id, servers.policy(), None, content.as_bytes(), transfer) {
+                Ok(v) => return Ok((server_uuid, v)),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| pgp::VerifyError::new(
+            pgp::UnverifiedReason::MissingCert,
+            anyhow::anyhow!("no added server has the signing key")
+        )))
+    };
+
+    let submits: Vec<_> = sc.submits.iter().map(|s| (s, verify(s.content.as_str()))).collect();
+    let verified = submits.iter().filter(|(_, v)| v.is_ok()).count();
+    for (s, v) in submits.iter() {
+        match v {
+            Ok((signer, d)) if *signer != &s.server_uuid || &d.value.uuid != *signer => {
+                eprintln!("warning: submit {} claims server {}, but was signed by the key of server {}", s.uuid, s.server_uuid, signer);
+            }
+            Ok((_, d)) if d.value.player_uuid != player_uuid => {
+                eprintln!("warning: submit {} is about player {}, not {}", s.uuid, d.value.player_uuid, player_uuid);
+            }
+            _ => {}
+        }
+    }
+
+    let mut registry_names = HashMap::new();
+    for (s, v) in submits.iter() {
+        let server_uuid = v.as_ref().map_or(&s.server_uuid, |(signer, _)| *signer);
+        if !registry_names.contains_key(server_uuid) {
+            let name = names.resolve(server_uuid, if resolve_names { Some((httpc, api_url)) } else { None }).map(str::to_owned);
+            registry_names.insert(*server_uuid, name);
+        }
+    }
+    let registry_name = |server_uuid: &Uuid| registry_names.get(server_uuid).and_then(|n| n.as_deref());
+
+    match format {
+        OutputFormat::Plain => {
+            for (s, v) in submits.iter() {
+                match v {
+                    Ok((signer, d)) => {
+                        writeln!(out, "+ Verified Message").map_err(error::AppError::new_other)?;
+                        writeln!(out, "{}", ServerDataDisplay(servers.get_data().get(*signer).unwrap(), signer)).map_err(error::AppError::new_other)?;
+                        if let Some(name) = registry_name(signer) {
+                            writeln!(out, "registry name: {}\n", name).map_err(error::AppError::new_other)?;
+                        }
+                        writeln!(out, "{:#?}", &d.value).map_err(error::AppError::new_other)?;
+                    }
+                    Err(e) => {
+                        writeln!(out, "- Un-Verified Message ({})", e).map_err(error::AppError::new_other)?;
+                        if let Some(name) = registry_name(&s.server_uuid) {
+                            writeln!(out, "registry name: {}", name).map_err(error::AppError::new_other)?;
+                        }
+                        writeln!(out, "uuid: {}\nserver_uuid: {}", s.uuid.to_hyphenated_ref(), s.server_uuid.to_hyphenated_ref()).map_err(error::AppError::new_other)?;
+                        if output.is_some() {
+                            writeln!(out, "{}", &s.content).map_err(error::AppError::new_other)?;
+                        }
+                        writeln!(out).map_err(error::AppError::new_other)?;
+                    }
+                }
+            }
+        }
+        OutputFormat::Json | OutputFormat::Csv => {
+            let records: Vec<_> = 
+                submits
+                    .iter()
+                    .map(
+                        |(s, v)| {
+                            let mut record = match v {
+                                Ok((signer, d)) => {
+                                    let mut record = SubmitRecordJson::verified(&s.uuid, d, servers.get_data().get(*signer).map(|d| d.trust));
+                                    record.server_uuid = signer;
+                                    record
+                                }
+                                Err(e) => SubmitRecordJson::unverified(&s.uuid, &s.server_uuid, None, e.reason),
+                            };
+                            record.registry_name = registry_name(record.server_uuid);
+                            if output.is_some() && v.is_err() {
+                                record.raw_content = Some(s.content.as_str());
+                            }
+                            record
+                        }
+                    )
+                    .collect();
+            if let OutputFormat::Json = format {
+                serde_json::to_writer_pretty(&mut *out, &records).map_err(error::AppError::new_other)?;
+                writeln!(out).map_err(error::AppError::new_other)?;
+            } else {
+                writeln!(out, "{}", SubmitRecordJson::CSV_HEADER).map_err(error::AppError::new_other)?;
+                for record in records.iter() {
+                    writeln!(out, "{}", record.csv_row()).map_err(error::AppError::new_other)?;
+                }
+            }
+        }
+    }
+    if let Some(path) = output {
+        write_report(path, buf.as_slice())?;
+    }
+    eprintln!("{} submits for player {}: {} verified, {} unverifiable", submits.len(), player_uuid.to_hyphenated_ref(), verified, submits.len() - verified);
+
+    Ok(())
+}
+
+/**
+ * verify a saved submit content offline, against the cert of the server whose key issued the signature
+ */
+pub fn command_verify_file<'a>(
+    servers: &ServersConfig,
+    path: &'a str,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let mut content = Vec::new();
+    if path.is_empty() || path == "-" {
+        io::Read::read_to_end(&mut io::stdin(), &mut content).map_err(error::AppError::new_other)?;
+    } else {
+        content = fs::read(path).map_err(error::AppError::new_other)?;
+    }
+
+    let issuers = pgp::signature_issuers(content.as_slice(), servers.policy())
+        .map_err(|e| anyhow::anyhow!("{} is not a signed message: {}", path, e))?;
+    let candidates: Vec<_> = issuers.iter()
+        .flat_map(|key_id| servers.find_by_key_id(key_id))
+        .collect();
+    if candidates.is_empty() {
+        let issuers: Vec<_> = issuers.iter().map(KeyID::to_hex).collect();
+        return Err(anyhow::Error::from(pgp::VerifyError::new(
+            pgp::UnverifiedReason::MissingCert,
+            anyhow::anyhow!("no cert in servers config for signing key {}", issuers.join(", "))
+        )).into())
+    }
+
+    let mut last_err = None;
+    for (server_uuid, d) in candidates {
+        match pgp::verify_detailed(d.get_cert(), &d.key_id, servers.policy(), None, content.as_slice(), transfer) {
+            Ok(v) => {
+                writeln!(out, "+ Verified Message").map_err(error::AppError::new_other)?;
+                writeln!(out, "{}", ServerDataDisplay(d, server_uuid)).map_err(error::AppError::new_other)?;
+                writeln!(out, "{:#?}", &v.value).map_err(error::AppError::new_other)?;
+                if &v.value.uuid != server_uuid {
+                    eprintln!("warning: content claims server {}, but was signed by the key of server {}", v.value.uuid, server_uuid);
+                }
+                return Ok(())
+            }
+            Err(e) => last_err = Some(e),
+        }
+    }
+
+    Err(anyhow::Error::from(last_err.unwrap()).into())
+}
+
+struct ServerDataDisplay<'a>(&'a ServerData, &'a Uuid);
+
+impl<'a> fmt::Display for ServerDataDisplay<'a> {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "server: {} [{}]\n   key: {}   trust: {}\n", 
+            self.0.name.as_str(),
+            self.1.to_hyphenated_ref(),
+            self.0.key_id,
+            self.0.trust
+        ))
+    }
+}
+
+
+/**
+ * `--after` and `--before` of `record`; both given make a closed window, so it must not be empty
+ */
+fn parse_time_window<'a>(after: Option<&'a str>, before: Option<&'a str>) -> Result<(Option<u64>, Option<u64>), error::ArgsError<'a>> {
+    let after_time = parse_datetime(after, "after")?;
+    let before_time = parse_datetime(before, "before")?;
+    if let (Some(a), Some(b), Some(s)) = (after_time, before_time, before) {
+        if b < a {
+            return Err(error::ArgsError::new("before", "time{YYYY-mm-dd HH:MM:SS} not earlier than --after", s))
+        }
+    }
+    Ok((after_time, before_time))
+}
+
+fn parse_datetime<'a>(s: Option<&'a str>, name: &'static str) -> Result<Option<u64>, error::ArgsError<'a>> {
+    use chrono::NaiveDateTime;
+
+    if let Some(s) = s {
+        match NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
+            Ok(datetime) => {
+                let t = datetime.timestamp();
+                if t > 0 {
+                    Ok(Some(t as u64))
+                } else {
+                    Ok(None)
+                }
+            }   
+            Err(e) => {
+                Err(error::ArgsError::new(name, "time{YYYY-mm-dd HH:MM:SS}", s))
+            }
+        }           
+    } else {
+        Ok(None)
+    }
+}
+
+
+/**
+ * where `submit --player-name` looks the name up; Mojang only through the cache and its limits
+ */
+pub enum NameSource<'a, 'c> {
+    UserCache(&'a str),
+    Online(&'c mut namecache::NameCache),
+}
+
+fn resolve_player_name<'a>(httpc: &http::Client, name: &'a str, source: NameSource<'a, '_>) -> Result<Uuid, error::AppError<'a>> {
+    let not_found = || error::ArgsError::new("player_name", "name of a player known to the usercache or Mojang", name);
+    if !mojang::is_valid_name(name) {
+        return Err(error::ArgsError::new("player_name", "1 to 16 letters, digits or _", name).into())
+    }
+    match source {
+        NameSource::UserCache(path) => {
+            let usercache = banlist::UserCache::load(path)?;
+            Ok(usercache.get(name).cloned().ok_or_else(not_found)?)
+        }
+        NameSource::Online(cache) => {
+            let mut resolver = namecache::NameResolver::new(cache, httpc, &namecache::SystemClock);
+            let resolved = resolver.resolve(name);
+            log_info!("{}", resolver.report());
+            match resolved {
+                Ok(Some(uuid)) => Ok(uuid),
+                Ok(None) => Err(not_found().into()),
+                Err(e) => Err(anyhow::anyhow!("can not look up player {}: {}", name, e).into()),
+            }
+        }
+    }
+}
+
+
+/// clocks of the signer and the registry may differ a little
+const TIMESTAMP_SKEW: u64 = 300;
+
+/**
+ * a unix timestamp or `YYYY-mm-dd HH:MM:SS`, not later than now (give or take `TIMESTAMP_SKEW`)
+ */
+fn parse_past_timestamp<'a>(s: Option<&'a str>, name: &'static str) -> Result<Option<u64>, error::ArgsError<'a>> {
+    let s = match s {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    let t = match u64::from_str(s) {
+        Ok(t) => t,
+        Err(_) => parse_datetime(Some(s), name)
+            .map_err(|_| error::ArgsError::new(name, "unix timestamp|time{YYYY-mm-dd HH:MM:SS}", s))?
+            .ok_or_else(|| error::ArgsError::new(name, "unix timestamp|time{YYYY-mm-dd HH:MM:SS}", s))?,
+    };
+    if t > timestamp(SystemTime::now()).unwrap() + TIMESTAMP_SKEW {
+        return Err(error::ArgsError::new(name, "time not in the future", s))
+    }
+    Ok(Some(t))
+}
+
+
+pub enum ServerHandleWrap<'a> {
+    UUID(&'a str),
+    KeyID(&'a str),
+}
+
+pub fn command_get_server_submit<'a>(
+    cfg: &ClientConfig,
+    servers: &ServersConfig,
+    names: &mut registry::RegistryNames,
+    resolve_names: bool,
+    httpc: &http::Client,
+    server_handle: ServerHandleWrap<'a>,
+    limit: Option<&'a str>,
+    after: Option<&'a str>,
+    before: Option<&'a str>,
+    player_uuid: Option<&'a str>,
+    max: Option<&'a str>,
+    format: OutputFormat,
+    output: Option<&'a str>,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let (uuid, cert, key_id, handle) = match server_handle {
+        ServerHandleWrap::UUID(s) => {
+            let server_uuid: Uuid = error::ArgsError::parse(s, "server_uuid", "uuid")?;
+            let (cert, key_id) = servers.get_ref(&server_uuid)
+                    .ok_or_else(|| anyhow::anyhow!("can not find cert for server:{}", &server_uuid))?;
+            (server_uuid, cert, key_id.clone(), api::ServerHandle::ServerUUID(server_uuid))
+        },
+        ServerHandleWrap::KeyID(s) => {
+            let key_id: KeyID = error::ArgsError::parse(s, "key_id", "hex")?;
+            match servers.find_by_key_id(&key_id).as_slice() {
+                [] => {
+                    return Err(anyhow::anyhow!("can not find cert for key_id:{}", &key_id).into())
+                }
+                [(server_uuid, d)] => {
+                    (**server_uuid, d.get_cert(), key_id.clone(), api::ServerHandle::KeyID(key_id.clone()))
+                }
+                _ => {
+                    return Err(anyhow::anyhow!("ambiguous key id {}, specify --server-uuid", &key_id).into())
+                }
+            }
+        },
+    };
+    let limit: Option<usize> = if let Some(s) = limit {
+        Some(error::ArgsError::parse(s, "limit", "unsigned integer")?)
+    } else {
+        None
+    };
+    let max: Option<usize> = if let Some(s) = max {
+        Some(error::ArgsError::parse(s, "max", "unsigned integer")?)
+    } else {
+        None
+    };
+    let (after, before) = parse_time_window(after, before)?;
+    let player_uuid: Option<Uuid> = match player_uuid {
+        Some(s) => Some(error::ArgsError::parse(s, "player", "uuid")?),
+        None => None,
+    };
+
+    let cfg_data = cfg.get_data();
+    let api_url = match servers.get_data().get(&uuid).and_then(|d| d.api_url.as_ref()) {
+        Some(api_url) => api_url,
+        None => error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?
+    };
+
+    fn transfer(r: &mut dyn io::Read) -> GeneralResult<api::SubmitContent> {
+        api::ReadFrom::read_from(r)
+    }
+
+    let trust = servers.get_data().get(&uuid).map(|d| d.trust);
+    let registry_name = names.resolve(&uuid, if resolve_names { Some((httpc, cfg_data.api_url.as_ref().unwrap_or(api_url))) } else { None });
+
+    let write_pages = |out: &mut dyn io::Write| -> error::AppResult<'a> {
+        match format {
+            OutputFormat::Plain => {
+                writeln!(out, "+ Verified Message").map_err(error::AppError::new_other)?;
+                writeln!(out, "{}", ServerDataDisplay(servers.get_data().get(&uuid).unwrap(), &uuid)).map_err(error::AppError::new_other)?;
+                if let Some(name) = registry_name {
+                    writeln!(out, "registry name: {}", name).map_err(error::AppError::new_other)?;
+                }
+                writeln!(out, "endpoint: {}\n", api_url).map_err(error::AppError::new_other)?;
+            }
+            OutputFormat::Json => {
+                write!(out, "[").map_err(error::AppError::new_other)?;
+            }
+            OutputFormat::Csv => {
+                writeln!(out, "{}", SubmitRecordJson::CSV_HEADER).map_err(error::AppError::new_other)?;
+            }
+        }
+
+        // the server caps the submits of one response; a full page is followed by the next one, its window moved past
+        // the raw timestamps of every submit on the page, verified or not; submits seen before are dropped by uuid
+        let mut cursor = PageCursor::new(after, before);
+        let mut page_size = limit;
+        let mut seen: HashSet<Uuid> = HashSet::new();
+        let mut shown = 0;
+        loop {
+            let req = api::GetServerSubmitRequest::new(handle.clone(), limit, cursor.after, cursor.before, player_uuid);
+            let sc = httpc.request::<api::GetServerSubmitRequest, api::GetServerSubmitResponse>(api_url, req)?;
+            let page_len = sc.submits.len();
+            // without --limit the size of the first page stands for the server's cap
+            let page_size = *page_size.get_or_insert(page_len);
+            let timestamps: Vec<u64> = sc.submits.iter()
+                .filter_map(|s| pgp::read_unverified(s.content.as_bytes(), servers.policy(), transfer).ok())
+                .map(|c| c.timestamp)
+                .collect();
+
+            let mut fresh: Vec<_> = sc.submits.iter().filter(|s| seen.insert(s.uuid)).collect();
+            let capped = match max {
+                Some(max) if seen.len() >= max => {
+                    fresh.truncate(fresh.len() - (seen.len() - max));
+                    true
+                }
+                _ => false,
+            };
+
+            for s in fresh {
+                let v = pgp::verify_detailed(cert, &key_id, servers.policy(), None, s.content.as_bytes(), transfer);
+                if let Err(ref e) = v {
+                    match registry_name {
+                        Some(name) => eprintln!("Un-Verified Message from registry name {} ({})", name, e),
+                        None => eprintln!("Un-Verified Message ({})", e),
+                    }
+                    eprintln!("{}\n", &s.content);
+                }
+                // servers older than the `player` parameter send every submit; only verified ones tell the player
+                match (player_uuid, &v) {
+                    (Some(p), Ok(d)) if d.value.player_uuid != p => continue,
+                    (Some(_), Err(_)) => continue,
+                    _ => {}
+                }
+                match format {
+                    OutputFormat::Plain => match &v {
+                        Ok(d) => writeln!(out, "{:#?}", &d.value).map_err(error::AppError::new_other)?,
+                        Err(_) if output.is_some() => write_unverified(out, &s.uuid, &uuid, &s.content).map_err(error::AppError::new_other)?,
+                        Err(_) => {}
+                    },
+                    OutputFormat::Json | OutputFormat::Csv => {
+                        let mut record = match &v {
+                            Ok(d) => SubmitRecordJson::verified(&s.uuid, d, trust),
+                            Err(e) => SubmitRecordJson::unverified(&s.uuid, &uuid, trust, e.reason),
+                        };
+                        record.registry_name = registry_name;
+                        if output.is_some() && v.is_err() {
+                            record.raw_content = Some(s.content.as_str());
+                        }
+                        if let OutputFormat::Json = format {
+                            let json = serde_json::to_string_pretty(&record).map_err(error::AppError::new_other)?;
+                            write!(out, "{}{}", if shown == 0 { "\n" } else { ",\n" }, json).map_err(error::AppError::new_other)?;
+                        } else {
+                            writeln!(out, "{}", record.csv_row()).map_err(error::AppError::new_other)?;
+                        }
+                    }
+                }
+                shown += 1;
+            }
+
+            if capped || page_len == 0 || page_len < page_size {
+                break;
+            }
+            if !cursor.advance(&timestamps) {
+                eprintln!("warning: stopped after {} submits, the time window of the last page can not be moved on", seen.len());
+                break;
+            }
+        }
+
+        if let OutputFormat::Json = format {
+            writeln!(out, "{}]", if shown == 0 { "" } else { "\n" }).map_err(error::AppError::new_other)?;
+        }
+        if let Some(p) = player_uuid {
+            eprintln!("Showing {} records for player {}", shown, p.to_hyphenated_ref());
+        }
+        Ok(())
+    };
+
+    match output {
+        Some(path) => stream_report(path, write_pages),
+        None => write_pages(out),
+    }
+}
+
+/**
+ * the time window of `record --server-uuid` pages; the registry may send a page oldest or newest first, so the
+ * window is moved past the last submit in the order it was sent, keeping that second so submits sharing it are not lost
+ */
+struct PageCursor {
+    after: Option<u64>,
+    before: Option<u64>,
+    descending: bool,
+}
+
+impl PageCursor {
+
+    fn new(after: Option<u64>, before: Option<u64>) -> Self {
+        PageCursor { after, before, descending: false }
+    }
+
+    /// false when the window would not move, e.g. a full page of submits all of one second
+    fn advance(&mut self, timestamps: &[u64]) -> bool {
+        let (first, last) = match (timestamps.first(), timestamps.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return false,
+        };
+        if first != last {
+            self.descending = last < first;
+        }
+        if self.descending {
+            let before = timestamps.iter().min().unwrap() + 1;
+            if self.before.map_or(false, |b| before >= b) {
+                return false
+            }
+            self.before = Some(before);
+        } else {
+            let after = timestamps.iter().max().unwrap().saturating_sub(1);
+            if self.after.map_or(false, |a| after <= a) {
+                return false
+            }
+            self.after = Some(after);
+        }
+        true
+    }
+}
+
+/**
+ * uuids of every submit the registry serves for the server, paged like `record --server-uuid`; a listing that can
+ * not be completed is an error, as a submit missing from it would be taken for one gone from the registry
+ */
+fn list_server_submits<'a>(
+    httpc: &http::Client,
+    api_url: &Url,
+    handle: api::ServerHandle,
+    policy: &dyn Policy,
+) -> Result<HashSet<Uuid>, error::AppError<'a>> {
+
+    fn transfer(r: &mut dyn io::Read) -> GeneralResult<api::SubmitContent> {
+        api::ReadFrom::read_from(r)
+    }
+
+    let mut cursor = PageCursor::new(None, None);
+    let mut page_size = None;
+    let mut seen = HashSet::new();
+    loop {
+        let req = api::GetServerSubmitRequest::new(handle.clone(), None, cursor.after, cursor.before, None);
+        let sc = httpc.request::<api::GetServerSubmitRequest, api::GetServerSubmitResponse>(api_url, req)?;
+        let page_len = sc.submits.len();
+        let page_size = *page_size.get_or_insert(page_len);
+        let timestamps: Vec<u64> = sc.submits.iter()
+            .filter_map(|s| pgp::read_unverified(s.content.as_bytes(), policy, transfer).ok())
+            .map(|c| c.timestamp)
+            .collect();
+        seen.extend(sc.submits.iter().map(|s| s.uuid));
+
+        if page_len == 0 || page_len < page_size {
+            return Ok(seen)
+        }
+        if !cursor.advance(&timestamps) {
+            return Err(anyhow::anyhow!(
+                "can not list every submit after {} of them: the time window of the last page can not be moved on",
+                seen.len()
+            ).into())
+        }
+    }
+}
+
+/// unverified submits go to a `record -o` report too, marked so they are not taken for verified ones
+fn write_unverified(out: &mut dyn io::Write, uuid: &Uuid, server_uuid: &Uuid, content: &str) -> io::Result<()> {
+    writeln!(out, "- Un-Verified Message")?;
+    writeln!(out, "uuid: {}\nserver_uuid: {}", uuid.to_hyphenated_ref(), server_uuid.to_hyphenated_ref())?;
+    writeln!(out, "{}\n", content)
+}
+
+/// written whole, so a crash does not leave half a report
+fn write_report<'a>(path: &'a str, buf: &[u8]) -> error::AppResult<'a> {
+    crate::config::atomic_write(path, |ofile| ofile.write_all(buf)).map_err(error::AppError::new_other)?;
+    eprintln!("written to {}", path);
+    Ok(())
+}
+
+/// like `write_report`, but written while `f` runs instead of being held in memory; a failed `f` leaves no file
+fn stream_report<'a, F>(path: &'a str, f: F) -> error::AppResult<'a>
+where
+    F: FnOnce(&mut dyn io::Write) -> error::AppResult<'a>,
+{
+    let mut failed = None;
+    let written = crate::config::atomic_write(path, |ofile| {
+        let mut w = io::BufWriter::new(ofile);
+        match f(&mut w) {
+            Ok(()) => w.flush(),
+            Err(e) => {
+                failed = Some(e);
+                Err(io::Error::new(io::ErrorKind::Other, "report not complete"))
+            }
+        }
+    });
+    if let Some(e) = failed {
+        return Err(e)
+    }
+    written.map_err(error::AppError::new_other)?;
+    eprintln!("written to {}", path);
+    Ok(())
+}
+
+
+/// one row per player, keyed by the earliest submit time seen for them so the table comes out in a stable order
+struct RecordTable<S, D> {
+    sp: char,
+    servers: Vec<S>,
+    data: HashMap<Uuid, (u64, Vec<Option<D>>)>,
+}
+
+impl<S: Clone, D: Clone> RecordTable<S, D> {
+
+    pub fn new<I: IntoIterator<Item = S>>(servers: I, sp: char) -> Self {
+        RecordTable {
+            sp,
+            servers: servers.into_iter().collect(),
+            data: HashMap::new()
+        }
+    }
+
+    pub fn col_range(&self) -> Range<usize> {
+        0 .. self.servers.len()
+    }
+
+    pub fn get_server(&self, index: usize) -> S {
+        self.servers[index].clone()
+    }
+
+    pub fn insert(&mut self, uuid: Uuid, index: usize, value: D, timestamp: u64) {
+        let len = self.servers.len();
+        if index < len {
+            let (first, line) = self.data.entry(uuid).or_insert_with(|| (timestamp, vec![None; len]));
+            *first = (*first).min(timestamp);
+            line[index] = Some(value);
+        }
+    }
+}
+
+impl<S, D> RecordTable<S, D> {
+
+    /// rows ordered by time, then by player uuid
+    fn rows(&self) -> Vec<(&Uuid, &[Option<D>])> {
+        let mut rows: Vec<_> = self.data.iter().map(|(k, (t, v))| (*t, k, v.as_slice())).collect();
+        rows.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        rows.into_iter().map(|(_, k, v)| (k, v)).collect()
+    }
+}
+
+impl<'s, D: fmt::Display> api::WriteTo for RecordTable<(&'s Uuid, &'s ServerData), D> {
+    type Error = io::Error;
+
+    fn write_to<W: io::Write + Sync + Send>(&self, mut w: W) -> Result<(), Self::Error> {
+        let sp = self.sp;
+
+        for (_, server_data) in &self.servers {
+            write!(w, "{}{}", sp, server_data.name.as_str())?;
+        }
+        writeln!(w)?;
+
+        for (uuid, _) in &self.servers {
+            write!(w, "{}{}", sp, uuid.to_hyphenated_ref())?;
+        }
+        writeln!(w)?;
+
+        for (_, server_data) in &self.servers {
+            write!(w, "{}{}", sp, server_data.trust)?;
+        }
+        writeln!(w)?;
+
+        for (k, v) in self.rows() {
+            write!(w, "{}", k.to_hyphenated_ref())?;
+            for p in v.iter() {
+                if let Some(p) = p {
+                    write!(w, "{}{}", sp, p)?;
+                } else {
+                    write!(w, "{}", sp)?;
+                }
+            }
+            writeln!(w)?;
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+} 
+
+const AUTO_REFRESH_THRESHOLD: usize = 3;
+
+fn refresh_server_cert(
+    httpc: &http::Client,
+    registry: &Url,
+    server_uuid: &Uuid,
+    server_data: &ServerData,
+    policy: &dyn Policy,
+) -> GeneralResult<Option<(Rc<Cert>, KeyID)>> {
+
+    let req = api::ServerListRequest::new(None);
+    let s = httpc.request::<api::ServerListRequest, api::ServerListResponse>(registry, req)
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    let registered = match s.servers.into_iter().find(|d| &d.uuid == server_uuid) {
+        Some(d) => d,
+        None => {
+            eprintln!("warning: server {} ({}) is not registered in {}", server_data.name, server_uuid, registry);
+            return Ok(None)
+        }
+    };
+    let old = server_data.get_cert().fingerprint();
+    let new = registered.public_key.fingerprint();
+    if old == new {
+        eprintln!("cert of server {} ({}) is the same as registered: {}", server_data.name, server_uuid, &old);
+        return Ok(None)
+    }
+    if !pgp::check_key(&registered.public_key, policy, None, &registered.key_id) {
+        eprintln!("warning: registered key {} of server {} ({}) is not valid", &registered.key_id, server_data.name, server_uuid);
+        return Ok(None)
+    }
+    eprintln!("server {} ({}) has a new key registered: {} -> {} (key_id: {})", server_data.name, server_uuid, &old, &new, &registered.key_id);
+    if confirm("update the cert with the registered key?")? {
+        Ok(Some((Rc::new(registered.public_key), registered.key_id)))
+    } else {
+        eprintln!("cert not updated; use `cert --remove` and `cert --add` to update it manually");
+        Ok(None)
+    }
+}
+
+pub fn command_get_server_submit_auto<'a>(
+    cfg: &ClientConfig,
+    servers: &mut ServersConfig,
+    httpc: &http::Client,
+    limit: Option<&'a str>,
+    after: Option<&'a str>,
+    before: Option<&'a str>,
+    min_trust: Option<&'a str>,
+    output: &'a str,
+    observer: Option<&dyn Observer>,
+) -> error::AppResult<'a> {
+    use api::WriteTo as _;
+    use api::RequestInfo as _;
+
+    let observer = observer.unwrap_or(&observer::NoopObserver);
+
+    let limit = if let Some(s) = limit {
+        Some(error::ArgsError::parse(s, "limit", "unsigned integer")?)
+    } else {
+        None
+    };
+    let (after, before) = parse_time_window(after, before)?;
+    let min_trust = match min_trust {
+        Some(s) => parse_trust(s)?,
+        None => 0,
+    };
+
+    let cfg_data = cfg.get_data();
+    let api_url = cfg_data.api_url.as_ref();
+    let auto_refresh = cfg_data.auto_refresh_certs.unwrap_or(false);
+    let mut refreshed = Vec::new();
+
+    let mut table: RecordTable<_, f32> = RecordTable::new(servers.get_data().iter().filter(|(_, d)| d.trust >= min_trust), ',');
+
+    let total = table.col_range().len();
+    for i in table.col_range() {
+
+        let (server_uuid, server_data) = table.get_server(i);
+        let item = Item { kind: ItemKind::Server, index: i, total, uuid: server_uuid, name: Some(server_data.name.as_str()) };
+        let endpoint = match error::ConfigMissing::ok(server_data.api_url.as_ref().or(api_url), "client.api_url") {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                observer.on_warning(&format!("skip server {} ({}): {}", server_data.name, server_uuid.to_hyphenated_ref(), e));
+                continue;
+            }
+        };
+        observer.on_item_start(&item);
+        let handle = api::ServerHandle::ServerUUID(server_uuid.clone());
+        let req = api::GetServerSubmitRequest::new(handle, limit, after, before, None);
+        observer.on_http_request(req.method().as_str(), &req.url(endpoint));
+        let sc = match httpc.request::<api::GetServerSubmitRequest, api::GetServerSubmitResponse>(endpoint, req) {
+            Ok(sc) => sc,
+            Err(e) => {
+                let message = e.to_string();
+                observer.on_warning(&format!("skip server {} ({}): {}", server_data.name, server_uuid.to_hyphenated_ref(), &message));
+                observer.on_item_done(&item, &Outcome::Failed { message });
+                continue;
+            }
+        };
+        let mut failed = Vec::new();
+        let mut consecutive = 0;
+        let mut max_consecutive = 0;
+        for s in &sc.submits {
+            match pgp::verify(server_data.get_cert(), &server_data.key_id, servers.policy(), None, s.content.as_bytes(), transfer) {
+                Ok(d) => {
+                    observer.on_verify_result(server_uuid, &s.uuid, Ok(()));
+                    table.insert(d.player_uuid.clone(), i, d.points, d.timestamp);
+                    consecutive = 0;
+                }
+                Err(e) => {
+                    observer.on_verify_result(server_uuid, &s.uuid, Err(e.to_string().as_str()));
+                    failed.push(s);
+                    consecutive += 1;
+                    max_consecutive = max_consecutive.max(consecutive);
+                }
+            }
+        }
+
+        if auto_refresh && max_consecutive > AUTO_REFRESH_THRESHOLD {
+            let registry = match api_url {
+                Some(registry) => registry,
+                None => {
+                    observer.on_warning(&format!("can not refresh cert of server {} ({}): missing client.api_url", server_data.name, server_uuid.to_hyphenated_ref()));
+                    observer.on_item_done(&item, &Outcome::Fetched { verified: sc.submits.len() - failed.len(), unverified: failed.len() });
+                    continue;
+                }
+            };
+            eprintln!("{} submits of server {} ({}) failed verification in a row; check registered key", max_consecutive, server_data.name, server_uuid.to_hyphenated_ref());
+            match refresh_server_cert(httpc, registry, server_uuid, server_data, servers.policy()) {
+                Ok(Some((cert, key_id))) => {
+                    let mut still_failed = Vec::new();
+                    for s in failed {
+                        match pgp::verify(cert.as_ref(), &key_id, servers.policy(), None, s.content.as_bytes(), transfer) {
+                            Ok(d) => {
+                                observer.on_verify_result(server_uuid, &s.uuid, Ok(()));
+                                table.insert(d.player_uuid.clone(), i, d.points, d.timestamp);
+                            }
+                            Err(e) => {
+                                observer.on_verify_result(server_uuid, &s.uuid, Err(e.to_string().as_str()));
+                                still_failed.push(s);
+                            }
+                        }
+                    }
+                    failed = still_failed;
+                    refreshed.push((server_uuid.clone(), cert, key_id));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    observer.on_warning(&format!("can not refresh cert of server {} ({}): {}", server_data.name, server_uuid.to_hyphenated_ref(), e));
+                }
+            }
+        }
+
+        observer.on_item_done(&item, &Outcome::Fetched { verified: sc.submits.len() - failed.len(), unverified: failed.len() });
+    }
+
+    crate::config::atomic_write(output, |ofile| table.write_to(ofile)).map_err(error::AppError::new_other)?;
+
+    for (server_uuid, cert, key_id) in refreshed {
+        if servers.replace_cert(&server_uuid, cert, key_id) {
+            eprintln!("cert of server {} updated", &server_uuid);
+        }
+    }
+
+    Ok(())
+}
+
+
+fn sleep_interval(interval: Duration, last: SystemTime) {
+    let now = SystemTime::now();
+    if let Ok(d) = now.duration_since(last) {
+        if interval > d {
+            let rest = interval - d;
+            thread::sleep(rest)
+        }
+    }
+}
+
+
+struct ImportSummary<'b> {
+    submitted: usize,
+    skipped: usize,
+    failed: usize,
+    protected: Vec<(&'b Uuid, &'b str)>,
+    ip: banlist::IpResolveStats,
+    records: Vec<output::SubmittedJson>,
+}
+
+#[derive(serde::Serialize)]
+struct ImportSummaryJson<'s> {
+    submitted: usize,
+    skipped: usize,
+    failed: usize,
+    protected: Vec<&'s Uuid>,
+    ip_resolved: usize,
+    ip_skipped: usize,
+    ip_failed: usize,
+    records: &'s [output::SubmittedJson],
+}
+
+impl<'b> ImportSummary<'b> {
+
+    fn new(ip: banlist::IpResolveStats) -> Self {
+        ImportSummary { submitted: 0, skipped: 0, failed: 0, protected: Vec::new(), ip, records: Vec::new() }
+    }
+
+    /// the summary goes to stderr as text, or to `out` as one json object
+    fn report(&self, format: OutputFormat, dry_run: bool, out: &mut dyn io::Write) -> io::Result<()> {
+        match format {
+            OutputFormat::Json => {
+                let body = ImportSummaryJson {
+                    submitted: self.submitted,
+                    skipped: self.skipped,
+                    failed: self.failed,
+                    protected: self.protected.iter().map(|(uuid, _)| *uuid).collect(),
+                    ip_resolved: self.ip.resolved,
+                    ip_skipped: self.ip.skipped,
+                    ip_failed: self.ip.failed,
+                    records: self.records.as_slice(),
+                };
+                if dry_run {
+                    output::write_json(out, &StatusJson::dry_run(body))
+                } else {
+                    output::write_json(out, &StatusJson::ok(body))
+                }
+            }
+            _ => {
+                self.print();
+                Ok(())
+            }
+        }
+    }
+
+    fn print(&self) {
+        if !self.protected.is_empty() {
+            eprintln!("\n==== {} protected player(s) skipped ====", self.protected.len());
+            for (uuid, name) in self.protected.iter() {
+                eprintln!("! {} {}", uuid, name);
+            }
+        }
+        if !self.ip.is_empty() {
+            eprintln!("ip entries: {} resolved, {} skipped, {} failed", self.ip.resolved, self.ip.skipped, self.ip.failed);
+        }
+        eprintln!("Done: {} submitted, {} skipped (already existed), {} failed", self.submitted, self.skipped, self.failed);
+    }
+}
+
+
+pub fn command_import<'a>(
+    cfg: &mut ClientConfig,
+    records: &mut RecordConfig,
+    httpc: &http::Client,
+    kpg: &mut SigningKeyPairGenerator,
+    banlist: &'a str,
+    interval: Option<&'a str>,
+    protect_from: Vec<&'a str>,
+    ignore_errors: bool,
+    dry_run: bool,
+    timestamp_policy: Option<&'a str>,
+    usercache: Option<&'a str>,
+    checkpoint: &Path,
+    resume: bool,
+    rules: &dyn banlist::GeneratePoints,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
+    observer: Option<&dyn Observer>,
+) -> error::AppResult<'a> {
+    use api::RequestInfo as _;
+
+    let observer = observer.unwrap_or(&observer::NoopObserver);
+    let timestamp_policy: banlist::TimestampPolicy = match timestamp_policy {
+        Some(s) => error::ArgsError::parse(s, "timestamp_policy", banlist::TimestampPolicy::EXPECTING)?,
+        None => Default::default()
+    };
+    let usercache = match usercache {
+        Some(path) => Some(banlist::UserCache::load(path)?),
+        None => None
+    };
+    let entries = banlist::load_banlist(banlist)?;
+    let mut invalid = Vec::new();
+    let mut ip_stats = banlist::IpResolveStats::default();
+    let mut banlist = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.into_iter().enumerate() {
+        match entry {
+            Ok(banlist::BanEntry::Player(item)) => banlist.push(item),
+            Ok(banlist::BanEntry::Ip(item)) => {
+                let ip = item.ip.clone();
+                match banlist::resolve_ip_entry(item, usercache.as_ref()) {
+                    Ok(Some(item)) => {
+                        ip_stats.resolved += 1;
+                        banlist.push(item);
+                    }
+                    Ok(None) => {
+                        observer.on_warning(&format!("skip entry {}: ip {} can not be mapped to a player", i + 1, ip));
+                        ip_stats.skipped += 1;
+                    }
+                    Err(e) => {
+                        observer.on_warning(&format!("skip entry {}: {}", i + 1, e));
+                        ip_stats.failed += 1;
+                    }
+                }
+            }
+            Err(e) => invalid.push(format!("entry {}: {}", i + 1, e)),
+        }
+    }
+    if !invalid.is_empty() && !dry_run {
+        return Err(anyhow::anyhow!("invalid banlist {}", invalid.join("; ")).into())
+    }
+    let interval = match interval {
+        Some(s) => Duration::from_millis(error::ArgsError::parse(s, "interval", "integer of milliseconds")?),
+        None => Duration::ZERO
+    };
+    let protected = load_protected_players(cfg, protect_from.as_slice())?;
+    let start = if resume {
+        match banlist::ImportCheckpoint::load(checkpoint)? {
+            Some(c) => {
+                if banlist.get(c.index).map(|item| &item.uuid) != Some(&c.player_uuid) {
+                    return Err(anyhow::anyhow!("checkpoint {} does not match the banlist", checkpoint.display()).into())
+                }
+                log_info!("resume after entry {} ({})", c.index + 1, &c.player_uuid);
+                c.index + 1
+            }
+            None => {
+                log_info!("no checkpoint found, start from the first entry");
+                0
+            }
+        }
+    } else {
+        0
+    };
+
+    cfg.require_cert()?;
+    let cfg_data = cfg.get_data();
+    let server_uuid = error::ConfigMissing::ok(cfg_data.server_uuid.as_ref(), "client.server_uuid")?.clone();
+    let cert = error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?;
+    let key_id = error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?;
+    warn_key_expiring(cert, cfg.policy(), key_id);
+    let keypair = if dry_run {
+        None
+    } else {
+        Some(kpg.generate(cert, key_id, None)?)
+    };
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
+
+    let mut last = SystemTime::UNIX_EPOCH;
+    let total = banlist.len();
+    let mut summary = ImportSummary::new(ip_stats);
+    let json = format == OutputFormat::Json;
+
+    if dry_run && !json {
+        writeln!(out, "player_uuid\tpoints\ttimestamp\tcomment").map_err(error::AppError::new_other)?;
+    }
+
+    for (i, item) in banlist.iter().enumerate().skip(start) {
+        let progress = Item { kind: ItemKind::Player, index: i, total, uuid: &item.uuid, name: Some(item.name.as_str()) };
+        observer.on_item_start(&progress);
+        if protected.contains(&item.uuid) {
+
+            observer.on_item_done(&progress, &Outcome::Protected);
+            if dry_run && !json {
+                writeln!(out, "[dry-run] would skip player={} (protected)", &item.uuid).map_err(error::AppError::new_other)?;
+            }
+            summary.protected.push((&item.uuid, item.name.as_str()));
+
+        } else if let Some(record_uuid) = records.check_player_uuid(&item.uuid) {
+
+            observer.on_item_done(&progress, &Outcome::Skipped { record_uuid });
+            if dry_run && !json {
+                writeln!(out, "[dry-run] would skip player={} (record: {})", &item.uuid, record_uuid).map_err(error::AppError::new_other)?;
+            }
+            summary.skipped += 1;
+            
+        } else {
+
+            if !dry_run {
+                sleep_interval(interval, last);
+            }
+
+            let timestamp = timestamp_policy.timestamp(item.created.timestamp() as u64, timestamp(SystemTime::now()).unwrap());
+            let player_uuid = item.uuid.clone();
+            let points = rules.generate(item);
+            let comment = timestamp_policy.comment(item);
+
+            if dry_run {
+                if json {
+                    summary.records.push(output::SubmittedJson { record_uuid: None, player_uuid, points, timestamp });
+                } else {
+                    writeln!(out, "{}\t{}\t{}\t{}", &player_uuid, points, TimestampDisplay(timestamp), comment.as_str()).map_err(error::AppError::new_other)?;
+                }
+                observer.on_item_done(&progress, &Outcome::WouldSubmit);
+                summary.submitted += 1;
+                continue;
+            }
+
+            let req = api::SubmitRequest::new(
+                api::SubmitContent{ 
+                    uuid: server_uuid.clone(),
+                    timestamp,
+                    player_uuid,
+                    points,
+                    comment,
+                },
+                keypair.unwrap()
+            );
+
+            last = SystemTime::now();
+            observer.on_http_request(req.method().as_str(), &req.url(api_url));
+        
+            match httpc.request::<api::SubmitRequest, api::SubmitResponse>(api_url, req) {
+                Ok(s) => {
+                    observer.on_item_done(&progress, &Outcome::Submitted { record_uuid: s.uuid });
+                    summary.submitted += 1;
+                    if json {
+                        summary.records.push(output::SubmittedJson { record_uuid: Some(s.uuid.clone()), player_uuid, points, timestamp });
+                    }
+                    records.new_submit(s.uuid, timestamp, player_uuid);
+                    let c = banlist::ImportCheckpoint { index: i, player_uuid };
+                    if let Err(e) = c.save(checkpoint) {
+                        observer.on_warning(&format!("can not write checkpoint {}: {}", checkpoint.display(), e));
+                    }
+                }
+                Err(e) => {
+                    summary.failed += 1;
+                    observer.on_item_done(&progress, &Outcome::Failed { message: e.to_string() });
+                    if !ignore_errors {
+                        summary.print();
+                        return Err(e.into())
+                    }
+                }
+            }
+        }
+    }
+
+    summary.report(format, dry_run, out).map_err(error::AppError::new_other)?;
+
+    if !dry_run {
+        if let Err(e) = fs::remove_file(checkpoint) {
+            if e.kind() != io::ErrorKind::NotFound {
+                eprintln!("warning: can not remove checkpoint {}: {}", checkpoint.display(), e);
+            }
+        }
+    }
+
+    if !invalid.is_empty() {
+        eprintln!("\n==== {} invalid entries ====", invalid.len());
+        for e in invalid.iter() {
+            eprintln!("! {}", e);
+        }
+        return Err(anyhow::anyhow!("{} invalid entries in banlist", invalid.len()).into())
+    }
+
+    Ok(())
+}
+
+
+pub fn command_submit_batch<'a>(
+    cfg: &mut ClientConfig,
+    records: &mut RecordConfig,
+    httpc: &http::Client,
+    kpg: &mut SigningKeyPairGenerator,
+    batch: &'a str,
+    interval: Option<&'a str>,
+    force: bool,
+    dry_run: bool,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
+    observer: Option<&dyn Observer>,
+) -> error::AppResult<'a> {
+    use api::RequestInfo as _;
+
+    let observer = observer.unwrap_or(&observer::NoopObserver);
+
+    let rows = batch::load_batch(batch)?;
+    let interval = match interval {
+        Some(s) => Duration::from_millis(error::ArgsError::parse(s, "interval", "integer of milliseconds")?),
+        None => Duration::ZERO
+    };
+    let protected = load_protected_players(cfg, &[])?;
+
+    cfg.require_cert()?;
+    let cfg_data = cfg.get_data();
+    let server_uuid = error::ConfigMissing::ok(cfg_data.server_uuid.as_ref(), "client.server_uuid")?.clone();
+    let cert = error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?;
+    let key_id = error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?;
+    warn_key_expiring(cert, cfg.policy(), key_id);
+    let keypair = kpg.generate(cert, key_id, None)?;
+    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
+
+    let mut last = SystemTime::UNIX_EPOCH;
+    let total = rows.len();
+    let mut summary = ImportSummary::new(Default::default());
+    let json = format == OutputFormat::Json;
+
+    for (i, row) in rows.iter().enumerate() {
+        let progress = Item { kind: ItemKind::Player, index: i, total, uuid: &row.player_uuid, name: None };
+        observer.on_item_start(&progress);
+        if protected.contains(&row.player_uuid) {
+
+            observer.on_item_done(&progress, &Outcome::Protected);
+            summary.protected.push((&row.player_uuid, ""));
+            continue;
+        }
+        if let Some(record_uuid) = records.check_player_uuid(&row.player_uuid) {
+            if !force {
+                observer.on_item_done(&progress, &Outcome::Skipped { record_uuid });
+                summary.skipped += 1;
+                continue;
+            }
+        }
+
+        if !dry_run {
+            sleep_interval(interval, last);
+        }
+
+        let timestamp = match row.timestamp {
+            Some(t) => t,
+            None => timestamp(SystemTime::now()).unwrap(),
+        };
+        let req = api::SubmitRequest::new(
+            api::SubmitContent{ 
+                uuid: server_uuid.clone(),
+                timestamp,
+                player_uuid: row.player_uuid,
+                points: row.points,
+                comment: row.comment.clone(),
+            },
+            &keypair
+        );
+
+        if dry_run {
+            api::WriteTo::write_to(&req, io::sink())?;
+            if json {
+                summary.records.push(output::SubmittedJson { record_uuid: None, player_uuid: row.player_uuid, points: row.points, timestamp });
+            } else {
+                writeln!(out, "[dry-run] would submit player={} points={}", &row.player_uuid, row.points).map_err(error::AppError::new_other)?;
+            }
+            observer.on_item_done(&progress, &Outcome::WouldSubmit);
+            summary.submitted += 1;
+            continue;
+        }
+
+        last = SystemTime::now();
+        observer.on_http_request(req.method().as_str(), &req.url(api_url));
+
+        match httpc.request::<api::SubmitRequest, api::SubmitResponse>(api_url, req) {
+            Ok(s) => {
+                observer.on_item_done(&progress, &Outcome::Submitted { record_uuid: s.uuid });
+                summary.submitted += 1;
+                if json {
+                    summary.records.push(output::SubmittedJson { record_uuid: Some(s.uuid.clone()), player_uuid: row.player_uuid, points: row.points, timestamp });
+                }
+                records.new_submit(s.uuid, timestamp, row.player_uuid);
+            }
+            Err(e) => {
+                observer.on_item_done(&progress, &Outcome::Failed { message: e.to_string() });
+                summary.failed += 1;
+            }
+        }
+    }
+
+    let failed = summary.failed;
+    summary.report(format, dry_run, out).map_err(error::AppError::new_other)?;
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} of {} submit(s) failed", failed, total).into())
+    }
+
+    Ok(())
+}
 
-    let success = cfg.add(server_uuid, ServerData::new(name, key_id, trust), |_, _| {
-        println!("==== please input pgp text, end with ctrl-Z ===");
-        pgp::read_cert_from_console().map(Rc::new)
-    })?;
+/**
+ * whether the registry answers at all; needs only api_url, no key
+ */
+pub fn command_ping<'a>(
+    cfg: &ClientConfig,
+    httpc: &http::Client,
+    api_url: Option<&'a str>,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+    use std::time::Instant;
 
-    if success {
-        println!("success.")
-    } else {
-        println!("existed.")
+    let api_url: Url = match api_url {
+        Some(s) => error::ArgsError::parse(s, "api_url", "url")?,
+        None => error::ConfigMissing::ok(cfg.get_data().api_url.as_ref(), "client.api_url")?.clone(),
+    };
+
+    let start = Instant::now();
+    let result = httpc.request::<api::PingRequest, api::PingResponse>(&api_url, api::PingRequest);
+    let elapsed = start.elapsed().as_millis();
+    match result {
+        Ok(api::PingResponse { status: api::Status::OK }) => {}
+        Ok(r) => eprintln!("warning: {} answered ping with status {:?}", api_url, r.status),
+        Err(http::RequsetError::Response(r)) if r.code == 404 => {
+            eprintln!("warning: {} does not implement the ping endpoint, but the server is reachable", api_url);
+        }
+        Err(e) => return Err(e.into()),
     }
+    writeln!(out, "OK: {} responded in {}ms", api_url, elapsed).map_err(error::AppError::new_other)?;
 
     Ok(())
 }
 
 
-pub fn command_cert_remove<'a>(
-    cfg: &mut ServersConfig, 
-    server_uuid: &'a str, 
+pub fn command_version<'a>(
+    base_dir: &Path,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
 ) -> error::AppResult<'a> {
-    
-    let server_uuid: Uuid = error::ArgsError::parse(server_uuid, "server_uuid", "uuid")?;
-
-    let success = cfg.remove(&server_uuid);
 
-    if success {
-        println!("success.")
-    } else {
-        println!("not existed.")
+    let info = info::BuildInfo::collect(base_dir);
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, &info).map_err(error::AppError::new_other)?;
+            writeln!(out).map_err(error::AppError::new_other)?;
+        }
+        _ => {
+            writeln!(out, "{}", &info).map_err(error::AppError::new_other)?;
+        }
     }
-
     Ok(())
 }
 
 
-pub fn command_server_list<'a>(
-    cfg: &ClientConfig,
-    httpc: &http::Client,
-    limit: Option<&'a str>
+pub fn command_records_compact<'a>(
+    records: &mut RecordConfig,
+    out: &mut dyn io::Write,
 ) -> error::AppResult<'a> {
-      
-    let limit = if let Some(s) = limit {
-        Some(error::ArgsError::parse(s, "limit", "unsigned integer")?)
-    } else {
-        None
-    };
 
-    let cfg_data = cfg.get_data();
-    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url" )?;
+    let removed = records.compact().map_err(error::AppError::new_other)?;
+    writeln!(out, "compacted: {} line(s) removed", removed).map_err(error::AppError::new_other)?;
 
-    let req = api::ServerListRequest::new(limit);
-    let s = httpc.request::<api::ServerListRequest, api::ServerListResponse>(api_url, req)?;
-    
-    struct ServerDataDisplay<'a>(&'a api::ServerData);
+    Ok(())
+}
 
-    impl<'a> fmt::Display for ServerDataDisplay<'a> {
-        
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            let data = self.0;  
-            let mut buf = Vec::new();
-            if let Ok(_) = pgp::export_publickey(&data.public_key, &mut buf) {
-                f.write_fmt(format_args!("server_name:{}\nserver_uuid: {}\nkey_id:{}\n\n", data.server_name.as_str(), data.uuid.to_hyphenated_ref(), data.key_id))?;
-                f.write_str(String::from_utf8_lossy(buf.as_slice()).borrow())?;
-            }
-            Ok(())
-        }
-    }
 
-    for d in &s.servers {
-        println!("====================\n{}\n\n", ServerDataDisplay(d));
+pub fn command_records_export<'a>(
+    records: &RecordConfig,
+    output: Option<&'a str>,
+    format: OutputFormat,
+) -> error::AppResult<'a> {
+
+    let w: Box<dyn io::Write> = match output {
+        Some(s) if !s.is_empty() && s != "-" => Box::new(File::create(s).map_err(error::AppError::new_other)?),
+        _ => Box::new(io::stdout()),
+    };
+    let mut w = io::BufWriter::new(w);
+
+    match format {
+        OutputFormat::Json => {
+            records.export_json(&mut w).map_err(error::AppError::new_other)?;
+            writeln!(w).map_err(error::AppError::new_other)?;
+        }
+        _ => {
+            records.export_csv(&mut w).map_err(error::AppError::new_other)?;
+        }
     }
+    w.flush().map_err(error::AppError::new_other)?;
 
     Ok(())
 }
 
-fn transfer(r: &mut dyn io::Read) -> anyhow::Result<api::SubmitContent> {
-    api::ReadFrom::read_from(r)
+
+pub fn command_records_stats<'a>(
+    records: &RecordConfig,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let (active, unique_players) = records.stats();
+    writeln!(out, "active: {}, unique_players: {}", active, unique_players).map_err(error::AppError::new_other)?;
+
+    Ok(())
 }
 
-pub fn command_get_submit<'a> (
+
+/**
+ * compare the recall state of local records with the submits the registry serves for this server;
+ * only the local record file is ever changed, and only with `auto_heal_local`
+ */
+pub fn command_records_sync<'a>(
     cfg: &ClientConfig,
-    servers: &ServersConfig,
+    records: &mut RecordConfig,
     httpc: &http::Client,
-    record_uuid: &'a str
+    auto_heal_local: bool,
+    out: &mut dyn io::Write,
 ) -> error::AppResult<'a> {
 
-    let record_uuid: Uuid = error::ArgsError::parse(record_uuid, "record_uuid", "uuid")?;
-    
     let cfg_data = cfg.get_data();
+    let server_uuid = error::ConfigMissing::ok(cfg_data.server_uuid.as_ref(), "client.server_uuid")?.clone();
     let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
 
-    let req = api::GetSubmitRequest::new(record_uuid);
-    let s = httpc.request::<api::GetSubmitRequest, api::GetSubmitResponse>(api_url, req)?;
+    // the registry caps one response, so the listing is paged; a record missing from all pages is gone
+    let remote_live = list_server_submits(httpc, api_url, api::ServerHandle::ServerUUID(server_uuid), cfg.policy())?;
 
-    let v = if let Some((cert, key_id)) = servers.get_ref(&s.server_uuid) {
-        pgp::verify(cert, key_id, servers.policy(), None, s.content.as_bytes(), transfer)
+    let mut local_live = HashSet::new();
+    let mut local_recalled = HashSet::new();
+    for e in records.entries() {
+        if e.recalled.is_some() {
+            local_recalled.insert(e.record_uuid);
+        } else {
+            local_live.insert(e.record_uuid);
+        }
+    }
+
+    let found = sync::classify(&local_live, &local_recalled, &remote_live);
+    if found.is_empty() {
+        writeln!(out, "local records agree with {}", api_url).map_err(error::AppError::new_other)?;
+        return Ok(())
+    }
+
+    let now = timestamp(SystemTime::now()).unwrap();
+    let mut healed = 0;
+    for (record_uuid, d) in found.iter() {
+        writeln!(out, "{} {}", d, record_uuid).map_err(error::AppError::new_other)?;
+        if auto_heal_local && *d == sync::Discrepancy::LocalLiveRemoteGone {
+            if records.new_recall(*record_uuid, now) {
+                healed += 1;
+                writeln!(out, "  healed: local recall entry appended").map_err(error::AppError::new_other)?;
+            }
+        } else {
+            writeln!(out, "  fix: {}", d.remediation(record_uuid)).map_err(error::AppError::new_other)?;
+        }
+    }
+    writeln!(out, "{} discrepancies, {} healed locally", found.len(), healed).map_err(error::AppError::new_other)?;
+
+    Ok(())
+}
+
+
+pub fn command_records<'a>(
+    records: &RecordConfig,
+    player_uuid: Option<&'a str>,
+    after: Option<&'a str>,
+    before: Option<&'a str>,
+    include_recalled: bool,
+    count: bool,
+    format: OutputFormat,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let player_uuid: Option<Uuid> = match player_uuid {
+        Some(s) => Some(error::ArgsError::parse(s, "player_uuid", "uuid")?),
+        None => None
+    };
+    let after = parse_datetime(after, "after")?.unwrap_or(0);
+    let before = parse_datetime(before, "before")?.unwrap_or(u64::MAX);
+
+    // (record_uuid, player_uuid, timestamp, recalled)
+    let entries: Vec<(&Uuid, &Uuid, u64, Option<u64>)> = if include_recalled {
+        records.entries()
+            .filter(|e| after < e.timestamp && e.timestamp < before)
+            .map(|e| (&e.record_uuid, &e.player_uuid, e.timestamp, e.recalled))
+            .collect()
     } else {
-        Err(anyhow::anyhow!("can not find cert for server:{}", &s.server_uuid))
+        records.submits_in_range(after, before)
+            .into_iter()
+            .map(|(r, p, t)| (r, p, t, None))
+            .collect()
     };
+    let entries = entries.into_iter().filter(|(_, p, _, _)| player_uuid.map_or(true, |u| **p == u));
+
+    if count {
+        writeln!(out, "{}", entries.count()).map_err(error::AppError::new_other)?;
+        return Ok(())
+    }
 
-    match v {
-        Ok(d) => {
-            println!("+ Verified Message");
-            println!("{}", ServerDataDisplay(servers.get_data().get(&s.server_uuid).unwrap(), &s.server_uuid));
-            println!("{:#?}", &d);
+    let entries = entries.map(|(record_uuid, player_uuid, timestamp, recalled)| output::LocalRecordJson { record_uuid, player_uuid, timestamp, recalled });
+    match format {
+        OutputFormat::Plain => {
+            for e in entries {
+                write!(out, "{} {} {}", e.record_uuid, e.player_uuid, TimestampDisplay(e.timestamp)).map_err(error::AppError::new_other)?;
+                if let Some(t) = e.recalled {
+                    write!(out, " (recalled {})", TimestampDisplay(t)).map_err(error::AppError::new_other)?;
+                }
+                writeln!(out).map_err(error::AppError::new_other)?;
+            }
+        }
+        OutputFormat::Json => {
+            let entries: Vec<_> = entries.collect();
+            output::write_json(out, &entries).map_err(error::AppError::new_other)?;
         }
-        Err(e) => {
-            println!("server_uuid: {}\n", s.server_uuid.to_hyphenated_ref());
-            println!("{}\n", &s.content);
-            return Err(e.into())
+        OutputFormat::Csv => {
+            let header = if include_recalled { output::LocalRecordJson::CSV_HEADER_RECALLED } else { output::LocalRecordJson::CSV_HEADER };
+            writeln!(out, "{}", header).map_err(error::AppError::new_other)?;
+            for e in entries {
+                writeln!(out, "{}", e.csv_row(include_recalled)).map_err(error::AppError::new_other)?;
+            }
         }
     }
 
     Ok(())
 }
 
-struct ServerDataDisplay<'a>(&'a ServerData, &'a Uuid);
+struct TimestampDisplay(u64);
 
-impl<'a> fmt::Display for ServerDataDisplay<'a> {
+impl fmt::Display for TimestampDisplay {
 
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_fmt(format_args!(
-            "server: {} [{}]\n   key: {}   trust: {}\n", 
-            self.0.name.as_str(),
-            self.1.to_hyphenated_ref(),
-            self.0.key_id,
-            self.0.trust
-        ))
+        match chrono::NaiveDateTime::from_timestamp_opt(self.0 as i64, 0) {
+            Some(t) => t.format("%Y-%m-%d %H:%M:%S").fmt(f),
+            None => self.0.fmt(f),
+        }
     }
 }
 
 
-pub enum ServerHandleWrap<'a> {
-    UUID(&'a str),
-    KeyID(&'a str),
-}
-
-pub fn command_get_server_submit<'a>(
-    cfg: &ClientConfig,
-    servers: &ServersConfig,
+pub fn command_status<'a>(
+    cfg: &mut ClientConfig,
+    servers: &GeneralResult<ServersConfig>,
+    records: Option<&io::Result<RecordConfig>>,
     httpc: &http::Client,
-    server_handle: ServerHandleWrap<'a>,
-    limit: Option<&'a str>,
-    after: Option<&'a str>
+    out: &mut dyn io::Write,
 ) -> error::AppResult<'a> {
-    use chrono::NaiveDateTime;
 
-    let (uuid, cert, key_id, handle) = match server_handle {
-        ServerHandleWrap::UUID(s) => {
-            let server_uuid: Uuid = error::ArgsError::parse(s, "server_uuid", "uuid")?;
-            let (cert, key_id) = servers.get_ref(&server_uuid)
-                    .ok_or_else(|| anyhow::anyhow!("can not find cert for server:{}", &server_uuid))?;
-            (server_uuid, cert, key_id.clone(), api::ServerHandle::ServerUUID(server_uuid))
-        },
-        ServerHandleWrap::KeyID(s) => {
-            let key_id: KeyID = error::ArgsError::parse(s, "key_id", "hex")?;
-            let mut tuple = None;
-            for (server_uuid, d) in servers.get_data().iter() {
-                if d.key_id == key_id {
-                    tuple = Some((server_uuid.clone(), d.get_cert(), key_id.clone(), api::ServerHandle::KeyID(key_id.clone())));
-                    break;
-                }
-            }
-            tuple
-                .ok_or_else(|| anyhow::anyhow!("can not find cert for key_id:{}", &key_id))?
-        },
+    let mut operational = true;
+    let mut check = |ok: bool, msg: String| -> io::Result<()> {
+        operational &= ok;
+        writeln!(out, "[{}] {}", if ok { " ok " } else { "FAIL" }, msg)
     };
-    let limit = if let Some(s) = limit {
-        Some(error::ArgsError::parse(s, "limit", "unsigned integer")?)
+
+    let loaded = cfg.require_cert();
+    let cfg_data = cfg.get_data();
+    match (&cfg_data.cert_file, loaded) {
+        (None, _) => check(false, String::from("cert_file is not set")),
+        (Some(path), Err(e)) => check(false, format!("cert_file {} can not be loaded: {}", path.display(), e)),
+        (Some(path), Ok(_)) => check(true, format!("cert_file {} loaded", path.display())),
+    }
+    .map_err(error::AppError::new_other)?;
+    match (&cfg_data.key_id, cfg_data.get_cert()) {
+        (None, _) => check(false, String::from("key_id is not set")),
+        (Some(key_id), Some(cert)) if !pgp::check_secret_key(cert, cfg.policy(), None, key_id) => {
+            check(false, format!("key_id {} is not a valid signing key of cert_file", key_id))
+        }
+        (Some(key_id), _) => check(true, format!("key_id {} selected", key_id)),
+    }
+    .map_err(error::AppError::new_other)?;
+    if let (Some(key_id), Some(cert)) = (&cfg_data.key_id, cfg_data.get_cert()) {
+        let required = cfg_data.require_encrypted_key.unwrap_or(false);
+        match pgp::is_secret_key_encrypted(cert, cfg.policy(), None, key_id) {
+            Some(true) => check(true, format!("secret key {} is protected by a passphrase", key_id)),
+            Some(false) => check(!required, format!("secret key {} is NOT protected by a passphrase", key_id)),
+            None => Ok(()),
+        }
+        .map_err(error::AppError::new_other)?;
+    }
+    match &cfg_data.server_uuid {
+        Some(server_uuid) => check(true, format!("registered as server {}", server_uuid)),
+        None => check(false, String::from("server_uuid is not set; not registered")),
+    }
+    .map_err(error::AppError::new_other)?;
+    match &cfg_data.api_url {
+        Some(api_url) => {
+            let req = api::ServerListRequest::new(Some(1));
+            match httpc.request::<api::ServerListRequest, api::ServerListResponse>(api_url, req) {
+                Ok(_) => check(true, format!("api_url {} responds", api_url)),
+                Err(e) => check(false, format!("api_url {} does not respond: {}", api_url, e)),
+            }
+        }
+        None => check(false, String::from("api_url is not set")),
+    }
+    .map_err(error::AppError::new_other)?;
+    match servers {
+        Ok(servers) => check(true, format!("{} trusted server(s)", servers.get_data().len())),
+        Err(e) => check(false, format!("servers config can not be loaded: {}", e)),
+    }
+    .map_err(error::AppError::new_other)?;
+    match records {
+        Some(Ok(records)) => check(true, format!("{} active record(s)", records.entries().filter(|e| e.recalled.is_none()).count())),
+        Some(Err(e)) if e.kind() == io::ErrorKind::NotFound => check(false, format!("record file is missing: {}", e)),
+        Some(Err(e)) => check(false, format!("record file can not be loaded: {}", e)),
+        None => check(false, String::from("no record file without server_uuid")),
+    }
+    .map_err(error::AppError::new_other)?;
+
+    if operational {
+        Ok(())
     } else {
-        None
+        Err(error::AppError::Other(anyhow::anyhow!("client is not fully operational")))
+    }
+}
+
+
+/**
+ * cross-check cert_file, key_id, api_url and server_uuid without sending anything, unless `httpc` is given
+ * to compare the key with the one registered for server_uuid; a mismatch there is only a warning
+ */
+pub fn command_config_validate<'a>(
+    cfg: &mut ClientConfig,
+    httpc: Option<&http::Client>,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let mut valid = true;
+    let mut check = |ok: Option<bool>, msg: String| -> io::Result<()> {
+        valid &= ok.unwrap_or(true);
+        let mark = match ok {
+            Some(true) => " ok ",
+            Some(false) => "FAIL",
+            None => "WARN",
+        };
+        writeln!(out, "[{}] {}", mark, msg)
     };
-    let after = if let Some(s) = after {
-        match NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
-            Ok(datetime) => {
-                let after = datetime.timestamp();
-                if after > 0 {
-                    Some(after as u64)
-                } else {
-                    None
+
+    let loaded = cfg.require_cert();
+    let cfg_data = cfg.get_data();
+    match (&cfg_data.cert_file, loaded) {
+        (None, _) => check(Some(false), String::from("cert_file is not set")),
+        (Some(path), Err(e)) => check(Some(false), format!("cert_file {} can not be loaded: {}", path.display(), e)),
+        (Some(path), Ok(_)) => check(Some(true), format!("cert_file {} loaded", path.display())),
+    }
+    .map_err(error::AppError::new_other)?;
+    match (&cfg_data.key_id, cfg_data.get_cert()) {
+        (None, _) => check(Some(false), String::from("key_id is not set")),
+        (Some(key_id), None) => check(Some(false), format!("key_id {} can not be checked without cert_file", key_id)),
+        (Some(key_id), Some(cert)) if !cert.keys().any(|ka| ka.key().keyid() == *key_id) => {
+            check(Some(false), format!("key_id {} is not in cert_file", key_id))
+        }
+        (Some(key_id), Some(cert)) if !pgp::check_key(cert, cfg.policy(), None, key_id) => {
+            check(Some(false), format!("key_id {} is not for signing, expired or revoked under the policy", key_id))
+        }
+        (Some(key_id), Some(cert)) if !pgp::check_secret_key(cert, cfg.policy(), None, key_id) => {
+            check(Some(false), format!("key_id {} has no secret key in cert_file", key_id))
+        }
+        (Some(key_id), Some(_)) => check(Some(true), format!("key_id {} is a valid signing key", key_id)),
+    }
+    .map_err(error::AppError::new_other)?;
+    match &cfg_data.api_url {
+        Some(api_url) if !api_url.path().ends_with('/') => check(Some(false), format!("api_url {} does not end with a slash", api_url)),
+        Some(api_url) => check(Some(true), format!("api_url {} is valid", api_url)),
+        None => check(Some(false), String::from("api_url is not set")),
+    }
+    .map_err(error::AppError::new_other)?;
+
+    match (&cfg_data.server_uuid, &cfg_data.api_url, httpc) {
+        (None, _, _) => check(Some(false), String::from("server_uuid is not set; not registered")),
+        (Some(server_uuid), Some(api_url), Some(httpc)) => {
+            let req = api::GetServerRequest::new(server_uuid.clone());
+            match httpc.request::<api::GetServerRequest, api::GetServerResponse>(api_url, req) {
+                Ok(res) => {
+                    let registered = res.server;
+                    let local = cfg_data.get_cert().map(Cert::fingerprint);
+                    if cfg_data.key_id.as_ref() == Some(&registered.key_id) && local.as_ref() == Some(&registered.public_key.fingerprint()) {
+                        check(Some(true), format!("server_uuid {} is registered with this key", server_uuid))
+                    } else {
+                        check(None, format!("server_uuid {} is registered with key {} ({}), not the configured one", server_uuid, registered.key_id, registered.public_key.fingerprint()))
+                    }
                 }
-            }   
-            Err(e) => {
-                return Err(
-                    error::ArgsError::new("after", "time{YYYY-mm-dd HH:MM:SS}", s).into()
-                );
+                Err(e) => check(None, format!("registration of server_uuid {} can not be checked: {}", server_uuid, e)),
             }
-        }           
+        }
+        (Some(server_uuid), _, _) => check(Some(true), format!("server_uuid {} is set; pass --online to compare it with the registry", server_uuid)),
+    }
+    .map_err(error::AppError::new_other)?;
+
+    if valid {
+        Ok(())
     } else {
-        None
-    };
+        Err(error::AppError::Other(anyhow::anyhow!("config is not valid")))
+    }
+}
 
-    let cfg_data = cfg.get_data();
-    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
 
-    let req = api::GetServerSubmitRequest::new(handle, limit, after);
-    let sc = httpc.request::<api::GetServerSubmitRequest, api::GetServerSubmitResponse>(api_url, req)?;
+pub const DEBUG_REQUEST_KINDS: &[&str] = &["register", "submit", "recall", "unregister", "server-list", "get-submit", "get-server-submits", "get-player-submits"];
 
-    fn transfer(r: &mut dyn io::Read) -> GeneralResult<api::SubmitContent> {
-        api::ReadFrom::read_from(r)
+/**
+ * build a request from the given fields and print it without sending;
+ * fields not given fall back to the config, then to placeholders such as the nil uuid
+ */
+pub fn command_debug_request<'a>(
+    cfg: &mut ClientConfig,
+    kpg: &mut SigningKeyPairGenerator,
+    kind: &'a str,
+    args: &'a ArgMatches,
+    use_real_key: bool,
+    out: &mut dyn io::Write,
+) -> error::AppResult<'a> {
+
+    let signed = matches!(kind, "register" | "submit" | "recall" | "unregister");
+    if signed && use_real_key {
+        cfg.require_cert()?;
     }
+    let cfg_data = cfg.get_data();
+    let api_url: Url = match error::ArgsError::parse_matches_optional(args, "api_url", "url")? {
+        Some(url) => url,
+        None => error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?.clone(),
+    };
+    let server_uuid: Uuid = error::ArgsError::parse_matches_optional(args, "server_uuid", "uuid")?
+        .or(cfg_data.server_uuid)
+        .unwrap_or_else(Uuid::nil);
+    let player_uuid: Uuid = error::ArgsError::parse_matches_optional(args, "player_uuid", "uuid")?.unwrap_or_else(Uuid::nil);
+    let record_uuid: Uuid = error::ArgsError::parse_matches_optional(args, "record_uuid", "uuid")?.unwrap_or_else(Uuid::nil);
+    let points: f32 = error::ArgsError::parse_matches_optional(args, "points", "float$[-1,1]")?.unwrap_or(0.0);
+    let key_id: Option<KeyID> = error::ArgsError::parse_matches_optional(args, "key_id", "hex")?;
+    let limit: Option<usize> = error::ArgsError::parse_matches_optional(args, "limit", "integer")?;
+    let after: Option<u64> = error::ArgsError::parse_matches_optional(args, "after", "unix timestamp")?;
+    let before: Option<u64> = error::ArgsError::parse_matches_optional(args, "before", "unix timestamp")?;
+    let server_name = args.value_of("server_name").unwrap_or("example").to_owned();
+    let comment = args.value_of("comment").unwrap_or_default().to_owned();
+    let timestamp = timestamp(SystemTime::now()).unwrap();
 
-    let submits: Vec<_> = 
-        sc.submits
-            .iter()
-            .filter_map(
-                |s| {
-                    match pgp::verify(cert, &key_id, servers.policy(), None, s.content.as_bytes(), transfer) {
-                        Ok(d) => Some(d),
-                        Err(e) => {
-                            println!("Un-Verified Message");
-                            println!("{}\n", &s.content);
-                            None
-                        }
-                    }
-                }
+    let dummy;
+    let signer = if signed {
+        let (cert, key_id) = if use_real_key {
+            (
+                error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?,
+                error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?.clone(),
             )
-            .collect();
+        } else {
+            dummy = pgp::generate_dummy_cert(cfg.policy())?;
+            (&dummy.0, dummy.1.clone())
+        };
+        Some((cert, kpg.generate(cert, &key_id, None)?))
+    } else {
+        None
+    };
 
-    println!("+ Verified Message");
-    println!("{}\n", ServerDataDisplay(servers.get_data().get(&uuid).unwrap(), &uuid));
-    for d in submits {
-        println!("{:#?}", &d);
+    match kind {
+        "register" => {
+            let (cert, keypair) = signer.unwrap();
+            let req = api::RegisterRequest::new(api::RegisterContent { server_name }, cert, keypair);
+            http::describe(&api_url, &req, out)?;
+        }
+        "submit" => {
+            let (_, keypair) = signer.unwrap();
+            let content = api::SubmitContent { uuid: server_uuid, timestamp, player_uuid, points, comment };
+            http::describe(&api_url, &api::SubmitRequest::new(content, keypair), out)?;
+        }
+        "recall" => {
+            let (_, keypair) = signer.unwrap();
+            let content = api::RecallContent { timestamp, comment };
+            http::describe(&api_url, &api::RecallRequest::new(record_uuid, content, keypair), out)?;
+        }
+        "unregister" => {
+            let (_, keypair) = signer.unwrap();
+            let content = api::UnregisterContent { timestamp, comment };
+            http::describe(&api_url, &api::UnregisterRequest::new(content, keypair, server_uuid), out)?;
+        }
+        "server-list" => {
+            http::describe(&api_url, &api::ServerListRequest::new(limit), out)?;
+        }
+        "get-submit" => {
+            http::describe(&api_url, &api::GetSubmitRequest::new(record_uuid), out)?;
+        }
+        "get-server-submits" => {
+            let handle = match key_id {
+                Some(key_id) => api::ServerHandle::KeyID(key_id),
+                None => api::ServerHandle::ServerUUID(server_uuid),
+            };
+            http::describe(&api_url, &api::GetServerSubmitRequest::new(handle, limit, after, before, Some(player_uuid).filter(|u| !u.is_nil())), out)?;
+        }
+        "get-player-submits" => {
+            http::describe(&api_url, &api::GetPlayerSubmitRequest::new(player_uuid), out)?;
+        }
+        _ => return Err(error::ArgsError::new("kind", "register|submit|recall|unregister|server-list|get-submit|get-server-submits|get-player-submits", kind).into())
     }
 
     Ok(())
 }
 
 
-fn sleep_interval(interval: Duration, last: SystemTime) {
-    let now = SystemTime::now();
-    if let Ok(d) = now.duration_since(last) {
-        if interval > d {
-            let rest = interval - d;
-            thread::sleep(rest)
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_records(name: &str) -> (PathBuf, RecordConfig) {
+        let path = std::env::temp_dir().join(format!("openmprdbc-stdout-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        let mut records = RecordConfig::new(path.clone(), RecordHeader::new(None, None), false).unwrap();
+        records.new_submit(Uuid::from_u128(1), 100, Uuid::from_u128(11));
+        records.new_submit(Uuid::from_u128(2), 200, Uuid::from_u128(12));
+        records.new_recall(Uuid::from_u128(1), 300);
+        records.save().unwrap();
+        (path, records)
     }
-}
 
+    fn capture<F>(f: F) -> String
+    where
+        F: for<'w> FnOnce(&'w mut dyn io::Write) -> error::AppResult<'static>,
+    {
+        let mut buf = Vec::new();
+        assert!(f(&mut buf).is_ok(), "command failed");
+        String::from_utf8(buf).unwrap()
+    }
 
-pub fn command_import<'a>(
-    cfg: &mut ClientConfig,
-    records: &mut RecordConfig,
-    httpc: &http::Client,
-    kpg: &mut SigningKeyPairGenerator,
-    banlist: &'a str,
-    interval: Option<&'a str>,
-    rules: &dyn banlist::GeneratePoints
-) -> error::AppResult<'a> {
-    
-    let reader = File::open(banlist).map_err(error::AppError::new_other)?;
-    let banlist: banlist::BanList = serde_json::from_reader(reader).map_err(error::AppError::new_other)?;
-    let interval = match interval {
-        Some(s) => Duration::from_millis(error::ArgsError::parse(s, "interval", "integer of milliseconds")?),
-        None => Duration::ZERO
-    };
+    #[test]
+    fn records_output_is_only_data() {
+        let (path, records) = temp_records("records");
 
-    let cfg_data = cfg.get_data();
-    let server_uuid = error::ConfigMissing::ok(cfg_data.server_uuid.as_ref(), "client.server_uuid")?.clone();
-    let cert = error::ConfigMissing::ok(cfg_data.get_cert(), "client.cert_file")?;
-    let key_id = error::ConfigMissing::ok(cfg_data.key_id.as_ref(), "client.key_id")?;
-    let keypair = kpg.generate(cert, key_id, None)?;
-    let api_url = error::ConfigMissing::ok(cfg_data.api_url.as_ref(), "client.api_url")?;
+        let json = capture(|out| command_records(&records, None, None, None, true, false, OutputFormat::Json, out));
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 2);
 
-    let mut last = SystemTime::UNIX_EPOCH;
+        let csv = capture(|out| command_records(&records, None, None, None, false, false, OutputFormat::Csv, out));
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines, vec![output::LocalRecordJson::CSV_HEADER, "00000000-0000-0000-0000-000000000002,00000000-0000-0000-0000-00000000000c,200"]);
 
-    for item in banlist.as_slice() {
-        if let Some(record_uuid) = records.check_player_uuid(&item.uuid) {
+        let count = capture(|out| command_records(&records, None, None, None, false, true, OutputFormat::Plain, out));
+        assert_eq!(count, "1\n");
 
-            println!("player #{} existed: {}", &item.uuid, record_uuid);
-            
-        } else {
+        drop(records);
+        fs::remove_file(&path).unwrap();
+    }
 
-            sleep_interval(interval, last);
+    #[test]
+    fn validate_keeps_config_of_missing_cert() {
+        use crate::config::Persist as _;
+
+        let path = std::env::temp_dir().join(format!("openmprdbc-config-{}-validate", std::process::id()));
+        let missing = std::env::temp_dir().join(format!("openmprdbc-config-{}-missing.pgp", std::process::id()));
+        let content = serde_json::to_string_pretty(&serde_json::json!({
+            "cert_file": missing,
+            "key_id": "0123456789ABCDEF",
+            "api_url": "https://example.com/api/",
+        }))
+        .unwrap();
+        fs::write(&path, &content).unwrap();
+
+        let policy = crate::config::build_policy();
+        let mut cfg = ClientConfig::new(path.clone(), policy.as_ref()).unwrap();
+        let mut out = Vec::new();
+        assert!(command_config_validate(&mut cfg, None, &mut out).is_err());
+        assert!(String::from_utf8(out).unwrap().contains("can not be loaded"));
+        assert!(cfg.get_data().key_id.is_some());
+        assert!(!cfg.persist().unwrap());
+        drop(cfg);
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+        fs::remove_file(&path).unwrap();
+    }
 
-            let timestamp = item.created.timestamp() as u64;
-            let player_uuid = item.uuid.clone();
-            let points = rules.generate(item);
-            let comment = item.reason.clone();
+    #[test]
+    fn cert_export_of_unknown_server_writes_nothing() {
+        let dir = std::env::temp_dir().join(format!("openmprdbc-cert-{}-export", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let policy = crate::config::build_policy();
+        let servers = ServersConfig::new(dir.join("servers"), dir.join("serverscert.pgp"), policy.as_ref()).unwrap();
+        let output = dir.join("exported.asc");
+        let output = output.to_str().unwrap();
+
+        let r = command_cert_export(&servers, Some("d2b4e4e2-8c1b-4d47-9f4e-2b6f5a0f3c11"), Some(output));
+        assert!(r.is_err());
+        assert!(!Path::new(output).exists());
+
+        drop(servers);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-            let req = api::SubmitRequest::new(
-                api::SubmitContent{ 
-                    uuid: server_uuid.clone(),
-                    timestamp,
-                    player_uuid,
-                    points,
-                    comment,
-                },
-                &keypair
-            );
+    #[test]
+    fn key_cache_regenerates_for_another_cert() {
+        let key_id = KeyID::new(0x1234_5678_9abc_def0);
+        let old_cert = Fingerprint::from_bytes(&[1; 20]);
+        let new_cert = Fingerprint::from_bytes(&[2; 20]);
+        let mut generated = 0;
+        let mut cache = KeyCache(None);
+        let mut generate = |cache: &mut KeyCache<u32>, key_id: &KeyID, fingerprint: &Fingerprint| -> u32 {
+            *cache.get_or_try_insert_with(key_id, fingerprint.clone(), || {
+                generated += 1;
+                Ok(generated)
+            }).unwrap()
+        };
+
+        assert_eq!(generate(&mut cache, &key_id, &old_cert), 1);
+        assert_eq!(generate(&mut cache, &key_id, &old_cert), 1);
+        // same key id, reloaded cert
+        assert_eq!(generate(&mut cache, &key_id, &new_cert), 2);
+        assert_eq!(generate(&mut cache, &key_id, &new_cert), 2);
+        assert_eq!(generate(&mut cache, &KeyID::new(1), &new_cert), 3);
+        assert_eq!(generate(&mut cache, &key_id, &old_cert), 4);
+    }
 
-            last = SystemTime::now();
-        
-            let s = httpc.request::<api::SubmitRequest, api::SubmitResponse>(api_url, req)?;
+    #[test]
+    fn record_table_rows_are_ordered() {
+        let a = Uuid::parse_str("00000000-0000-0000-0000-00000000000a").unwrap();
+        let b = Uuid::parse_str("00000000-0000-0000-0000-00000000000b").unwrap();
+        let c = Uuid::parse_str("00000000-0000-0000-0000-00000000000c").unwrap();
+        let mut table: RecordTable<u8, f32> = RecordTable::new(vec![0, 1], ',');
+        table.insert(c, 0, 1.0, 100);
+        table.insert(b, 0, 2.0, 200);
+        table.insert(a, 1, 3.0, 200);
+        table.insert(b, 1, 4.0, 50);
+        table.insert(a, 5, 5.0, 10);
+
+        let rows: Vec<_> = table.rows().into_iter().map(|(k, v)| (*k, v.to_vec())).collect();
+        assert_eq!(rows, vec![
+            (b, vec![Some(2.0), Some(4.0)]),
+            (c, vec![Some(1.0), None]),
+            (a, vec![None, Some(3.0)]),
+        ]);
+    }
 
-            println!("succeed\n+ record_uuid: {}", s.uuid);
+    #[test]
+    fn page_cursor_follows_ascending_pages() {
+        let mut cursor = PageCursor::new(Some(10), None);
+        assert!(cursor.advance(&[20, 30, 40]));
+        assert_eq!((cursor.after, cursor.before), (Some(39), None));
+        // unordered within the page, the latest one still bounds it
+        assert!(cursor.advance(&[40, 60, 50]));
+        assert_eq!(cursor.after, Some(59));
+    }
 
-            records.new_submit(s.uuid, timestamp, player_uuid);
-        }
+    #[test]
+    fn page_cursor_follows_descending_pages() {
+        let mut cursor = PageCursor::new(None, Some(100));
+        assert!(cursor.advance(&[90, 80, 70]));
+        assert_eq!((cursor.after, cursor.before), (None, Some(71)));
+        // a page of one second keeps the direction seen before
+        assert!(cursor.advance(&[60, 60]));
+        assert_eq!(cursor.before, Some(61));
     }
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn page_cursor_stalls() {
+        let mut cursor = PageCursor::new(None, None);
+        assert!(!cursor.advance(&[]));
+        assert!(cursor.advance(&[50, 50]));
+        assert_eq!(cursor.after, Some(49));
+        assert!(!cursor.advance(&[50, 50]));
+
+        let mut cursor = PageCursor::new(None, Some(51));
+        cursor.descending = true;
+        assert!(!cursor.advance(&[50, 50]));
+    }
+}
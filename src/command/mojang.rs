@@ -0,0 +1,125 @@
+use std::io;
+use std::borrow::Cow;
+
+use serde::Deserialize;
+use url::Url;
+use uuid::Uuid;
+
+use crate::api_v1::RequestInfo;
+use crate::api_v1::RequestMethod;
+use crate::api_v1::WriteTo;
+use super::http;
+
+
+/**
+ * Mojang profile api, to find the uuid of a player name; kept apart from the OpenMPRDB api
+ */
+pub const PROFILE_API_URL: &str = "https://api.mojang.com/";
+
+
+pub struct ProfileRequest<'a> {
+    name: &'a str,
+}
+
+impl<'a> ProfileRequest<'a> {
+
+    pub fn new(name: &'a str) -> Self {
+        ProfileRequest {
+            name
+        }
+    }
+}
+
+impl<'a> WriteTo for ProfileRequest<'a> {
+    type Error = anyhow::Error;
+
+    fn write_to<W: io::Write + Sync + Send>(&self, _w: W) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a> RequestInfo for ProfileRequest<'a> {
+
+    fn method(&self) -> RequestMethod {
+        RequestMethod::GET
+    }
+
+    fn content_type(&self) -> &'static str {
+        ""
+    }
+
+    fn url<'b>(&self, base_url: &'b Url) -> Cow<'b, Url> {
+        let mut url = base_url.join("users/profiles/minecraft/").unwrap();
+        url.path_segments_mut().unwrap().pop_if_empty().push(self.name);
+        Cow::Owned(url)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ProfileResponse {
+
+    /// without hyphens, which `Uuid` accepts
+    pub id: Uuid,
+}
+
+
+/// player names are 1 to 16 of letters, digits and underscores
+pub fn is_valid_name(name: &str) -> bool {
+    (1..=16).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/**
+ * `Ok(None)` if no player has the name
+ */
+pub fn lookup(httpc: &http::Client, name: &str) -> Result<Option<ProfileResponse>, http::RequsetError> {
+    let api_url = Url::parse(PROFILE_API_URL).unwrap();
+    match httpc.request::<ProfileRequest, ProfileResponse>(&api_url, ProfileRequest::new(name)) {
+        Ok(profile) => Ok(Some(profile)),
+        Err(http::RequsetError::Response(r)) if r.code == 404 || r.code == 204 => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn valid_names() {
+        assert!(is_valid_name("Notch"));
+        assert!(is_valid_name("jeb_"));
+        assert!(is_valid_name("a"));
+        assert!(is_valid_name("sixteen_chars_16"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("seventeen_chars17"));
+        assert!(!is_valid_name("two words"));
+        assert!(!is_valid_name("../ping"));
+        assert!(!is_valid_name("Jöran"));
+    }
+
+    #[test]
+    fn profile_url() {
+        let base = Url::parse(PROFILE_API_URL).unwrap();
+        let req = ProfileRequest::new("Notch");
+        assert_eq!(req.url(&base).as_str(), "https://api.mojang.com/users/profiles/minecraft/Notch");
+
+        // a base with a path, e.g. a proxy, keeps it
+        let base = Url::parse("http://localhost:8080/mojang/").unwrap();
+        assert_eq!(req.url(&base).as_str(), "http://localhost:8080/mojang/users/profiles/minecraft/Notch");
+    }
+
+    #[test]
+    fn profile_url_escapes_the_name() {
+        let base = Url::parse(PROFILE_API_URL).unwrap();
+        let req = ProfileRequest::new("a/b?c");
+        assert_eq!(req.url(&base).as_str(), "https://api.mojang.com/users/profiles/minecraft/a%2Fb%3Fc");
+    }
+
+    #[test]
+    fn profile_response_without_hyphens() {
+        let profile: ProfileResponse = serde_json::from_str(r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch"}"#).unwrap();
+        assert_eq!(profile.id.to_hyphenated_ref().to_string(), "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+    }
+}
@@ -0,0 +1,472 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Result as GeneralResult;
+use serde::Deserialize;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config;
+use super::http;
+use super::mojang;
+
+
+/// a name found is trusted for a week, a name not found for an hour, as names are changed and taken later
+pub const FOUND_TTL: u64 = 7 * 24 * 3600;
+pub const NOT_FOUND_TTL: u64 = 3600;
+
+/// Mojang allows about 600 profile lookups in 10 minutes from one ip; a burst of 10, then one per second
+const BUCKET_CAPACITY: u32 = 10;
+const BUCKET_REFILL: Duration = Duration::from_secs(1);
+
+/// after a 429 wait this long, doubled on each further 429, and give up after `MAX_RETRIES`
+const BACKOFF_START: Duration = Duration::from_secs(2);
+const MAX_RETRIES: u32 = 4;
+
+
+/**
+ * time of the cache and the limiter; tests move it by hand
+ */
+pub trait Clock {
+
+    /// milliseconds since the epoch
+    fn now_millis(&self) -> u64;
+
+    fn sleep(&self, d: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+
+    fn now_millis(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+    }
+
+    fn sleep(&self, d: Duration) {
+        thread::sleep(d)
+    }
+}
+
+
+pub enum LookupError {
+    RateLimited,
+    Failed(anyhow::Error),
+}
+
+impl fmt::Display for LookupError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RateLimited => f.write_str("rate limited by Mojang, try again later"),
+            Self::Failed(e) => e.fmt(f),
+        }
+    }
+}
+
+/**
+ * the lookup behind the cache; `Ok(None)` if no player has the name
+ */
+pub trait ProfileLookup {
+
+    fn lookup(&self, name: &str) -> Result<Option<Uuid>, LookupError>;
+}
+
+impl ProfileLookup for http::Client {
+
+    fn lookup(&self, name: &str) -> Result<Option<Uuid>, LookupError> {
+        match mojang::lookup(self, name) {
+            Ok(profile) => Ok(profile.map(|p| p.id)),
+            Err(http::RequsetError::Response(r)) if r.code == 429 => Err(LookupError::RateLimited),
+            Err(e) => Err(LookupError::Failed(anyhow::anyhow!("{}", e))),
+        }
+    }
+}
+
+
+/**
+ * token bucket for Mojang calls only, apart from any retry of registry requests
+ */
+pub struct TokenBucket {
+    capacity: u32,
+    refill: Duration,
+    tokens: u32,
+    last_refill: u64,
+}
+
+impl TokenBucket {
+
+    pub fn new(capacity: u32, refill: Duration, now_millis: u64) -> Self {
+        TokenBucket {
+            capacity,
+            refill,
+            tokens: capacity,
+            last_refill: now_millis,
+        }
+    }
+
+    /// take a token; if there is none, `Some` tells how long until the next one
+    pub fn take(&mut self, now_millis: u64) -> Option<Duration> {
+        let refill = self.refill.as_millis() as u64;
+        let elapsed = now_millis.saturating_sub(self.last_refill);
+        let n = elapsed / refill;
+        if n > 0 {
+            self.tokens = self.capacity.min(self.tokens.saturating_add(n as u32));
+            self.last_refill = if self.tokens == self.capacity { now_millis } else { self.last_refill + n * refill };
+        }
+        if self.tokens > 0 {
+            self.tokens -= 1;
+            None
+        } else {
+            Some(Duration::from_millis(refill - (now_millis - self.last_refill)))
+        }
+    }
+}
+
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// `None` for a name no player has
+    uuid: Option<Uuid>,
+    /// seconds since the epoch
+    fetched: u64,
+}
+
+/**
+ * player names resolved with Mojang, by lowercase name, saved in `namecache.json`
+ */
+pub struct NameCache {
+    path: PathBuf,
+    entries: BTreeMap<String, CacheEntry>,
+    changed: bool,
+}
+
+impl NameCache {
+
+    pub fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(path.as_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(content.as_str()).ok())
+            .unwrap_or_default();
+        NameCache {
+            path,
+            entries,
+            changed: false,
+        }
+    }
+
+    /// `Some(None)` for a name cached as not found; `None` if not cached or expired
+    pub fn get(&self, name: &str, now: u64) -> Option<Option<Uuid>> {
+        let entry = self.entries.get(name.to_ascii_lowercase().as_str())?;
+        let ttl = if entry.uuid.is_some() { FOUND_TTL } else { NOT_FOUND_TTL };
+        if now < entry.fetched + ttl {
+            Some(entry.uuid)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, uuid: Option<Uuid>, now: u64) {
+        self.entries.insert(name.to_ascii_lowercase(), CacheEntry { uuid, fetched: now });
+        self.changed = true;
+    }
+
+    pub fn save(&mut self) -> GeneralResult<bool> {
+        if self.changed {
+            let entries = &self.entries;
+            config::atomic_write(self.path.as_path(), |ofile| Ok(serde_json::to_writer_pretty(ofile, entries)?))?;
+            self.changed = false;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl config::Persist for NameCache {
+
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn persist(&mut self) -> GeneralResult<bool> {
+        self.save()
+    }
+}
+
+impl Drop for NameCache {
+
+    fn drop(&mut self) {
+        match self.save() {
+            Ok(true) => eprintln!("warning: {}: unsaved changes flushed in destructor", self.path.display()),
+            Ok(false) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+
+/**
+ * outcome of the names of one batch, reported when it is done
+ */
+#[derive(Default, Debug, PartialEq)]
+pub struct Report {
+    pub resolved: usize,
+    pub cached: usize,
+    pub failed: usize,
+    pub rate_limited: usize,
+}
+
+impl fmt::Display for Report {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "player names: {} resolved, {} cached, {} failed, {} rate-limited",
+            self.resolved, self.cached, self.failed, self.rate_limited
+        ))
+    }
+}
+
+/**
+ * resolve names from the cache, or with Mojang within the limits of the bucket, backing off on 429
+ */
+pub struct NameResolver<'a> {
+    cache: &'a mut NameCache,
+    lookup: &'a dyn ProfileLookup,
+    clock: &'a dyn Clock,
+    bucket: TokenBucket,
+    report: Report,
+}
+
+impl<'a> NameResolver<'a> {
+
+    pub fn new(cache: &'a mut NameCache, lookup: &'a dyn ProfileLookup, clock: &'a dyn Clock) -> Self {
+        let bucket = TokenBucket::new(BUCKET_CAPACITY, BUCKET_REFILL, clock.now_millis());
+        NameResolver {
+            cache,
+            lookup,
+            clock,
+            bucket,
+            report: Report::default(),
+        }
+    }
+
+    /// `Ok(None)` if no player has the name
+    pub fn resolve(&mut self, name: &str) -> Result<Option<Uuid>, LookupError> {
+        if let Some(uuid) = self.cache.get(name, self.clock.now_millis() / 1000) {
+            self.report.cached += 1;
+            return Ok(uuid)
+        }
+
+        let mut backoff = BACKOFF_START;
+        let mut retries = 0;
+        loop {
+            while let Some(wait) = self.bucket.take(self.clock.now_millis()) {
+                self.clock.sleep(wait);
+            }
+            match self.lookup.lookup(name) {
+                Ok(uuid) => {
+                    self.cache.insert(name, uuid, self.clock.now_millis() / 1000);
+                    match uuid {
+                        Some(_) => self.report.resolved += 1,
+                        None => self.report.failed += 1,
+                    }
+                    return Ok(uuid)
+                }
+                Err(LookupError::RateLimited) if retries < MAX_RETRIES => {
+                    self.clock.sleep(backoff);
+                    backoff *= 2;
+                    retries += 1;
+                }
+                Err(LookupError::RateLimited) => {
+                    self.report.rate_limited += 1;
+                    return Err(LookupError::RateLimited)
+                }
+                Err(e) => {
+                    self.report.failed += 1;
+                    return Err(e)
+                }
+            }
+        }
+    }
+
+    pub fn report(&self) -> &Report {
+        &self.report
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::Cell;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::str::FromStr;
+
+    use super::*;
+
+    const PLAYER_UUID: &str = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+
+    struct TestClock(Cell<u64>);
+
+    impl Clock for TestClock {
+
+        fn now_millis(&self) -> u64 {
+            self.0.get()
+        }
+
+        fn sleep(&self, d: Duration) {
+            self.0.set(self.0.get() + d.as_millis() as u64);
+        }
+    }
+
+    /// answers in turn, and `Ok(None)` when out of answers
+    struct MockLookup {
+        answers: RefCell<VecDeque<Result<Option<Uuid>, LookupError>>>,
+        calls: Cell<usize>,
+    }
+
+    impl MockLookup {
+
+        fn new(answers: Vec<Result<Option<Uuid>, LookupError>>) -> Self {
+            MockLookup { answers: RefCell::new(answers.into()), calls: Cell::new(0) }
+        }
+    }
+
+    impl ProfileLookup for MockLookup {
+
+        fn lookup(&self, _name: &str) -> Result<Option<Uuid>, LookupError> {
+            self.calls.set(self.calls.get() + 1);
+            self.answers.borrow_mut().pop_front().unwrap_or(Ok(None))
+        }
+    }
+
+    fn player() -> Uuid {
+        Uuid::from_str(PLAYER_UUID).unwrap()
+    }
+
+    fn temp_cache(name: &str) -> NameCache {
+        let path = std::env::temp_dir().join(format!("openmprdbc-namecache-{}-{}.json", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        NameCache::load(path)
+    }
+
+    fn discard(mut cache: NameCache) {
+        cache.changed = false;
+        let _ = fs::remove_file(&cache.path);
+    }
+
+    #[test]
+    fn cache_entries_expire() {
+        let mut cache = temp_cache("expire");
+        cache.insert("Notch", Some(player()), 1000);
+        cache.insert("nobody_here", None, 1000);
+
+        assert_eq!(cache.get("notch", 1000 + FOUND_TTL - 1), Some(Some(player())));
+        assert_eq!(cache.get("NOTCH", 1000 + FOUND_TTL), None);
+        assert_eq!(cache.get("nobody_here", 1000 + NOT_FOUND_TTL - 1), Some(None));
+        assert_eq!(cache.get("nobody_here", 1000 + NOT_FOUND_TTL), None);
+        assert_eq!(cache.get("jeb_", 1000), None);
+        discard(cache);
+    }
+
+    #[test]
+    fn cache_is_saved_and_loaded() {
+        let mut cache = temp_cache("save");
+        cache.insert("Notch", Some(player()), 1000);
+        assert!(cache.save().unwrap());
+        assert!(!cache.save().unwrap());
+
+        let loaded = NameCache::load(cache.path.clone());
+        assert_eq!(loaded.get("notch", 1000), Some(Some(player())));
+        discard(loaded);
+        discard(cache);
+    }
+
+    #[test]
+    fn bucket_limits_bursts() {
+        let mut bucket = TokenBucket::new(3, Duration::from_secs(1), 0);
+        assert_eq!(bucket.take(0), None);
+        assert_eq!(bucket.take(0), None);
+        assert_eq!(bucket.take(0), None);
+        assert_eq!(bucket.take(400), Some(Duration::from_millis(600)));
+        assert_eq!(bucket.take(1000), None);
+        assert_eq!(bucket.take(1000), Some(Duration::from_millis(1000)));
+
+        // refilled up to the capacity only
+        assert_eq!(bucket.take(60_000), None);
+        assert_eq!(bucket.take(60_000), None);
+        assert_eq!(bucket.take(60_000), None);
+        assert!(bucket.take(60_000).is_some());
+    }
+
+    #[test]
+    fn cached_names_skip_the_lookup() {
+        let clock = TestClock(Cell::new(5_000_000));
+        let lookup = MockLookup::new(vec![Ok(Some(player())), Ok(None)]);
+        let mut cache = temp_cache("skip");
+        {
+            let mut resolver = NameResolver::new(&mut cache, &lookup, &clock);
+            assert_eq!(resolver.resolve("Notch").ok(), Some(Some(player())));
+            assert_eq!(resolver.resolve("notch").ok(), Some(Some(player())));
+            assert_eq!(resolver.resolve("nobody_here").ok(), Some(None));
+            assert_eq!(resolver.resolve("nobody_here").ok(), Some(None));
+            assert_eq!(resolver.report(), &Report { resolved: 1, cached: 2, failed: 1, rate_limited: 0 });
+        }
+        assert_eq!(lookup.calls.get(), 2);
+        discard(cache);
+    }
+
+    #[test]
+    fn lookups_wait_for_the_bucket() {
+        let clock = TestClock(Cell::new(0));
+        let lookup = MockLookup::new(vec![]);
+        let mut cache = temp_cache("bucket");
+        {
+            let mut resolver = NameResolver::new(&mut cache, &lookup, &clock);
+            for i in 0..BUCKET_CAPACITY + 2 {
+                resolver.resolve(format!("player{}", i).as_str()).ok().unwrap();
+            }
+        }
+        assert_eq!(clock.0.get(), 2 * BUCKET_REFILL.as_millis() as u64);
+        discard(cache);
+    }
+
+    #[test]
+    fn rate_limit_backs_off() {
+        let clock = TestClock(Cell::new(0));
+        let lookup = MockLookup::new(vec![Err(LookupError::RateLimited), Err(LookupError::RateLimited), Ok(Some(player()))]);
+        let mut cache = temp_cache("backoff");
+        {
+            let mut resolver = NameResolver::new(&mut cache, &lookup, &clock);
+            assert_eq!(resolver.resolve("Notch").ok(), Some(Some(player())));
+            assert_eq!(resolver.report(), &Report { resolved: 1, ..Report::default() });
+        }
+        assert_eq!(clock.0.get(), (BACKOFF_START + BACKOFF_START * 2).as_millis() as u64);
+        discard(cache);
+    }
+
+    #[test]
+    fn rate_limit_gives_up() {
+        let clock = TestClock(Cell::new(0));
+        let answers = (0..=MAX_RETRIES).map(|_| Err(LookupError::RateLimited)).collect();
+        let lookup = MockLookup::new(answers);
+        let mut cache = temp_cache("give-up");
+        {
+            let mut resolver = NameResolver::new(&mut cache, &lookup, &clock);
+            assert!(matches!(resolver.resolve("Notch"), Err(LookupError::RateLimited)));
+            assert!(matches!(resolver.resolve("jeb_"), Ok(None)));
+            assert_eq!(resolver.report(), &Report { failed: 1, rate_limited: 1, ..Report::default() });
+        }
+        assert_eq!(lookup.calls.get(), MAX_RETRIES as usize + 2);
+        assert_eq!(cache.get("notch", clock.0.get() / 1000), None);
+        discard(cache);
+    }
+}
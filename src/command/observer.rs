@@ -0,0 +1,153 @@
+use std::io;
+use std::io::Write as _;
+
+use serde::Serialize;
+use url::Url;
+use uuid::Uuid;
+
+
+/**
+ * what one step of a bulk command is about: a player of an import or batch, or a server of an aggregation
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    Player,
+    Server,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Item<'i> {
+    pub kind: ItemKind,
+    pub index: usize,
+    pub total: usize,
+    pub uuid: &'i Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<&'i str>,
+}
+
+impl<'i> Item<'i> {
+
+    /// 1-based position, as shown in progress lines
+    pub fn n(&self) -> usize {
+        self.index + 1
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum Outcome<'o> {
+    Submitted { record_uuid: Uuid },
+    WouldSubmit,
+    Skipped { record_uuid: &'o Uuid },
+    Protected,
+    Fetched { verified: usize, unverified: usize },
+    Failed { message: String },
+}
+
+
+/**
+ * callbacks on the progress of bulk commands (import, submit --batch, record --auto);
+ * every method defaults to doing nothing, so an observer only implements what it needs
+ */
+pub trait Observer {
+
+    fn on_item_start(&self, _item: &Item) {}
+
+    fn on_item_done(&self, _item: &Item, _outcome: &Outcome) {}
+
+    fn on_warning(&self, _message: &str) {}
+
+    fn on_http_request(&self, _method: &str, _url: &Url) {}
+
+    fn on_verify_result(&self, _server_uuid: &Uuid, _submit_uuid: &Uuid, _result: Result<(), &str>) {}
+}
+
+pub struct NoopObserver;
+
+impl Observer for NoopObserver {}
+
+
+/**
+ * the progress lines printed on stderr by default; honours `--quiet` like the rest of the diagnostics
+ */
+pub struct ProgressPrinter;
+
+impl Observer for ProgressPrinter {
+
+    fn on_item_start(&self, item: &Item) {
+        if item.kind == ItemKind::Server {
+            log_info!("[{}/{}] fetch {} ({})", item.n(), item.total, item.name.unwrap_or(""), item.uuid.to_hyphenated_ref());
+        }
+    }
+
+    fn on_item_done(&self, item: &Item, outcome: &Outcome) {
+        match outcome {
+            Outcome::Submitted { record_uuid } => log_info!("[{}/{}] submitted {} (record: {})", item.n(), item.total, item.uuid, record_uuid),
+            Outcome::Skipped { record_uuid } => log_info!("[{}/{}] skipped {} (record: {})", item.n(), item.total, item.uuid, record_uuid),
+            Outcome::Protected => log_info!("[{}/{}] protected {}", item.n(), item.total, item.uuid),
+            Outcome::Failed { message } => eprintln!("[{}/{}] failed {}: {}", item.n(), item.total, item.uuid, message),
+            Outcome::WouldSubmit | Outcome::Fetched { .. } => {}
+        }
+    }
+
+    fn on_warning(&self, message: &str) {
+        eprintln!("warning: {}", message);
+    }
+
+    fn on_verify_result(&self, _server_uuid: &Uuid, submit_uuid: &Uuid, result: Result<(), &str>) {
+        if let Err(e) = result {
+            eprintln!("Un-Verified Message [{}]: {}", submit_uuid, e);
+        }
+    }
+}
+
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum ProgressEvent<'e> {
+    ItemStart { item: &'e Item<'e> },
+    ItemDone { item: &'e Item<'e>, #[serde(flatten)] outcome: &'e Outcome<'e> },
+    Warning { message: &'e str },
+    HttpRequest { method: &'e str, url: &'e str },
+    VerifyResult { server_uuid: &'e Uuid, submit_uuid: &'e Uuid, verified: bool, #[serde(skip_serializing_if = "Option::is_none")] error: Option<&'e str> },
+}
+
+/**
+ * `--progress-json`: one json object per event on stderr, for scripts that follow a long run
+ */
+pub struct JsonProgress;
+
+impl JsonProgress {
+
+    fn emit(&self, event: &ProgressEvent) {
+        let stderr = io::stderr();
+        let mut w = stderr.lock();
+        if serde_json::to_writer(&mut w, event).is_ok() {
+            let _ = writeln!(w);
+        }
+    }
+}
+
+impl Observer for JsonProgress {
+
+    fn on_item_start(&self, item: &Item) {
+        self.emit(&ProgressEvent::ItemStart { item })
+    }
+
+    fn on_item_done(&self, item: &Item, outcome: &Outcome) {
+        self.emit(&ProgressEvent::ItemDone { item, outcome })
+    }
+
+    fn on_warning(&self, message: &str) {
+        self.emit(&ProgressEvent::Warning { message })
+    }
+
+    fn on_http_request(&self, method: &str, url: &Url) {
+        self.emit(&ProgressEvent::HttpRequest { method, url: url.as_str() })
+    }
+
+    fn on_verify_result(&self, server_uuid: &Uuid, submit_uuid: &Uuid, result: Result<(), &str>) {
+        self.emit(&ProgressEvent::VerifyResult { server_uuid, submit_uuid, verified: result.is_ok(), error: result.err() })
+    }
+}
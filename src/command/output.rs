@@ -0,0 +1,507 @@
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::pgp;
+use crate::api_v1 as api;
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    Plain,
+    Json,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(())
+        }
+    }
+}
+
+impl Default for OutputFormat {
+
+    fn default() -> Self {
+        Self::Plain
+    }
+}
+
+
+/**
+ * quote a csv field when necessary
+ */
+
+pub struct CsvField<'a>(pub &'a str);
+
+impl<'a> fmt::Display for CsvField<'a> {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = self.0;
+        if s.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+            f.write_str("\"")?;
+            f.write_str(s.replace('"', "\"\"").as_str())?;
+            f.write_str("\"")
+        } else {
+            f.write_str(s)
+        }
+    }
+}
+
+
+/**
+ * empty csv field for none
+ */
+
+pub struct CsvOptional<T>(pub Option<T>);
+
+impl<T: fmt::Display> fmt::Display for CsvOptional<T> {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(ref v) => v.fmt(f),
+            None => Ok(())
+        }
+    }
+}
+
+
+/**
+ * serializable shadows of api data
+ */
+
+#[derive(Serialize)]
+pub struct ServerDataJson<'a> {
+    pub id: usize,
+    pub server_name: &'a str,
+    pub uuid: &'a Uuid,
+    pub key_id: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub public_key: String,
+}
+
+impl<'a> From<&'a api::ServerData> for ServerDataJson<'a> {
+
+    fn from(data: &'a api::ServerData) -> Self {
+        let mut buf = Vec::new();
+        let public_key = match pgp::export_publickey(&data.public_key, &mut buf) {
+            Ok(_) => String::from_utf8_lossy(buf.as_slice()).into_owned(),
+            Err(_) => String::new(),
+        };
+        ServerDataJson {
+            id: data.id,
+            server_name: data.server_name.as_str(),
+            uuid: &data.uuid,
+            key_id: data.key_id.to_hex(),
+            public_key,
+        }
+    }
+}
+
+impl<'a> ServerDataJson<'a> {
+
+    pub const CSV_HEADER: &'static str = "id,server_name,uuid,key_id";
+
+    pub fn csv_row(&self) -> String {
+        format!("{},{},{},{}", self.id, CsvField(self.server_name), self.uuid, self.key_id)
+    }
+}
+
+
+#[derive(Serialize)]
+pub struct ServerGetJson<'a> {
+    #[serde(flatten)]
+    pub server: ServerDataJson<'a>,
+    pub fingerprint: String,
+    /// whether the key matches the cert added by `cert --add`; absent if there is none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches_local_cert: Option<bool>,
+}
+
+
+#[derive(Serialize)]
+pub struct ServerInfoJson<'a> {
+    pub server_name: &'a str,
+    pub uuid: &'a Uuid,
+    pub key_id: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub public_key: String,
+    pub fingerprint: String,
+    pub registered: bool,
+    /// whether the registered key is the one of cert_file and key_id in config
+    pub matches_config_key: bool,
+}
+
+impl<'a> ServerInfoJson<'a> {
+
+    pub const CSV_HEADER: &'static str = "server_name,uuid,key_id,fingerprint,matches_config_key";
+
+    pub fn csv_row(&self) -> String {
+        format!("{},{},{},{},{}", CsvField(self.server_name), self.uuid, self.key_id, self.fingerprint, self.matches_config_key)
+    }
+}
+
+
+#[derive(Serialize)]
+pub struct SubmitRecordJson<'a> {
+    pub uuid: &'a Uuid,
+    pub server_uuid: &'a Uuid,
+    pub timestamp: Option<u64>,
+    pub player_uuid: Option<&'a Uuid>,
+    pub points: Option<f32>,
+    pub comment: Option<&'a str>,
+    pub trust: Option<u32>,
+    pub verified_by_fingerprint: Option<String>,
+    pub verified_by_keyid: Option<String>,
+    pub signature_time: Option<u64>,
+    pub unverified_reason: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry_name: Option<&'a str>,
+    /// the signed content as received, kept for unverified submits in `record -o` reports
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_content: Option<&'a str>,
+}
+
+impl<'a> SubmitRecordJson<'a> {
+
+    pub const CSV_HEADER: &'static str = "uuid,server_uuid,timestamp,player_uuid,points,comment,trust,verified_by_fingerprint,verified_by_keyid,signature_time,unverified_reason,registry_name,raw_content";
+
+    pub fn verified(uuid: &'a Uuid, verified: &'a pgp::Verified<api::SubmitContent>, trust: Option<u32>) -> Self {
+        let content = &verified.value;
+        SubmitRecordJson {
+            uuid,
+            server_uuid: &content.uuid,
+            timestamp: Some(content.timestamp),
+            player_uuid: Some(&content.player_uuid),
+            points: Some(content.points),
+            comment: Some(content.comment.as_str()),
+            trust,
+            verified_by_fingerprint: Some(verified.fingerprint.to_hex()),
+            verified_by_keyid: Some(verified.key_id.to_hex()),
+            signature_time: verified.signature_time
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            unverified_reason: None,
+            registry_name: None,
+            raw_content: None,
+        }
+    }
+
+    pub fn unverified(uuid: &'a Uuid, server_uuid: &'a Uuid, trust: Option<u32>, reason: pgp::UnverifiedReason) -> Self {
+        SubmitRecordJson {
+            uuid,
+            server_uuid,
+            timestamp: None,
+            player_uuid: None,
+            points: None,
+            comment: None,
+            trust,
+            verified_by_fingerprint: None,
+            verified_by_keyid: None,
+            signature_time: None,
+            unverified_reason: Some(reason.as_str()),
+            registry_name: None,
+            raw_content: None,
+        }
+    }
+
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}", 
+            self.uuid, 
+            self.server_uuid, 
+            CsvOptional(self.timestamp), 
+            CsvOptional(self.player_uuid), 
+            CsvOptional(self.points), 
+            CsvField(self.comment.unwrap_or_default()),
+            CsvOptional(self.trust),
+            CsvOptional(self.verified_by_fingerprint.as_ref()),
+            CsvOptional(self.verified_by_keyid.as_ref()),
+            CsvOptional(self.signature_time),
+            CsvField(self.unverified_reason.unwrap_or_default()),
+            CsvField(self.registry_name.unwrap_or_default()),
+            CsvField(self.raw_content.unwrap_or_default()),
+        )
+    }
+}
+
+
+#[derive(Serialize)]
+pub struct CertEntryJson<'a> {
+    pub server_uuid: &'a Uuid,
+    pub name: &'a str,
+    pub key_id: String,
+    pub trust: u32,
+    pub fingerprint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<&'a str>,
+    pub valid: bool,
+    /// unix timestamp of the key expiration; absent if it never expires
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<u64>,
+}
+
+
+/**
+ * results of commands that change remote state, tagged with a status for scripts
+ */
+
+#[derive(Serialize)]
+pub struct StatusJson<T: Serialize> {
+    pub status: &'static str,
+    #[serde(flatten)]
+    pub body: T,
+}
+
+impl<T: Serialize> StatusJson<T> {
+
+    pub fn ok(body: T) -> Self {
+        StatusJson { status: "ok", body }
+    }
+
+    pub fn dry_run(body: T) -> Self {
+        StatusJson { status: "dry-run", body }
+    }
+}
+
+
+#[derive(Serialize)]
+pub struct ServerUuidJson {
+    pub server_uuid: Uuid,
+}
+
+/// what `register --dry-run` would send
+#[derive(Serialize)]
+pub struct RequestJson {
+    pub method: &'static str,
+    pub url: String,
+    pub body: serde_json::Value,
+}
+
+
+/**
+ * a submit in the local record file, with the time it was recalled for `records --include-recalled`
+ */
+#[derive(Serialize)]
+pub struct LocalRecordJson<'a> {
+    pub record_uuid: &'a Uuid,
+    pub player_uuid: &'a Uuid,
+    pub timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recalled: Option<u64>,
+}
+
+impl<'a> LocalRecordJson<'a> {
+
+    pub const CSV_HEADER: &'static str = "record_uuid,player_uuid,timestamp";
+    pub const CSV_HEADER_RECALLED: &'static str = "record_uuid,player_uuid,timestamp,recalled";
+
+    /// with `recalled`, the recall time is a last column, empty for an active record
+    pub fn csv_row(&self, recalled: bool) -> String {
+        match (recalled, self.recalled) {
+            (false, _) => format!("{},{},{}", self.record_uuid, self.player_uuid, self.timestamp),
+            (true, Some(t)) => format!("{},{},{},{}", self.record_uuid, self.player_uuid, self.timestamp, t),
+            (true, None) => format!("{},{},{},", self.record_uuid, self.player_uuid, self.timestamp),
+        }
+    }
+}
+
+
+#[derive(Serialize)]
+pub struct SubmittedJson {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record_uuid: Option<Uuid>,
+    pub player_uuid: Uuid,
+    pub points: f32,
+    pub timestamp: u64,
+}
+
+
+#[derive(Serialize)]
+pub struct RecalledJson {
+    pub record_uuid: Uuid,
+}
+
+
+/// one player of `submit -p a -p b ...`: its new record, or why it was not submitted; neither in a dry run
+#[derive(Serialize)]
+pub struct PlayerSubmitJson {
+    pub player_uuid: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record_uuid: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SubmitManyJson {
+    pub points: f32,
+    pub timestamp: u64,
+    pub players: Vec<PlayerSubmitJson>,
+}
+
+
+#[derive(Serialize, Default)]
+pub struct RecallBatchJson {
+    pub recalled: Vec<Uuid>,
+    pub skipped: Vec<Uuid>,
+    pub failed: Vec<Uuid>,
+}
+
+
+#[derive(Serialize)]
+pub struct ErrorJson<'a> {
+    pub status: &'static str,
+    pub code: &'static str,
+    pub exit_code: i32,
+    pub message: &'a str,
+}
+
+impl<'a> ErrorJson<'a> {
+
+    pub fn new(code: &'static str, exit_code: i32, message: &'a str) -> Self {
+        ErrorJson { status: "error", code, exit_code, message }
+    }
+}
+
+
+pub fn write_json<T: Serialize>(out: &mut dyn io::Write, value: &T) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // scripts read these keys; renaming or reordering one is a breaking change
+    fn json<T: Serialize>(value: &T) -> String {
+        serde_json::to_string(value).unwrap()
+    }
+
+    fn uuid(n: u128) -> Uuid {
+        Uuid::from_u128(n)
+    }
+
+    #[test]
+    fn status_keys() {
+        assert_eq!(
+            json(&StatusJson::ok(ServerUuidJson { server_uuid: uuid(1) })),
+            r#"{"status":"ok","server_uuid":"00000000-0000-0000-0000-000000000001"}"#
+        );
+        assert_eq!(json(&StatusJson::dry_run(RecalledJson { record_uuid: uuid(2) })), r#"{"status":"dry-run","record_uuid":"00000000-0000-0000-0000-000000000002"}"#);
+    }
+
+    #[test]
+    fn error_keys() {
+        assert_eq!(json(&ErrorJson::new("E001", 2, "bad")), r#"{"status":"error","code":"E001","exit_code":2,"message":"bad"}"#);
+    }
+
+    #[test]
+    fn submit_record_keys() {
+        let (u, server_uuid) = (uuid(1), uuid(2));
+        let record = SubmitRecordJson::unverified(&u, &server_uuid, Some(3), pgp::UnverifiedReason::BadSignature);
+        assert_eq!(
+            json(&record),
+            concat!(
+                r#"{"uuid":"00000000-0000-0000-0000-000000000001","server_uuid":"00000000-0000-0000-0000-000000000002","#,
+                r#""timestamp":null,"player_uuid":null,"points":null,"comment":null,"trust":3,"#,
+                r#""verified_by_fingerprint":null,"verified_by_keyid":null,"signature_time":null,"unverified_reason":"bad signature"}"#
+            )
+        );
+        assert_eq!(record.csv_row().split(',').count(), SubmitRecordJson::CSV_HEADER.split(',').count());
+    }
+
+    #[test]
+    fn submit_record_csv_quotes_name_and_raw_content() {
+        let (u, server_uuid) = (uuid(1), uuid(2));
+        let mut record = SubmitRecordJson::unverified(&u, &server_uuid, None, pgp::UnverifiedReason::BadSignature);
+        record.registry_name = Some("survival, \"main\"");
+        record.raw_content = Some("-----BEGIN PGP MESSAGE-----\nabc,def\n-----END PGP MESSAGE-----");
+        assert!(SubmitRecordJson::CSV_HEADER.ends_with(",unverified_reason,registry_name,raw_content"));
+        assert_eq!(
+            record.csv_row(),
+            concat!(
+                "00000000-0000-0000-0000-000000000001,00000000-0000-0000-0000-000000000002,,,,,,,,,bad signature,",
+                "\"survival, \"\"main\"\"\",",
+                "\"-----BEGIN PGP MESSAGE-----\nabc,def\n-----END PGP MESSAGE-----\""
+            )
+        );
+
+        record.registry_name = None;
+        record.raw_content = None;
+        assert!(record.csv_row().ends_with(",bad signature,,"));
+    }
+
+    #[test]
+    fn local_record_keys() {
+        let (r, p) = (uuid(1), uuid(2));
+        let active = LocalRecordJson { record_uuid: &r, player_uuid: &p, timestamp: 100, recalled: None };
+        let recalled = LocalRecordJson { record_uuid: &r, player_uuid: &p, timestamp: 100, recalled: Some(200) };
+        assert_eq!(json(&active), r#"{"record_uuid":"00000000-0000-0000-0000-000000000001","player_uuid":"00000000-0000-0000-0000-000000000002","timestamp":100}"#);
+        assert!(json(&recalled).ends_with(r#","timestamp":100,"recalled":200}"#));
+        assert_eq!(active.csv_row(false).split(',').count(), LocalRecordJson::CSV_HEADER.split(',').count());
+        assert_eq!(active.csv_row(true).split(',').count(), LocalRecordJson::CSV_HEADER_RECALLED.split(',').count());
+        assert_eq!(recalled.csv_row(true).split(',').count(), LocalRecordJson::CSV_HEADER_RECALLED.split(',').count());
+    }
+
+    #[test]
+    fn submit_keys() {
+        assert_eq!(
+            json(&SubmittedJson { record_uuid: None, player_uuid: uuid(2), points: -0.5, timestamp: 100 }),
+            r#"{"player_uuid":"00000000-0000-0000-0000-000000000002","points":-0.5,"timestamp":100}"#
+        );
+        let many = SubmitManyJson {
+            points: -1.0,
+            timestamp: 100,
+            players: vec![
+                PlayerSubmitJson { player_uuid: uuid(2), record_uuid: Some(uuid(1)), error: None },
+                PlayerSubmitJson { player_uuid: uuid(3), record_uuid: None, error: Some(String::from("failed")) },
+            ],
+        };
+        assert_eq!(
+            json(&many),
+            concat!(
+                r#"{"points":-1.0,"timestamp":100,"players":["#,
+                r#"{"player_uuid":"00000000-0000-0000-0000-000000000002","record_uuid":"00000000-0000-0000-0000-000000000001"},"#,
+                r#"{"player_uuid":"00000000-0000-0000-0000-000000000003","error":"failed"}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn recall_batch_keys() {
+        assert_eq!(json(&RecallBatchJson::default()), r#"{"recalled":[],"skipped":[],"failed":[]}"#);
+    }
+
+    #[test]
+    fn cert_entry_keys() {
+        let server_uuid = uuid(1);
+        let entry = CertEntryJson {
+            server_uuid: &server_uuid,
+            name: "peer",
+            key_id: String::from("0123456789ABCDEF"),
+            trust: 3,
+            fingerprint: String::from("AB"),
+            api_url: None,
+            valid: true,
+            expires: None,
+        };
+        assert_eq!(
+            json(&entry),
+            r#"{"server_uuid":"00000000-0000-0000-0000-000000000001","name":"peer","key_id":"0123456789ABCDEF","trust":3,"fingerprint":"AB","valid":true}"#
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result as GeneralResult;
+use uuid::Uuid;
+use url::Url;
+
+use crate::api_v1 as api;
+use crate::config;
+use super::http;
+
+
+/**
+ * names of servers as registered in OpenMPRDB, cached from the server list;
+ * not to be confused with the local name given by `cert --add`
+ */
+pub struct RegistryNames {
+    path: PathBuf,
+    names: HashMap<Uuid, String>,
+    changed: bool,
+}
+
+impl RegistryNames {
+
+    pub fn load(path: PathBuf) -> Self {
+        let names = fs::read_to_string(path.as_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(content.as_str()).ok())
+            .unwrap_or_default();
+        RegistryNames {
+            path,
+            names,
+            changed: false,
+        }
+    }
+
+    pub fn update(&mut self, servers: &[api::ServerData]) {
+        for s in servers {
+            self.insert(&s.uuid, &s.server_name);
+        }
+    }
+
+    pub fn insert(&mut self, server_uuid: &Uuid, server_name: &str) {
+        if self.names.get(server_uuid).map(String::as_str) != Some(server_name) {
+            self.names.insert(server_uuid.clone(), server_name.to_owned());
+            self.changed = true;
+        }
+    }
+
+    pub fn get(&self, server_uuid: &Uuid) -> Option<&str> {
+        self.names.get(server_uuid).map(String::as_str)
+    }
+
+    /// look up the cache first; on a miss fetch the server list if `fetch` is given
+    pub fn resolve(&mut self, server_uuid: &Uuid, fetch: Option<(&http::Client, &Url)>) -> Option<&str> {
+        if !self.names.contains_key(server_uuid) {
+            if let Some((httpc, api_url)) = fetch {
+                let req = api::ServerListRequest::new(None);
+                match httpc.request::<api::ServerListRequest, api::ServerListResponse>(api_url, req) {
+                    Ok(s) => self.update(s.servers.as_slice()),
+                    Err(e) => eprintln!("warning: can not fetch server list: {}", e),
+                }
+            }
+        }
+        self.get(server_uuid)
+    }
+
+    pub fn save(&mut self) -> GeneralResult<bool> {
+        if self.changed {
+            let names = &self.names;
+            config::atomic_write(self.path.as_path(), |ofile| Ok(serde_json::to_writer_pretty(ofile, names)?))?;
+            self.changed = false;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl config::Persist for RegistryNames {
+
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn persist(&mut self) -> GeneralResult<bool> {
+        self.save()
+    }
+}
+
+impl Drop for RegistryNames {
+
+    fn drop(&mut self) {
+        match self.save() {
+            Ok(true) => eprintln!("warning: {}: unsaved changes flushed in destructor", self.path.display()),
+            Ok(false) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use uuid::Uuid;
+
+
+/**
+ * a record whose recall state differs between the local record file and the registry
+ */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Discrepancy {
+    /// live in the local record file, but the registry no longer has it (recalled elsewhere)
+    LocalLiveRemoteGone,
+    /// recalled in the local record file, but the registry still serves it
+    LocalRecalledRemoteLive,
+}
+
+impl Discrepancy {
+
+    /// what to run to bring both sides together
+    pub fn remediation(&self, record_uuid: &Uuid) -> String {
+        match self {
+            Self::LocalLiveRemoteGone => {
+                String::from("records sync --auto-heal-local  # append a local recall entry")
+            }
+            Self::LocalRecalledRemoteLive => {
+                format!("recall --record-uuid {} --force  # recall it again on the registry", record_uuid)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Discrepancy {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::LocalLiveRemoteGone => "LOCAL-LIVE/REMOTE-GONE",
+            Self::LocalRecalledRemoteLive => "LOCAL-RECALLED/REMOTE-LIVE",
+        })
+    }
+}
+
+
+/**
+ * compare the recall state of records known on both sides;
+ * records the registry has but the local file never saw are not a recall asymmetry and are left out
+ */
+pub fn classify(local_live: &HashSet<Uuid>, local_recalled: &HashSet<Uuid>, remote_live: &HashSet<Uuid>) -> Vec<(Uuid, Discrepancy)> {
+    let mut found: Vec<(Uuid, Discrepancy)> = local_live
+        .difference(remote_live)
+        .map(|u| (*u, Discrepancy::LocalLiveRemoteGone))
+        .chain(
+            local_recalled
+                .intersection(remote_live)
+                .filter(|u| !local_live.contains(u))
+                .map(|u| (*u, Discrepancy::LocalRecalledRemoteLive))
+        )
+        .collect();
+    found.sort();
+    found
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn uuid(n: u128) -> Uuid {
+        Uuid::from_u128(n)
+    }
+
+    fn set(ns: &[u128]) -> HashSet<Uuid> {
+        ns.iter().map(|n| uuid(*n)).collect()
+    }
+
+    #[test]
+    fn in_sync() {
+        assert!(classify(&set(&[1, 2]), &set(&[3]), &set(&[1, 2])).is_empty());
+        assert!(classify(&set(&[]), &set(&[]), &set(&[])).is_empty());
+    }
+
+    #[test]
+    fn live_locally_gone_remotely() {
+        assert_eq!(classify(&set(&[1, 2]), &set(&[]), &set(&[2])), vec![(uuid(1), Discrepancy::LocalLiveRemoteGone)]);
+    }
+
+    #[test]
+    fn recalled_locally_live_remotely() {
+        assert_eq!(classify(&set(&[]), &set(&[3]), &set(&[3])), vec![(uuid(3), Discrepancy::LocalRecalledRemoteLive)]);
+    }
+
+    #[test]
+    fn unknown_remote_records_are_left_out() {
+        assert!(classify(&set(&[1]), &set(&[2]), &set(&[1, 9])).is_empty());
+    }
+
+    #[test]
+    fn resubmitted_uuid_counts_as_live() {
+        // submitted again after a recall, the record is live on both sides
+        assert!(classify(&set(&[4]), &set(&[4]), &set(&[4])).is_empty());
+    }
+
+    #[test]
+    fn sorted_by_uuid() {
+        let found = classify(&set(&[5, 1]), &set(&[7, 3]), &set(&[7, 3]));
+        assert_eq!(found, vec![
+            (uuid(1), Discrepancy::LocalLiveRemoteGone),
+            (uuid(3), Discrepancy::LocalRecalledRemoteLive),
+            (uuid(5), Discrepancy::LocalLiveRemoteGone),
+            (uuid(7), Discrepancy::LocalRecalledRemoteLive),
+        ]);
+    }
+}
@@ -1,3 +1,4 @@
+use std::env;
 use std::fmt;
 use std::fs;
 use std::str::FromStr;
@@ -5,6 +6,7 @@ use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::Duration;
 
 use anyhow::Result as GeneralResult;
 use serde::Serialize;
@@ -14,6 +16,7 @@ use serde::Deserializer;
 use serde::de;
 use serde::de::Visitor;
 use sequoia_openpgp::KeyID;
+use sequoia_openpgp::Fingerprint;
 use sequoia_openpgp::Cert;
 use sequoia_openpgp::crypto::KeyPair;
 use sequoia_openpgp::policy::Policy;
@@ -34,6 +37,12 @@ pub struct ClientData {
     #[serde(deserialize_with = "deserialize_optional_fromstr")]
     pub key_id: Option<KeyID>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    #[serde(serialize_with = "serialize_optional_fingerprint")]
+    #[serde(deserialize_with = "deserialize_optional_fromstr")]
+    pub fingerprint: Option<Fingerprint>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub server_uuid: Option<Uuid>,
@@ -44,12 +53,90 @@ pub struct ClientData {
     #[serde(deserialize_with = "deserialize_optional_fromstr")]
     pub api_url: Option<Url>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub prompt_timeout_secs: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub protected_players_file: Option<PathBuf>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub auto_refresh_certs: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub confirm_registry: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub require_encrypted_key: Option<bool>,
+
     #[serde(skip)]
     cert: Option<Rc<Cert>>,
 }
 
 
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClientField {
+    CertFile,
+    KeyId,
+    Fingerprint,
+    ServerUuid,
+    ApiUrl,
+    PromptTimeoutSecs,
+    ProtectedPlayersFile,
+    AutoRefreshCerts,
+    ConfirmRegistry,
+    RequireEncryptedKey,
+}
+
+impl ClientField {
+
+    pub const NAMES: &'static str = "cert_file|key_id|fingerprint|server_uuid|api_url|prompt_timeout_secs|protected_players_file|auto_refresh_certs|confirm_registry|require_encrypted_key";
+}
+
+impl FromStr for ClientField {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.replace('-', "_").as_str() {
+            "cert_file" => Ok(Self::CertFile),
+            "key_id" => Ok(Self::KeyId),
+            "fingerprint" => Ok(Self::Fingerprint),
+            "server_uuid" => Ok(Self::ServerUuid),
+            "api_url" => Ok(Self::ApiUrl),
+            "prompt_timeout_secs" => Ok(Self::PromptTimeoutSecs),
+            "protected_players_file" => Ok(Self::ProtectedPlayersFile),
+            "auto_refresh_certs" => Ok(Self::AutoRefreshCerts),
+            "confirm_registry" => Ok(Self::ConfirmRegistry),
+            "require_encrypted_key" => Ok(Self::RequireEncryptedKey),
+            _ => Err(())
+        }
+    }
+}
+
+impl fmt::Display for ClientField {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::CertFile => "cert_file",
+            Self::KeyId => "key_id",
+            Self::Fingerprint => "fingerprint",
+            Self::ServerUuid => "server_uuid",
+            Self::ApiUrl => "api_url",
+            Self::PromptTimeoutSecs => "prompt_timeout_secs",
+            Self::ProtectedPlayersFile => "protected_players_file",
+            Self::AutoRefreshCerts => "auto_refresh_certs",
+            Self::ConfirmRegistry => "confirm_registry",
+            Self::RequireEncryptedKey => "require_encrypted_key",
+        })
+    }
+}
+
+
 impl ClientData {
 
     pub fn cert(&self) -> Option<Rc<Cert>> {
@@ -60,34 +147,91 @@ impl ClientData {
         self.cert.as_ref().map(AsRef::as_ref)
     }
 
+    pub fn prompt_timeout(&self) -> Option<Duration> {
+        self.prompt_timeout_secs.map(Duration::from_secs)
+    }
+
+    /// checks key_id & fingerprint against the cert file; nothing is changed unless all checks pass,
+    /// so a cert that is briefly unavailable or replaced never costs the configured key
     fn update(&mut self, policy: &dyn Policy) -> GeneralResult<bool> {
-        let mut changed = false;
-        if let Some(ref cert_file) = self.cert_file {
-            match pgp::load_cert(cert_file.as_path()) {
-                Ok(cert) => {
-                    let cert = Rc::new(cert);
-                    self.cert = Some(cert.clone());
-                    if let Some(ref key_id) = self.key_id {
-                        if pgp::check_secret_key(cert.as_ref(), policy, None, key_id) {
-                            
-                        } else {
-                            let e = anyhow::anyhow!("invalid key-id of cert: {}", key_id);
-                            self.key_id = None;
-                            changed = true;
-                            return Err(e)
+        let cert_file = match self.cert_file {
+            Some(ref cert_file) => cert_file,
+            None => return Ok(false),
+        };
+        let cert = Rc::new(pgp::load_cert(cert_file.as_path())?);
+        if let Some(ref key_id) = self.key_id {
+            if !pgp::check_secret_key(cert.as_ref(), policy, None, key_id) {
+                return Err(anyhow::anyhow!("invalid key-id of cert: {}", key_id))
+            }
+            if self.require_encrypted_key.unwrap_or(false) && pgp::is_secret_key_encrypted(cert.as_ref(), policy, None, key_id) == Some(false) {
+                return Err(anyhow::anyhow!(
+                    "secret key {} is not protected by a passphrase, refused by require_encrypted_key; protect it with `keyring --protect`", 
+                    key_id
+                ))
+            }
+        }
+        let mut found_key_id = None;
+        if let Some(ref fingerprint) = self.fingerprint {
+            match pgp::find_secret_key_id(cert.as_ref(), policy, None, fingerprint) {
+                Some(key_id) => {
+                    match self.key_id {
+                        Some(ref k) if *k != key_id => {
+                            return Err(anyhow::anyhow!("key-id {} does not match fingerprint {}", k, fingerprint))
                         }
+                        Some(_) => {}
+                        None => found_key_id = Some(key_id),
                     }
                 }
-                Err(e) => {
-                    self.cert_file = None;
-                    changed = true;
-                    return Err(e)
+                None => {
+                    return Err(anyhow::anyhow!("invalid fingerprint of cert: {}", fingerprint))
                 }
             }
         }
+
+        self.cert = Some(cert);
+        let changed = found_key_id.is_some();
+        if found_key_id.is_some() {
+            self.key_id = found_key_id;
+        }
         Ok(changed)
     }
 
+    /// load & check the cert on first use, so that commands not signing anything do not depend on it
+    pub fn require_cert(&mut self, policy: &dyn Policy) -> GeneralResult<bool> {
+        if self.cert.is_some() || self.cert_file.is_none() {
+            return Ok(false)
+        }
+        self.update(policy)
+    }
+
+    fn unset(&mut self, field: ClientField) -> bool {
+        match field {
+            ClientField::CertFile => {
+                // key_id & fingerprint only make sense within the cert file
+                let changed = self.cert_file.is_some() || self.key_id.is_some() || self.fingerprint.is_some();
+                self.cert_file = None;
+                self.cert = None;
+                self.key_id = None;
+                self.fingerprint = None;
+                changed
+            }
+            ClientField::KeyId => {
+                let changed = self.key_id.is_some() || self.fingerprint.is_some();
+                self.key_id = None;
+                self.fingerprint = None;
+                changed
+            }
+            ClientField::Fingerprint => self.fingerprint.take().is_some(),
+            ClientField::ServerUuid => self.server_uuid.take().is_some(),
+            ClientField::ApiUrl => self.api_url.take().is_some(),
+            ClientField::PromptTimeoutSecs => self.prompt_timeout_secs.take().is_some(),
+            ClientField::ProtectedPlayersFile => self.protected_players_file.take().is_some(),
+            ClientField::AutoRefreshCerts => self.auto_refresh_certs.take().is_some(),
+            ClientField::ConfirmRegistry => self.confirm_registry.take().is_some(),
+            ClientField::RequireEncryptedKey => self.require_encrypted_key.take().is_some(),
+        }
+    }
+
     fn try_update_cert_file(&mut self, cert_file: PathBuf, policy: &dyn Policy) -> bool {
         match pgp::load_cert(cert_file.as_path()) {
             Ok(cert) => {
@@ -102,6 +246,20 @@ impl ClientData {
                         return false;
                     }
                 }
+                if let Some(ref fingerprint) = self.fingerprint {
+                    match pgp::find_secret_key_id(cert.as_ref(), policy, None, fingerprint) {
+                        Some(key_id) if self.key_id.is_none() => {
+                            self.key_id = Some(key_id);
+                        }
+                        Some(key_id) if self.key_id.as_ref() == Some(&key_id) => {
+
+                        }
+                        _ => {
+                            self.fingerprint = None;
+                            return false;
+                        }
+                    }
+                }
                 true
             }
             Err(e) => {
@@ -111,6 +269,11 @@ impl ClientData {
     }
 
     fn try_update_key_id(&mut self, key_id: KeyID, policy: &dyn Policy) -> bool {
+        if let Some(ref fingerprint) = self.fingerprint {
+            if KeyID::from(fingerprint) != key_id {
+                return false;
+            }
+        }
         if let Some(ref cert) = self.cert {
             if pgp::check_secret_key(cert.as_ref(), policy, None, &key_id) {
                 self.key_id = Some(key_id);
@@ -123,12 +286,124 @@ impl ClientData {
             true
         }
     }
+
+    fn try_update_fingerprint(&mut self, fingerprint: Fingerprint, policy: &dyn Policy) -> bool {
+        let key_id = if let Some(ref cert) = self.cert {
+            match pgp::find_secret_key_id(cert.as_ref(), policy, None, &fingerprint) {
+                Some(key_id) => key_id,
+                None => return false,
+            }
+        } else {
+            KeyID::from(&fingerprint)
+        };
+        match self.key_id {
+            Some(ref k) if *k != key_id => false,
+            _ => {
+                self.key_id = Some(key_id);
+                self.fingerprint = Some(fingerprint);
+                true
+            }
+        }
+    }
+}
+
+
+/**
+ * on-disk values of the fields taken from environment variables for this run only; written back on drop
+ */
+#[derive(Default)]
+struct EnvOverrides {
+    cert_file: Option<Option<PathBuf>>,
+    /// key_id & fingerprint, kept when either cert_file or key_id is overridden as a cert check may reset both
+    key: Option<(Option<KeyID>, Option<Fingerprint>)>,
+    api_url: Option<Option<Url>>,
+    server_uuid: Option<Option<Uuid>>,
+}
+
+pub const ENV_HELP: &str = "ENVIRONMENT:
+    OPENMPRDB_CERT_FILE      override cert_file
+    OPENMPRDB_KEY_ID         override key_id
+    OPENMPRDB_API_URL        override api_url
+    OPENMPRDB_SERVER_UUID    override server_uuid
+    overrides are used for the current run only and never saved to the config file
+
+";
+
+impl EnvOverrides {
+
+    fn var<T, F: FnOnce(&str) -> Option<T>>(name: &str, parse: F) -> Option<T> {
+        let v = env::var(name).ok().filter(|v| !v.is_empty())?;
+        let parsed = parse(v.as_str());
+        if parsed.is_none() {
+            eprintln!("warning: ignore invalid {}: {}", name, v);
+        }
+        parsed
+    }
+
+    fn apply(data: &mut ClientData) -> Self {
+        let mut overrides = EnvOverrides::default();
+        let cert_file = Self::var("OPENMPRDB_CERT_FILE", |v| Some(PathBuf::from(v)));
+        let key_id = Self::var("OPENMPRDB_KEY_ID", |v| KeyID::from_str(v).ok());
+        if cert_file.is_some() || key_id.is_some() {
+            overrides.key = Some((data.key_id.clone(), data.fingerprint.clone()));
+        }
+        if let Some(v) = cert_file {
+            overrides.cert_file = Some(data.cert_file.replace(v));
+        }
+        if let Some(v) = key_id {
+            data.key_id = Some(v);
+        }
+        if let Some(v) = Self::var("OPENMPRDB_API_URL", |v| Url::from_str(v).ok()) {
+            let mut url = v;
+            if !url.path().ends_with('/') {
+                let path = url.path().to_owned() + "/";
+                url.set_path(path.as_str());
+            }
+            overrides.api_url = Some(data.api_url.replace(url));
+        }
+        if let Some(v) = Self::var("OPENMPRDB_SERVER_UUID", |v| Uuid::from_str(v).ok()) {
+            overrides.server_uuid = Some(data.server_uuid.replace(v));
+        }
+        overrides
+    }
+
+    /// an explicit `config set`/`config unset` of a field drops its override, so the new value is saved
+    fn forget(&mut self, field: ClientField) {
+        match field {
+            ClientField::CertFile => {
+                self.cert_file = None;
+                self.key = None;
+            }
+            ClientField::KeyId | ClientField::Fingerprint => self.key = None,
+            ClientField::ServerUuid => self.server_uuid = None,
+            ClientField::ApiUrl => self.api_url = None,
+            _ => {}
+        }
+    }
+
+    /// the originals come back unconditionally; whatever an overridden field became during this run
+    /// (e.g. reset by a failed cert check) is never saved
+    fn restore(self, data: &mut ClientData) {
+        fn restore_field<T>(field: &mut Option<T>, saved: Option<Option<T>>) {
+            if let Some(original) = saved {
+                *field = original;
+            }
+        }
+        restore_field(&mut data.cert_file, self.cert_file);
+        if let Some((key_id, fingerprint)) = self.key {
+            data.key_id = key_id;
+            data.fingerprint = fingerprint;
+        }
+        restore_field(&mut data.api_url, self.api_url);
+        restore_field(&mut data.server_uuid, self.server_uuid);
+    }
 }
 
 
 pub struct ClientConfig<'a> {
     cfg: super::FileConfig<ClientData>,
     policy: &'a dyn Policy,
+    overrides: EnvOverrides,
 }
 
 impl<'a> ClientConfig<'a> {
@@ -139,8 +414,10 @@ impl<'a> ClientConfig<'a> {
             let mut changed = false;
             if let Some(ref mut cert_file) = data.cert_file {
                 if !cert_file.is_absolute() {
-                    *cert_file = fs::canonicalize(cert_file.as_path())?;
-                    changed |= true;
+                    if let Ok(path) = fs::canonicalize(cert_file.as_path()) {
+                        *cert_file = path;
+                        changed |= true;
+                    }
                 }           
             }
             if let Some(ref mut api_url) = data.api_url {
@@ -152,18 +429,23 @@ impl<'a> ClientConfig<'a> {
                 }
             }
 
-            changed |= data.update(policy)?;
-
             Ok(changed)
         };
 
         let mut cfg = super::FileConfig::new(path, Default::default)?;
         cfg.modify(rectify)?;
+        let mut overrides = EnvOverrides::default();
+        cfg.modify(|data: &mut ClientData| -> Result<bool, ()> {
+            overrides = EnvOverrides::apply(data);
+            Ok(false)
+        })
+        .unwrap();
 
         Ok(
             ClientConfig {
                 cfg,
                 policy,
+                overrides,
             }
         )
     }
@@ -176,10 +458,37 @@ impl<'a> ClientConfig<'a> {
         self.cfg.get_data_mut()
     }
 
+    pub fn require_cert(&mut self) -> GeneralResult<()> {
+        let policy = self.policy;
+        self.cfg.modify(|data: &mut ClientData| data.require_cert(policy))
+    }
+
     pub fn policy(&self) -> &dyn Policy {
         self.policy
     }
 
+    pub fn unset(&mut self, field: ClientField) -> bool {
+        self.overrides.forget(field);
+        let mut changed = false;
+        let p_changed = &mut changed;
+        self.cfg.modify(move |data: &mut ClientData| -> Result<bool, ()> {
+            *p_changed = data.unset(field);
+            Ok(*p_changed)
+        })
+        .unwrap();
+        changed
+    }
+
+    pub fn unset_all(&mut self) -> bool {
+        self.overrides = EnvOverrides::default();
+        self.cfg.modify(|data: &mut ClientData| -> Result<bool, ()> {
+            *data = ClientData::default();
+            Ok(true)
+        })
+        .unwrap();
+        true
+    }
+
     pub fn set_cert_file(&mut self, v: &str) -> bool {
         let path = Path::new(v);
         if path.is_file() {
@@ -189,6 +498,7 @@ impl<'a> ClientConfig<'a> {
                 fs::canonicalize(path).unwrap()
             };
             
+            self.overrides.forget(ClientField::CertFile);
             let policy = self.policy;
             let mut success = false;
             let p_success = &mut success;
@@ -204,7 +514,11 @@ impl<'a> ClientConfig<'a> {
     }
 
     pub fn set_key_id(&mut self, v: &str) -> bool {
+        if let Err(e) = self.require_cert() {
+            eprintln!("warning: cert is currently unloadable: {}", e);
+        }
         if let Ok(key_id) = KeyID::from_str(v) {
+            self.overrides.forget(ClientField::KeyId);
             let policy = self.policy;
             let mut success = false;
             let p_success = &mut success;
@@ -219,8 +533,29 @@ impl<'a> ClientConfig<'a> {
         }
     }
 
+    pub fn set_fingerprint(&mut self, v: &str) -> bool {
+        if let Err(e) = self.require_cert() {
+            eprintln!("warning: cert is currently unloadable: {}", e);
+        }
+        if let Ok(fingerprint) = Fingerprint::from_str(v) {
+            self.overrides.forget(ClientField::Fingerprint);
+            let policy = self.policy;
+            let mut success = false;
+            let p_success = &mut success;
+            self.cfg.modify(move |data: &mut ClientData| -> GeneralResult<bool> {
+                *p_success = data.try_update_fingerprint(fingerprint, policy);
+                Ok(*p_success)
+            })
+            .unwrap();
+            success
+        } else {
+            false
+        }
+    }
+
     pub fn set_server_uuid(&mut self, v: &str) -> bool {
         if let Ok(uuid) = Uuid::from_str(v) {
+            self.overrides.forget(ClientField::ServerUuid);
             self.cfg.get_data_mut().server_uuid = Some(uuid);
             true
         } else {
@@ -228,6 +563,57 @@ impl<'a> ClientConfig<'a> {
         }
     }
 
+    pub fn set_protected_players_file(&mut self, v: &str) -> bool {
+        let path = Path::new(v);
+        if path.is_file() {
+            let path = if path.is_absolute() {
+                path.to_owned()
+            } else {
+                fs::canonicalize(path).unwrap()
+            };
+            self.cfg.get_data_mut().protected_players_file = Some(path);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_auto_refresh_certs(&mut self, v: &str) -> bool {
+        if let Ok(b) = bool::from_str(v) {
+            self.cfg.get_data_mut().auto_refresh_certs = Some(b);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_confirm_registry(&mut self, v: &str) -> bool {
+        if v.is_empty() {
+            false
+        } else {
+            self.cfg.get_data_mut().confirm_registry = Some(v.to_owned());
+            true
+        }
+    }
+
+    pub fn set_require_encrypted_key(&mut self, v: &str) -> bool {
+        if let Ok(b) = bool::from_str(v) {
+            self.cfg.get_data_mut().require_encrypted_key = Some(b);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_prompt_timeout_secs(&mut self, v: &str) -> bool {
+        if let Ok(secs) = u64::from_str(v) {
+            self.cfg.get_data_mut().prompt_timeout_secs = Some(secs);
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn set_api_url(&mut self, v: &str) -> bool {
         if let Ok(mut url) = Url::from_str(v) {
             let path = url.path();
@@ -235,6 +621,7 @@ impl<'a> ClientConfig<'a> {
                 let path = path.to_owned() + "/";
                 url.set_path(path.as_str());
             }
+            self.overrides.forget(ClientField::ApiUrl);
             self.cfg.get_data_mut().api_url = Some(url);
             true
         } else {
@@ -251,7 +638,15 @@ fn serialize_optional_key_id<S: Serializer>(v: &Option<KeyID>, s: S) -> Result<S
     }
 }
 
-fn serialize_optional_api_url<S: Serializer>(v: &Option<Url>, s: S) -> Result<S::Ok, S::Error> {
+fn serialize_optional_fingerprint<S: Serializer>(v: &Option<Fingerprint>, s: S) -> Result<S::Ok, S::Error> {
+    if let Some(v) = v {
+        s.serialize_str(v.to_hex().as_str())
+    } else {
+        s.serialize_none()
+    }
+}
+
+pub fn serialize_optional_api_url<S: Serializer>(v: &Option<Url>, s: S) -> Result<S::Ok, S::Error> {
     if let Some(v) = v {
         s.serialize_str(v.as_str())
     } else {
@@ -259,7 +654,7 @@ fn serialize_optional_api_url<S: Serializer>(v: &Option<Url>, s: S) -> Result<S:
     }
 }
 
-fn deserialize_optional_fromstr<'de, D, T, TE>(d: D) -> Result<Option<T>, D::Error> 
+pub fn deserialize_optional_fromstr<'de, D, T, TE>(d: D) -> Result<Option<T>, D::Error> 
 where
     D: Deserializer<'de>,
     T: FromStr<Err = TE>,
@@ -299,3 +694,78 @@ where
     d.deserialize_option(InnerVisitor(PhantomData))
 }
 
+
+impl<'a> ClientConfig<'a> {
+
+    fn restore_overrides(&mut self) {
+        let overrides = std::mem::take(&mut self.overrides);
+        self.cfg.modify(|data: &mut ClientData| -> Result<bool, ()> {
+            overrides.restore(data);
+            Ok(false)
+        })
+        .unwrap();
+    }
+}
+
+/// environment overrides are taken out before writing and are not applied again afterwards
+impl<'a> super::Persist for ClientConfig<'a> {
+
+    fn describe(&self) -> String {
+        self.cfg.path().display().to_string()
+    }
+
+    fn persist(&mut self) -> GeneralResult<bool> {
+        self.restore_overrides();
+        Ok(self.cfg.save()?)
+    }
+}
+
+impl<'a> Drop for ClientConfig<'a> {
+
+    fn drop(&mut self) {
+        self.restore_overrides();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restore_brings_back_key_reset_by_invalid_override() {
+        let key_id = KeyID::from_str("0123456789ABCDEF").unwrap();
+        let cert_file = PathBuf::from("/data/cert.pgp");
+        let mut data = ClientData::default();
+        data.cert_file = Some(cert_file.clone());
+        data.key_id = Some(key_id.clone());
+
+        // as EnvOverrides::apply with OPENMPRDB_CERT_FILE pointing to a cert without the key
+        let mut overrides = EnvOverrides::default();
+        overrides.key = Some((data.key_id.clone(), data.fingerprint.clone()));
+        overrides.cert_file = Some(data.cert_file.replace(PathBuf::from("/data/other.pgp")));
+        // the cert check of this run drops the key that is not in the overriding cert
+        data.key_id = None;
+        data.fingerprint = None;
+
+        overrides.restore(&mut data);
+        assert_eq!(data.cert_file, Some(cert_file));
+        assert_eq!(data.key_id, Some(key_id));
+    }
+
+    #[test]
+    fn forget_keeps_explicitly_set_value() {
+        let original = Url::from_str("https://example.com/api/").unwrap();
+        let set = Url::from_str("https://example.org/api/").unwrap();
+        let mut data = ClientData::default();
+        data.api_url = Some(original.clone());
+
+        let mut overrides = EnvOverrides::default();
+        overrides.api_url = Some(data.api_url.replace(original));
+        overrides.forget(ClientField::ApiUrl);
+        data.api_url = Some(set.clone());
+
+        overrides.restore(&mut data);
+        assert_eq!(data.api_url, Some(set));
+    }
+}
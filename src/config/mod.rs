@@ -5,8 +5,10 @@ pub mod config;
 
 use std::env;
 use std::io;
+use std::io::Write as _;
 use std::path::Path;
 use std::path::PathBuf;
+use std::fs;
 use std::fs::File;
 use std::fs::OpenOptions;
 
@@ -15,18 +17,75 @@ use serde::Deserialize;
 use serde::de::DeserializeOwned;
 use sequoia_openpgp::policy::Policy;
 
-pub fn current_exe_path<P: AsRef<Path>>(to_join: P) -> io::Result<PathBuf> {
+pub fn current_exe_dir() -> io::Result<PathBuf> {
     let exe = env::current_exe()?;
-    if let Some(path) = exe.parent() {
-        let mut path = PathBuf::from(path);
-        path.push(to_join);
-        Ok(path)
-    } else {
-        Err(io::Error::from(io::ErrorKind::Other))
+    exe.parent().map(PathBuf::from).ok_or_else(|| io::Error::from(io::ErrorKind::Other))
+}
+
+
+/**
+ * directory of config files: `$XDG_CONFIG_HOME/openmprdb-client/`, then `~/.config/openmprdb-client/`,
+ * and the directory of the executable where neither is available (or on non-unix systems);
+ * an existing config next to the executable is kept in use
+ */
+pub fn config_dir() -> io::Result<PathBuf> {
+    let exe_dir = current_exe_dir()?;
+    if !cfg!(unix) || exe_dir.join("config").is_file() {
+        return Ok(exe_dir)
+    }
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").filter(|v| !v.is_empty()).map(|home| PathBuf::from(home).join(".config")));
+    match base {
+        Some(base) => {
+            let dir = base.join("openmprdb-client");
+            fs::create_dir_all(dir.as_path())?;
+            Ok(dir)
+        }
+        None => Ok(exe_dir)
     }
 }
 
 
+/**
+ * write `<path>.tmp` and rename it over `path`, so a crash never leaves a truncated file
+ */
+pub fn atomic_write<P: AsRef<Path>, F: FnOnce(&mut File) -> io::Result<()>>(path: P, f: F) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let written = File::create(tmp.as_path()).and_then(|mut ofile| {
+        f(&mut ofile)?;
+        ofile.flush()?;
+        ofile.sync_all()
+    });
+    match written.and_then(|_| fs::rename(tmp.as_path(), path)) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(tmp.as_path());
+            Err(e)
+        }
+    }
+}
+
+
+/**
+ * state a command opened and writes back when it is done; `Session` in main.rs commits every opened
+ * object in a fixed order, the `Drop` impls only flush what was left uncommitted
+ */
+pub trait Persist {
+
+    /// shown in messages about this state, usually the file
+    fn describe(&self) -> String;
+
+    /// write pending changes; `Ok(false)` if there was nothing to write
+    fn persist(&mut self) -> anyhow::Result<bool>;
+}
+
+
 pub fn build_policy() -> Box<dyn Policy> {
     use sequoia_openpgp::policy::StandardPolicy;
 
@@ -52,8 +111,11 @@ impl<D: Serialize + DeserializeOwned> FileConfig<D> {
             },
             Err(e) => {
                 if e.kind() == io::ErrorKind::NotFound {
-                    let ofile = OpenOptions::new().write(true).create_new(true).open(path.as_path())?;
-                    default()
+                    // created with the defaults, as an empty file would fail to parse on the next run
+                    let data = default();
+                    let mut ofile = OpenOptions::new().write(true).create_new(true).open(path.as_path())?;
+                    serde_json::to_writer_pretty(&mut ofile, &data)?;
+                    data
                 } else {
                     return Err(e)
                 }
@@ -74,14 +136,19 @@ impl<D: Serialize + DeserializeOwned> FileConfig<D> {
 
     pub fn save(&mut self) -> io::Result<bool> {
         if self.changed {
-            let mut ofile = File::create(self.path.as_path())?;
-            serde_json::to_writer_pretty(&mut ofile, &self.data)?;
+            let data = &self.data;
+            atomic_write(self.path.as_path(), |ofile| Ok(serde_json::to_writer_pretty(ofile, data)?))?;
+            self.changed = false;
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
     pub fn get_data(&self) -> &D {
         &self.data
     }
@@ -91,17 +158,11 @@ impl<D: Serialize + DeserializeOwned> FileConfig<D> {
         &mut self.data
     }
 
+    /// a callback that fails must leave the data as it found it; its error never marks the config changed
     pub fn modify<F: FnOnce(&mut D) -> Result<bool, E>, E>(&mut self, callback: F) -> Result<(), E> {
-        match callback(&mut self.data) {
-            Ok(changed) => {
-                self.changed = changed;
-                Ok(())
-            }
-            Err(e) => {
-                self.changed = true;
-                Err(e)
-            }
-        }
+        let changed = callback(&mut self.data)?;
+        self.changed |= changed;
+        Ok(())
     }
 }
 
@@ -110,8 +171,10 @@ impl<D: Serialize + DeserializeOwned> FileConfig<D> {
 impl<D: Serialize + DeserializeOwned> Drop for FileConfig<D> {
     
     fn drop(&mut self) {
-        if let Err(e) = self.save() {
-            eprintln!("{}", e)
+        match self.save() {
+            Ok(true) => eprintln!("warning: {}: unsaved changes flushed in destructor", self.path.display()),
+            Ok(false) => {}
+            Err(e) => eprintln!("{}", e),
         }
     }
 }
@@ -6,11 +6,17 @@ use std::str::FromStr;
 use std::io;
 use std::io::Write as _;
 use std::io::BufRead as _;
+use std::path::Path;
 use std::path::PathBuf;
+use std::fs;
 use std::fs::File;
 use std::fs::OpenOptions;
 
+use serde::Serialize;
 use uuid::Uuid;
+use url::Url;
+
+const RECORD_VERSION: u32 = 2;
 
 struct SubmitRecord {
     record_uuid: Uuid,
@@ -23,6 +29,23 @@ struct RecallRecord {
     timestamp: u64,
 }
 
+/**
+ * a submit in the record file, with the time it was recalled if it was
+ */
+pub struct RecordEntry {
+    pub record_uuid: Uuid,
+    pub player_uuid: Uuid,
+    pub timestamp: u64,
+    pub recalled: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct ExportedRecord<'a> {
+    record_uuid: &'a Uuid,
+    player_uuid: &'a Uuid,
+    timestamp: u64,
+}
+
 enum Record {
     Submit(SubmitRecord),
     Recall(RecallRecord)
@@ -30,10 +53,67 @@ enum Record {
 
 /**
  * format
+ * # api_url=<url> server_uuid=<uuid> v=<version>
  * + <record_uuid>:<timestamp> <player_uuid>
  * - <record_uuid>:<timestamp>
  */
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordHeader {
+    pub api_url: Option<Url>,
+    pub server_uuid: Option<Uuid>,
+    pub version: u32,
+}
+
+impl RecordHeader {
+
+    pub fn new(api_url: Option<Url>, server_uuid: Option<Uuid>) -> Self {
+        RecordHeader {
+            api_url,
+            server_uuid,
+            version: RECORD_VERSION,
+        }
+    }
+
+    /// a field known on one side only is a conflict too, so a header written before api_url was set still mismatches later
+    pub fn conflicts(&self, other: &RecordHeader) -> bool {
+        self.api_url != other.api_url || self.server_uuid != other.server_uuid
+    }
+}
+
+impl fmt::Display for RecordHeader {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("#")?;
+        if let Some(api_url) = &self.api_url {
+            f.write_fmt(format_args!(" api_url={}", api_url))?;
+        }
+        if let Some(server_uuid) = &self.server_uuid {
+            f.write_fmt(format_args!(" server_uuid={}", server_uuid))?;
+        }
+        f.write_fmt(format_args!(" v={}", self.version))
+    }
+}
+
+impl FromStr for RecordHeader {
+    type Err = usize;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix('#').ok_or_else(|| 0usize)?;
+        let mut header = RecordHeader { api_url: None, server_uuid: None, version: 1 };
+        for pair in rest.split_whitespace() {
+            let (key, value) = pair.split_once('=').ok_or_else(|| s.find(pair).unwrap())?;
+            match key {
+                "api_url" => header.api_url = Some(Url::from_str(value).map_err(|_| s.find(value).unwrap())?),
+                "server_uuid" => header.server_uuid = Some(Uuid::from_str(value).map_err(|_| s.find(value).unwrap())?),
+                "v" => header.version = u32::from_str(value).map_err(|_| s.find(value).unwrap())?,
+                _ => { }
+            }
+        }
+        Ok(header)
+    }
+}
+
 impl fmt::Display for Record {
 
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -93,44 +173,96 @@ impl FromStr for Record {
 
 pub struct RecordConfig {
     path: PathBuf,
-    cache: HashMap<Uuid, Uuid>, // submit_uuid +=> record
+    header: Option<RecordHeader>, // None for legacy headerless files
+    origin: RecordHeader, // api_url & server_uuid of the current config
+    cache: HashMap<Uuid, (Uuid, u64)>, // submit_uuid +=> (player_uuid, timestamp)
     index: HashMap<Uuid, Uuid>, // player_uuid +=> submit_uuid
+    history: Vec<RecordEntry>,
     change: Vec<Record>,
 }
 
 impl RecordConfig {
 
-    pub fn new(path: PathBuf) -> io::Result<Self> {
+    /// opens the record file, creating it with the `origin` header if it does not exist yet
+    pub fn new(path: PathBuf, origin: RecordHeader, accept_origin: bool) -> io::Result<Self> {
+        match Self::open(path.clone(), origin.clone(), accept_origin) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let mut ofile = OpenOptions::new().write(true).create_new(true).open(path.as_path())?;
+                ofile.write_fmt(format_args!("{}\n", &origin))?;
+                Ok(
+                    RecordConfig {
+                        path,
+                        header: Some(origin.clone()),
+                        origin,
+                        cache: HashMap::new(),
+                        index: HashMap::new(),
+                        history: Vec::new(),
+                        change: Vec::new(),
+                    }
+                )
+            }
+            r => r
+        }
+    }
+
+    /// opens an existing record file without creating it; a missing file is a `NotFound` error
+    pub fn open(path: PathBuf, origin: RecordHeader, accept_origin: bool) -> io::Result<Self> {
         
         let mut index = HashMap::new();
+        let mut history = Vec::new();
+        let mut header = None;
         let cache = match File::open(path.as_path()) {
             Ok(ifile) => {
                 let mut cache = HashMap::new();
                 let reader = io::BufReader::new(ifile);
+                let mut first = true;
                 for maybe_line in reader.lines() {
                     let line = maybe_line?;
                     if line.is_empty() {
                         continue
                     }
+                    if line.starts_with('#') {
+                        if first {
+                            let h = RecordHeader::from_str(line.as_str()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("`{}` @{}", line.as_str(), e)))?;
+                            if h.conflicts(&origin) {
+                                if accept_origin {
+                                    eprintln!("warning: records in {} were created for `{}`, current config is `{}`", path.display(), &h, &origin);
+                                } else {
+                                    return Err(io::Error::new(
+                                        io::ErrorKind::InvalidData, 
+                                        format!(
+                                            "records in {} belong to a different registry or identity: file has `{}`, current config is `{}`; use --accept-records-origin to use them anyway", 
+                                            path.display(), &h, &origin
+                                        )
+                                    ));
+                                }
+                            }
+                            header = Some(h);
+                        }
+                        first = false;
+                        continue
+                    }
+                    first = false;
                     let record = Record::from_str(line.as_str()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("`{}` @{}", line.as_str(), e)))?;
                     match record {
                         Record::Submit(s) => {
-                            cache.insert(s.record_uuid.clone(), s.player_uuid.clone());
+                            cache.insert(s.record_uuid.clone(), (s.player_uuid.clone(), s.timestamp));
+                            history.push(RecordEntry { record_uuid: s.record_uuid, player_uuid: s.player_uuid, timestamp: s.timestamp, recalled: None });
                         }
                         Record::Recall(r)=> {
                             cache.remove(&r.record_uuid);
+                            mark_recalled(&mut history, &r);
                         }
                     }
                 }
-                for (record_uuid, player_uuid) in cache.iter() {
+                for (record_uuid, (player_uuid, _)) in cache.iter() {
                     index.insert(player_uuid.clone(), record_uuid.clone());
                 }
                 cache
             },
             Err(e) => {
                 if e.kind() == io::ErrorKind::NotFound {
-                    let ofile = OpenOptions::new().write(true).create_new(true).open(path.as_path())?;
-                    HashMap::new()
+                    return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} does not exist", path.display())))
                 } else {
                     return Err(e.into())
                 }
@@ -140,8 +272,11 @@ impl RecordConfig {
         Ok(
             RecordConfig {
                 path,
+                header,
+                origin,
                 cache,
                 index,
+                history,
                 change: Vec::new(),
             }
         )
@@ -150,9 +285,11 @@ impl RecordConfig {
     pub fn save(&mut self) -> io::Result<bool> {
         if self.change.len() > 0 {
             let mut ofile = OpenOptions::new().append(true).create(true).open(self.path.as_path())?;
+            // appending never truncates what is already recorded; sync so a crash loses at most the last line
             for r in self.change.as_slice() {
                 ofile.write_fmt(format_args!("{}\n", r))?;
             }
+            ofile.sync_all()?;
             self.change.clear();
             Ok(true)
         } else {
@@ -162,13 +299,14 @@ impl RecordConfig {
 
     pub fn new_submit(&mut self, record_uuid: Uuid, timestamp: u64, player_uuid: Uuid) -> bool {
         match self.cache.entry(record_uuid) {
-            Entry::Occupied(o) => {
+            Entry::Occupied(_) => {
                 false
             }
             Entry::Vacant(v) => {
                 let r = SubmitRecord { record_uuid, timestamp, player_uuid };
-                v.insert(r.player_uuid.clone());
+                v.insert((r.player_uuid.clone(), r.timestamp));
                 self.index.insert(r.player_uuid.clone(), r.record_uuid.clone());
+                self.history.push(RecordEntry { record_uuid, player_uuid, timestamp, recalled: None });
                 self.change.push(Record::Submit(r));
                 true
             }
@@ -176,9 +314,10 @@ impl RecordConfig {
     }
 
     pub fn new_recall(&mut self, record_uuid: Uuid, timestamp: u64) -> bool {
-        if let Some(player_uuid) = self.cache.remove(&record_uuid) {
+        if let Some((player_uuid, _)) = self.cache.remove(&record_uuid) {
             let r = RecallRecord { record_uuid, timestamp };
             self.index.remove(&player_uuid);
+            mark_recalled(&mut self.history, &r);
             self.change.push(Record::Recall(r));
             true
         } else {
@@ -187,19 +326,245 @@ impl RecordConfig {
     }
 
     pub fn check_record_uuid(&self, record_uuid: &Uuid) -> Option<&Uuid> {
-        self.cache.get(record_uuid)
+        self.cache.get(record_uuid).map(|(player_uuid, _)| player_uuid)
+    }
+
+    /// active submits with `after < timestamp < before`, as (record_uuid, player_uuid, timestamp) in time order
+    pub fn submits_in_range(&self, after: u64, before: u64) -> Vec<(&Uuid, &Uuid, u64)> {
+        let mut submits: Vec<_> = self.cache.iter()
+            .filter(|(_, (_, timestamp))| after < *timestamp && *timestamp < before)
+            .map(|(record_uuid, (player_uuid, timestamp))| (record_uuid, player_uuid, *timestamp))
+            .collect();
+        submits.sort_by_key(|(_, _, timestamp)| *timestamp);
+        submits
     }
 
     pub fn check_player_uuid(&self, player_uuid: &Uuid) -> Option<&Uuid> {
         self.index.get(player_uuid)
     }
+
+    pub fn players(&self) -> impl Iterator<Item = &Uuid> {
+        self.index.keys()
+    }
+
+    /// all submits in file order, including recalled ones
+    pub fn entries(&self) -> impl Iterator<Item = &RecordEntry> {
+        self.history.iter()
+    }
+
+    /// number of active records and of distinct players among them
+    pub fn stats(&self) -> (usize, usize) {
+        (self.cache.len(), self.index.len())
+    }
+
+    /// active submits in time order
+    fn exported(&self) -> Vec<ExportedRecord<'_>> {
+        let mut exported: Vec<_> = self.cache.iter()
+            .map(|(record_uuid, (player_uuid, timestamp))| ExportedRecord { record_uuid, player_uuid, timestamp: *timestamp })
+            .collect();
+        exported.sort_by_key(|e| e.timestamp);
+        exported
+    }
+
+    /// write active submits as csv: `record_uuid,player_uuid,timestamp`
+    pub fn export_csv<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(b"record_uuid,player_uuid,timestamp\n")?;
+        for e in self.exported() {
+            w.write_fmt(format_args!("{},{},{}\n", e.record_uuid, e.player_uuid, e.timestamp))?;
+        }
+        w.flush()
+    }
+
+    /// write active submits as a json array of `{record_uuid, player_uuid, timestamp}`
+    pub fn export_json<W: io::Write>(&self, w: W) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(w, &self.exported())
+    }
+
+    /**
+     * rewrite the file with the header and one `+` line per active record, dropping recalled submits;
+     * a legacy headerless file gets the header of the current config; returns the number of lines removed
+     */
+    pub fn compact(&mut self) -> io::Result<usize> {
+        self.save()?;
+
+        let before = io::BufReader::new(File::open(self.path.as_path())?)
+            .lines()
+            .filter(|line| line.as_ref().map_or(true, |l| !l.is_empty() && !l.starts_with('#')))
+            .count();
+
+        self.history.retain(|e| e.recalled.is_none());
+        let mut header = self.header.clone().unwrap_or_else(|| self.origin.clone());
+        header.version = RECORD_VERSION;
+        let history = &self.history;
+        super::atomic_write(self.path.as_path(), |ofile| {
+            ofile.write_fmt(format_args!("{}\n", &header))?;
+            for e in history.iter() {
+                let r = Record::Submit(SubmitRecord { record_uuid: e.record_uuid, timestamp: e.timestamp, player_uuid: e.player_uuid });
+                ofile.write_fmt(format_args!("{}\n", r))?;
+            }
+            Ok(())
+        })?;
+        self.header = Some(header);
+
+        Ok(before.saturating_sub(self.history.len()))
+    }
+}
+
+fn mark_recalled(history: &mut Vec<RecordEntry>, r: &RecallRecord) {
+    if let Some(e) = history.iter_mut().rev().find(|e| e.record_uuid == r.record_uuid && e.recalled.is_none()) {
+        e.recalled = Some(r.timestamp);
+    }
+}
+
+/**
+ * carry the record file of one server identity over to another, with the header rewritten for `origin`;
+ * `Ok(false)` if `from` does not exist, and an existing `to` is never overwritten
+ */
+pub fn move_records(from: &Path, to: &Path, origin: &RecordHeader) -> io::Result<bool> {
+    let content = match fs::read_to_string(from) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if to.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", to.display())));
+    }
+    let body = match content.split_once('\n') {
+        Some((first, rest)) if first.starts_with('#') => rest,
+        _ => content.as_str(),
+    };
+    super::atomic_write(to, |ofile| {
+        ofile.write_fmt(format_args!("{}\n", origin))?;
+        ofile.write_all(body.as_bytes())
+    })?;
+    fs::remove_file(from)?;
+    Ok(true)
+}
+
+impl super::Persist for RecordConfig {
+
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn persist(&mut self) -> anyhow::Result<bool> {
+        Ok(self.save()?)
+    }
 }
 
 impl Drop for RecordConfig {
 
     fn drop(&mut self) {
-        if let Err(e) = self.save() {
-            eprintln!("{}", e)
+        match self.save() {
+            Ok(true) => eprintln!("warning: {}: unsaved changes flushed in destructor", self.path.display()),
+            Ok(false) => {}
+            Err(e) => eprintln!("{}", e),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const API_URL: &str = "https://example.com/api/";
+    const SERVER_UUID: &str = "3f2b1a5e-6c1d-4a8e-9b7f-0c2d4e6f8a10";
+    const RECORD_UUID: &str = "9d7e4a2c-1b3f-4c5d-8e6f-7a8b9c0d1e2f";
+    const PLAYER_UUID: &str = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("openmprdbc-records-{}-{}", std::process::id(), name))
+    }
+
+    fn origin() -> RecordHeader {
+        RecordHeader::new(Some(Url::parse(API_URL).unwrap()), Some(Uuid::from_str(SERVER_UUID).unwrap()))
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let header = origin();
+        let line = header.to_string();
+        assert_eq!(line, format!("# api_url={} server_uuid={} v={}", API_URL, SERVER_UUID, RECORD_VERSION));
+        assert_eq!(RecordHeader::from_str(line.as_str()).unwrap(), header);
+
+        let partial = RecordHeader::new(None, None);
+        assert_eq!(RecordHeader::from_str(partial.to_string().as_str()).unwrap(), partial);
+    }
+
+    #[test]
+    fn header_parse_errors() {
+        assert_eq!(RecordHeader::from_str("api_url=x").unwrap_err(), 0);
+        assert_eq!(RecordHeader::from_str("# server_uuid=not-a-uuid").unwrap_err(), 14);
+        assert_eq!(RecordHeader::from_str("# v").unwrap_err(), 2);
+        assert_eq!(RecordHeader::from_str("#").unwrap().version, 1);
+    }
+
+    #[test]
+    fn header_conflicts() {
+        let header = origin();
+        assert!(!header.conflicts(&origin()));
+
+        let other_server = RecordHeader::new(header.api_url.clone(), Some(Uuid::nil()));
+        assert!(header.conflicts(&other_server));
+
+        let no_api_url = RecordHeader::new(None, header.server_uuid);
+        assert!(header.conflicts(&no_api_url));
+        assert!(no_api_url.conflicts(&header));
+        assert!(!no_api_url.conflicts(&RecordHeader::new(None, header.server_uuid)));
+    }
+
+    #[test]
+    fn legacy_file_gets_header_on_compaction() {
+        let path = temp_path("legacy");
+        fs::write(&path, format!("+ {}:100 {}\n+ {}:200 {}\n- {}:300\n", RECORD_UUID, PLAYER_UUID, SERVER_UUID, PLAYER_UUID, SERVER_UUID)).unwrap();
+
+        let mut records = RecordConfig::new(path.clone(), origin(), false).unwrap();
+        assert!(records.header.is_none());
+        assert_eq!(records.compact().unwrap(), 2);
+        drop(records);
+
+        let content = fs::read_to_string(&path).unwrap();
+        let mut lines = content.lines();
+        assert_eq!(RecordHeader::from_str(lines.next().unwrap()).unwrap(), origin());
+        assert_eq!(lines.next().unwrap(), format!("+ {}:100 {}", RECORD_UUID, PLAYER_UUID));
+        assert_eq!(lines.next(), None);
+
+        let records = RecordConfig::new(path.clone(), origin(), false).unwrap();
+        assert_eq!(records.header, Some(origin()));
+        drop(records);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn mismatched_origin_is_refused() {
+        let path = temp_path("mismatch");
+        let file_header = RecordHeader::new(None, Some(Uuid::from_str(SERVER_UUID).unwrap()));
+        fs::write(&path, format!("{}\n+ {}:100 {}\n", file_header, RECORD_UUID, PLAYER_UUID)).unwrap();
+
+        let e = RecordConfig::new(path.clone(), origin(), false).err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(e.to_string().contains("--accept-records-origin"));
+
+        let records = RecordConfig::new(path.clone(), origin(), true).unwrap();
+        assert_eq!(records.stats(), (1, 1));
+        drop(records);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn open_does_not_create_the_file() {
+        let path = temp_path("missing");
+        let _ = fs::remove_file(&path);
+
+        let e = RecordConfig::open(path.clone(), origin(), false).err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+        assert!(!path.exists());
+
+        let records = RecordConfig::new(path.clone(), origin(), false).unwrap();
+        assert_eq!(records.header, Some(origin()));
+        drop(records);
+        assert!(RecordConfig::open(path.clone(), origin(), false).is_ok());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -19,7 +19,10 @@ use sequoia_openpgp::KeyID;
 use sequoia_openpgp::Cert;
 use sequoia_openpgp::policy::Policy;
 use uuid::Uuid;
+use url::Url;
 use crate::pgp;
+use super::client::serialize_optional_api_url;
+use super::client::deserialize_optional_fromstr;
 
 #[derive(Serialize, Deserialize)]
 pub struct ServerData {
@@ -32,6 +35,12 @@ pub struct ServerData {
 
     pub trust: u32,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    #[serde(serialize_with = "serialize_optional_api_url")]
+    #[serde(deserialize_with = "deserialize_optional_fromstr")]
+    pub api_url: Option<Url>,
+
     #[serde(skip)]
     cert: Option<Rc<Cert>>,
 }
@@ -43,6 +52,7 @@ impl ServerData {
             name,
             key_id,
             trust,
+            api_url: None,
             cert: None
         }
     }
@@ -74,9 +84,9 @@ impl<'a> ServersConfig<'a> {
             let mut changed = false;
             let old_len = data.len();
             {
-                let mut indexs: HashMap<KeyID, &mut ServerData> = HashMap::new();
+                let mut indexs: HashMap<KeyID, Vec<&mut ServerData>> = HashMap::new();
                 for s in data.values_mut() {
-                    indexs.insert(s.key_id.clone(), s);
+                    indexs.entry(s.key_id.clone()).or_insert_with(Vec::new).push(s);
                 }
                 let certs = match File::open(cert_file_path) {
                     Ok(ifile) => {
@@ -94,8 +104,10 @@ impl<'a> ServersConfig<'a> {
                 for cert in certs {
                     let cert = Rc::new(cert);
                     for key_id in pgp::iter_cert(cert.as_ref(), p, None) {
-                        if let Some(s) = indexs.get_mut(&key_id) {
-                            (*s).cert = Some(cert.clone());
+                        if let Some(v) = indexs.get_mut(&key_id) {
+                            for s in v.iter_mut() {
+                                s.cert = Some(cert.clone());
+                            }
                             break;
                         }
                     }
@@ -109,22 +121,29 @@ impl<'a> ServersConfig<'a> {
             Ok(changed)
         })?;
 
-        Ok(
-            ServersConfig {
-                cfg,
-                cert_file,
-                policy
-            }
-        )
+        let servers = ServersConfig {
+            cfg,
+            cert_file,
+            policy
+        };
+        for (key_id, uuids) in servers.shared_key_ids() {
+            let uuids: Vec<String> = uuids.iter().map(Uuid::to_string).collect();
+            eprintln!("warning: key_id {} is shared by servers: {}", key_id.to_hex(), uuids.join(", "));
+        }
+
+        Ok(servers)
     }
 
     pub fn save(&mut self) -> GeneralResult<bool> {
         if self.cfg.save()? {
             let data = self.cfg.get_data();
-            let mut ofile = File::create(self.cert_file.as_path())?;
-            for e in data.values() {
-                pgp::export_publickey_raw(e.cert.as_ref().unwrap().as_ref(), &mut ofile)?;
-            }
+            super::atomic_write(self.cert_file.as_path(), |ofile| {
+                for e in data.values() {
+                    pgp::export_publickey_raw(e.cert.as_ref().unwrap().as_ref(), &mut *ofile)
+                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                }
+                Ok(())
+            })?;
             Ok(true)
         } else {
             Ok(false)
@@ -176,6 +195,57 @@ impl<'a> ServersConfig<'a> {
         success
     }
 
+    pub fn set_trust(&mut self, server_uuid: &Uuid, trust: u32, name: Option<String>) -> bool {
+        let mut success = false;
+        let p_success = &mut success;
+        self.cfg.modify(move |data: &mut HashMap<Uuid, ServerData>| -> Result<bool, ()> {
+            if let Some(v) = data.get_mut(server_uuid) {
+                v.trust = trust;
+                if let Some(name) = name {
+                    v.name = name;
+                }
+                *p_success = true;
+            }
+            Ok(*p_success)
+        })
+        .unwrap();
+        success
+    }
+
+    pub fn replace_cert(&mut self, server_uuid: &Uuid, cert: Rc<Cert>, key_id: KeyID) -> bool {
+        let mut success = false;
+        let p_success = &mut success;
+        let p = self.policy;
+        self.cfg.modify(move |data: &mut HashMap<Uuid, ServerData>| -> Result<bool, ()> {
+            if let Some(v) = data.get_mut(server_uuid) {
+                if pgp::check_key(cert.as_ref(), p, None, &key_id) {
+                    v.key_id = key_id;
+                    v.cert = Some(cert);
+                    *p_success = true;
+                }
+            }
+            Ok(*p_success)
+        })
+        .unwrap();
+        success
+    }
+
+    pub fn set_api_url(&mut self, server_uuid: &Uuid, api_url: Option<Url>) -> bool {
+        let mut success = false;
+        let p_success = &mut success;
+        self.cfg.modify(move |data: &mut HashMap<Uuid, ServerData>| -> Result<bool, ()> {
+            if let Some(v) = data.get_mut(server_uuid) {
+                if v.api_url != api_url {
+                    v.api_url = api_url;
+                    *p_success = true;
+                }
+            }
+            Ok(*p_success)
+        })
+        .unwrap();
+        success
+    }
+
     pub fn get(&self, server_uuid: &Uuid) -> Option<(Rc<Cert>, KeyID)> {
         self.cfg.get_data().get(server_uuid).map(|s| (s.cert.as_ref().unwrap().clone(), s.key_id.clone()))
     }
@@ -184,17 +254,45 @@ impl<'a> ServersConfig<'a> {
         self.cfg.get_data().get(server_uuid).map(|s| (s.cert.as_ref().unwrap().as_ref(), &s.key_id))
     }
 
+    pub fn find_by_key_id(&self, key_id: &KeyID) -> Vec<(&Uuid, &ServerData)> {
+        self.cfg.get_data().iter().filter(|(_, s)| &s.key_id == key_id).collect()
+    }
+
+    pub fn shared_key_ids(&self) -> Vec<(KeyID, Vec<Uuid>)> {
+        let mut indexs: HashMap<&KeyID, Vec<Uuid>> = HashMap::new();
+        for (server_uuid, s) in self.cfg.get_data().iter() {
+            indexs.entry(&s.key_id).or_insert_with(Vec::new).push(server_uuid.clone());
+        }
+        indexs.into_iter()
+            .filter(|(_, v)| v.len() > 1)
+            .map(|(k, v)| (k.clone(), v))
+            .collect()
+    }
+
     pub fn policy(&self) -> &dyn Policy {
         self.policy
     }
 }
 
 
+impl<'a> super::Persist for ServersConfig<'a> {
+
+    fn describe(&self) -> String {
+        self.cfg.path().display().to_string()
+    }
+
+    fn persist(&mut self) -> GeneralResult<bool> {
+        self.save()
+    }
+}
+
 impl<'a> Drop for ServersConfig<'a> {
 
     fn drop(&mut self) {
-        if let Err(e) = self.save() {
-            eprintln!("{}", e)
+        match self.save() {
+            Ok(true) => eprintln!("warning: {}: unsaved changes flushed in destructor", self.cfg.path().display()),
+            Ok(false) => {}
+            Err(e) => eprintln!("{}", e),
         }
     }
 }
@@ -0,0 +1,65 @@
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+
+
+/**
+ * verbosity of diagnostics on stderr; results and errors are always printed
+ */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level {
+    Quiet = 0,
+    Info = 1,
+    Debug = 2,
+    Trace = 3,
+}
+
+impl Level {
+
+    /// `--quiet`, default, `-v`, `-vv`
+    pub fn from_flags(quiet: bool, verbose: u64) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Self::Quiet,
+            (false, 0) => Self::Info,
+            (false, 1) => Self::Debug,
+            _ => Self::Trace,
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed)
+}
+
+pub fn enabled(level: Level) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+
+/// progress messages, hidden by `--quiet`
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        if $crate::logger::enabled($crate::logger::Level::Info) {
+            eprintln!($($arg)*)
+        }
+    };
+}
+
+/// requests sent, shown with `-v`
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if $crate::logger::enabled($crate::logger::Level::Debug) {
+            eprintln!($($arg)*)
+        }
+    };
+}
+
+/// message and response bodies, shown with `-vv`
+macro_rules! log_trace {
+    ($($arg:tt)*) => {
+        if $crate::logger::enabled($crate::logger::Level::Trace) {
+            eprintln!($($arg)*)
+        }
+    };
+}
@@ -1,15 +1,23 @@
+#[macro_use]
+mod logger;
 mod pgp;
 mod api_v1;
 mod config;
 mod command;
 
 use std::fmt;
+use std::io;
+use std::fs;
 use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 
 use clap::App;
 use clap::SubCommand;
 use clap::Arg;
 use clap::ArgGroup;
+use clap::AppSettings;
 use uuid::Uuid;
 use sequoia_openpgp::KeyID;
 use sequoia_openpgp::Fingerprint;
@@ -17,16 +25,93 @@ use sequoia_openpgp::Fingerprint;
 use config::client::ClientConfig;
 use config::servers::ServersConfig;
 use config::records::RecordConfig;
+use config::records::RecordHeader;
 
-fn main() {
+fn build_cli() -> App<'static, 'static> {
 
-    let app = App::new(env!("CARGO_PKG_NAME"))
+    App::new(env!("CARGO_PKG_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
+        .setting(AppSettings::DisableVersion)
+        .arg(
+            Arg::with_name("version")
+                .long("version")
+                .short("V")
+                .help("Prints version information; with -v also prints build details")
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .multiple(true)
+                .help("log the requests sent; -vv also logs signed messages and response bodies")
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .conflicts_with("verbose")
+                .help("print only results and errors")
+        )
+        .arg(
+            Arg::with_name("list_error_codes")
+                .long("list-error-codes")
+                .takes_value(true)
+                .min_values(0)
+                .possible_values(&["text", "json"])
+                .help("list all error codes with summary and affected commands, in text (default) or json")
+        )
+        .arg(
+            Arg::with_name("config_dir")
+                .long("config-dir")
+                .takes_value(true)
+                .env("OPENMPRDB_CONFIG_DIR")
+                .help("directory of config, servers and record files; default is $XDG_CONFIG_HOME/openmprdb-client or ~/.config/openmprdb-client")
+        )
+        .arg(
+            Arg::with_name("password_env")
+                .long("password-env")
+                .takes_value(true)
+                .value_name("VAR")
+                .help("environment variable holding the password of the secret key; default OPENMPRDB_PASSPHRASE")
+        )
+        .arg(
+            Arg::with_name("passphrase_file")
+                .long("passphrase-file")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("read the password of the secret key from the first line of the file if the environment variable is not set")
+        )
+        .arg(
+            Arg::with_name("retries")
+                .long("retries")
+                .takes_value(true)
+                .help("times to retry a request on network failure; default 0")
+        )
+        .arg(
+            Arg::with_name("http_log")
+                .long("http-log")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("append every request and response, bodies cut at 4 KB, to the file")
+        )
+        .arg(
+            Arg::with_name("user_agent")
+                .long("user-agent")
+                .takes_value(true)
+                .help("User-Agent header of requests; default openmprdb-client-cli/<version>")
+        )
+        .arg(
+            Arg::with_name("retry_delay")
+                .long("retry-delay")
+                .takes_value(true)
+                .help("delay between retries in milliseconds; default 1000")
+        )
         .subcommand(
             SubCommand::with_name("config")
                 .about("Config basic settings; use [option]=<value> to set value & [option]=? to check value")
+                .after_help(command::examples::after_help_with("config", config::client::ENV_HELP))
                 .arg(
                     Arg::with_name("cert_file")
                         .long("cert-file")
@@ -39,6 +124,12 @@ fn main() {
                         .takes_value(true)
                         .help("set specific key in the certification file to be used")
                 )
+                .arg(
+                    Arg::with_name("fingerprint")
+                        .long("fingerprint")
+                        .takes_value(true)
+                        .help("set fingerprint of the signing key in the certification file; key_id will be derived from it")
+                )
                 .arg(
                     Arg::with_name("server_uuid")
                         .long("server-uuid")
@@ -51,26 +142,148 @@ fn main() {
                         .takes_value(true)
                         .help("set openmprdb api url")   
                 )
+                .arg(
+                    Arg::with_name("prompt_timeout_secs")
+                        .long("prompt-timeout-secs")
+                        .takes_value(true)
+                        .help("set seconds to wait for the password prompt before giving up; default waits forever")
+                )
+                .arg(
+                    Arg::with_name("auto_refresh_certs")
+                        .long("auto-refresh-certs")
+                        .takes_value(true)
+                        .help("set true to check the registry for a new key when a server's submits keep failing verification in `record --auto`")
+                )
+                .arg(
+                    Arg::with_name("confirm_registry")
+                        .long("confirm-registry")
+                        .takes_value(true)
+                        .help("set a marker, e.g. prod, that api_url must contain before unregister or recall --force proceed")
+                )
+                .arg(
+                    Arg::with_name("require_encrypted_key")
+                        .long("require-encrypted-key")
+                        .takes_value(true)
+                        .help("set true to refuse signing with a secret key that is not protected by a passphrase")
+                )
+                .arg(
+                    Arg::with_name("protected_players_file")
+                        .long("protected-players-file")
+                        .takes_value(true)
+                        .help("set player list (ops.json, whitelist.json or one uuid per line) protected from submit & import")
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .takes_value(false)
+                        .help("print the full configuration in json")
+                )
+                .arg(
+                    Arg::with_name("show_all")
+                        .long("show-all")
+                        .takes_value(false)
+                        .help("print every configuration value; default when no other option is given")
+                )
+                .arg(
+                    Arg::with_name("validate")
+                        .long("validate")
+                        .takes_value(false)
+                        .conflicts_with_all(&["json", "show_all"])
+                        .help("check that cert_file, key_id, api_url and server_uuid fit together; fails if any check fails")
+                )
+                .arg(
+                    Arg::with_name("online")
+                        .long("online")
+                        .takes_value(false)
+                        .requires("validate")
+                        .help("also compare the key with the one registered for server_uuid in OpenMPRDB")
+                )
+                .arg(
+                    Arg::with_name("unset")
+                        .long("unset")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("clear the specific field, e.g. cert-file, key-id, server-uuid or api-url; can be used multiple times")
+                )
+                .arg(
+                    Arg::with_name("unset_all")
+                        .long("unset-all")
+                        .conflicts_with("unset")
+                        .help("reset the entire config to defaults")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("generate")
+                .about("Generate a new certification with a signing subkey for the server")
+                .after_help(command::examples::after_help("generate"))
+                .arg(
+                    Arg::with_name("user_id")
+                        .long("user-id")
+                        .takes_value(true)
+                        .required(true)
+                        .help("user id of the certification, e.g. \"Server <admin@example.com>\"")
+                )
+                .arg(
+                    Arg::with_name("cipher")
+                        .long("cipher")
+                        .takes_value(true)
+                        .possible_values(&["rsa2048", "rsa3072", "rsa4096", "cv25519"])
+                        .help("cipher suite; default is rsa2048")
+                )
+                .arg(
+                    Arg::with_name("expires")
+                        .long("expires")
+                        .takes_value(true)
+                        .help("validity period, in <n>d, <n>w, <n>m or <n>y; default never expires")
+                )
+                .arg(
+                    Arg::with_name("with_password")
+                        .long("with-password")
+                        .takes_value(false)
+                        .help("protect the secret key with a password, input from console")
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("output file of the certification; revocation certificate is written to <output>.rev; default means stdout")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("keyring")
+                .about("List keys info in the specific secret key file of the server (bind to this client)")
+                .after_help(command::examples::after_help("keyring"))
+                .arg(
+                    Arg::with_name("cert_file")
+                        .long("cert-file")
+                        .takes_value(true)
+                        .help("certification file of TPK and TSK data structures; default means use value in config")
+                )
+                .arg(
+                    Arg::with_name("key_id")
+                        .long("key-id")
+                        .takes_value(true)
+                        .help("specific key in the certification file; default means use value in config")
+                )
+                .arg(
+                    Arg::with_name("protect")
+                        .long("protect")
+                        .conflicts_with("key_id")
+                        .help("add a passphrase to the unprotected secret keys of the certification file, rewriting it")
+                )
+                .arg(
+                    Arg::with_name("signing_only")
+                        .long("signing-only")
+                        .conflicts_with("protect")
+                        .help("list only the keys usable for signing, i.e. the candidates for --key-id")
+                )
         )
-        // .subcommand(
-        //     SubCommand::with_name("keyring")
-        //         .about("List keys info in the specific secret key file of the server (bind to this client)")
-        //         .arg(
-        //             Arg::with_name("cert_file")
-        //                 .long("cert-file")
-        //                 .takes_value(true)
-        //                 .help("set certification file of TPK and TSK data structures")
-        //         )
-        //         .arg(
-        //             Arg::with_name("key_id")
-        //                 .long("key-id")
-        //                 .takes_value(true)
-        //                 .help("specific key in the certification file")
-        //         )
-        // )
         .subcommand(
             SubCommand::with_name("register")
                 .about("Register the server with the secret key to remote OpenMPRDB ")
+                .after_help(command::examples::after_help("register"))
                 .arg(
                     Arg::with_name("cert_file")
                         .long("cert-file")
@@ -104,52 +317,223 @@ fn main() {
                         .help("name of server to register")
                         .required(true)
                 )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("print the signed registration request instead of sending it; server_uuid is not changed")
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .long("force")
+                        .help("register even if server_uuid is already set; the old registration is kept")
+                )
+                .arg(
+                    Arg::with_name("unregister_old")
+                        .long("unregister-old")
+                        .requires("force")
+                        .conflicts_with("dry_run")
+                        .help("with --force, unregister the old server_uuid after the new registration")
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["plain", "json"])
+                        .default_value("plain")
+                        .help("output format; with json errors are also written to stderr as json")
+                )
         )
         .subcommand(
             SubCommand::with_name("unregister")
                 .about("Unregister the server with the secret key from remote OpenMPRDB")
+                .after_help(command::examples::after_help("unregister"))
                 .arg(
                     Arg::with_name("comment")
                         .long("comment")
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::with_name("yes")
+                        .long("yes")
+                        .short("y")
+                        .help("do not ask for confirmation; the target registry is still printed")
+                )
+                .arg(
+                    Arg::with_name("override_registry_guard")
+                        .long("override-registry-guard")
+                        .help("proceed even if api_url does not contain the marker set by `config --confirm-registry`")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("rotate-key")
+                .about("Switch to a new signing key: register with it, then unregister the old server")
+                .after_help(command::examples::after_help("rotate-key"))
+                .arg(
+                    Arg::with_name("cert_file")
+                        .long("cert-file")
+                        .takes_value(true)
+                        .help("certification file of the new key; default is cert_file in config")
+                )
+                .arg(
+                    Arg::with_name("key_id")
+                        .long("key-id")
+                        .takes_value(true)
+                        .required(true)
+                        .help("the new signing key in the certification file")
+                )
+                .arg(
+                    Arg::with_name("server_name")
+                        .long("server-name")
+                        .short("s")
+                        .takes_value(true)
+                        .help("name to register with; default is the registered name of the old server")
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .long("yes")
+                        .short("y")
+                        .help("do not ask for confirmation; the target registry is still printed")
+                )
+                .arg(
+                    Arg::with_name("override_registry_guard")
+                        .long("override-registry-guard")
+                        .help("proceed even if api_url does not contain the marker set by `config --confirm-registry`")
+                )
         )
         .subcommand(
             SubCommand::with_name("submit")
                 .about("Submit one record to remote OpenMPRDB")
+                .after_help(command::examples::after_help("submit"))
                 .arg(
                     Arg::with_name("player_uuid")
                         .long("player-uuid")
                         .short("p")
                         .takes_value(true)
-                        .required(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("asked on the console when missing; repeat to submit the same record for several players")
+                )
+                .arg(
+                    Arg::with_name("player_name")
+                        .long("player-name")
+                        .takes_value(true)
+                        .conflicts_with("player_uuid")
+                        .requires("name_source")
+                        .help("submit for the player with this name, looked up in --usercache or with --online-lookup")
+                )
+                .arg(
+                    Arg::with_name("usercache")
+                        .long("usercache")
+                        .takes_value(true)
+                        .requires("player_name")
+                        .help("usercache.json of the server to look --player-name up in")
+                )
+                .arg(
+                    Arg::with_name("online_lookup")
+                        .long("online-lookup")
+                        .requires("player_name")
+                        .help("look --player-name up with the Mojang profile api")
+                )
+                .group(
+                    ArgGroup::with_name("name_source")
+                        .args(&["usercache", "online_lookup"])
                 )
                 .arg(
                     Arg::with_name("points")
                         .long("points")
                         .short("s")
                         .takes_value(true)
-                        .required(true)
+                        .allow_hyphen_values(true)
+                        .help("in [-1, 1]; asked on the console when missing")
                 )
                 .arg(
                     Arg::with_name("comment")
                         .long("comment")
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::with_name("timestamp")
+                        .long("timestamp")
+                        .takes_value(true)
+                        .help("time of the event, as unix timestamp or \"YYYY-mm-dd HH:MM:SS\"; default now, may not be in the future")
+                )
+                .arg(
+                    Arg::with_name("batch")
+                        .long("batch")
+                        .takes_value(true)
+                        .conflicts_with_all(&["player_uuid", "player_name", "points", "comment", "timestamp"])
+                        .help("submit every row of a csv (player_uuid,points[,comment][,timestamp]) or json array file")
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .takes_value(true)
+                        .requires("batch")
+                        .help("requset interval in milliseconds")
+                )
+                .arg(
+                    Arg::with_name("progress_json")
+                        .long("progress-json")
+                        .requires("batch")
+                        .help("report progress as one json object per line on stderr instead of text")
+                )
                 .arg(
                     Arg::with_name("force")
                         .long("force")
                 )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("sign the request but do not send it")
+                )
+                .arg(
+                    Arg::with_name("accept_records_origin")
+                        .long("accept-records-origin")
+                        .help("use the local record file even if it was created for another api_url or server_uuid")
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["plain", "json"])
+                        .default_value("plain")
+                        .help("output format; with json errors are also written to stderr as json")
+                )
         )
         .subcommand(
             SubCommand::with_name("recall")
                 .about("Recall the specific record from remote OpenMPRDB")
+                .after_help(command::examples::after_help("recall"))
                 .arg(
                     Arg::with_name("record_uuid")
                         .long("record-uuid")
                         .short("r")
                         .takes_value(true)
-                        .required(true)   
+                )
+                .arg(
+                    Arg::with_name("player_uuid")
+                        .long("player-uuid")
+                        .short("p")
+                        .takes_value(true)
+                        .help("recall the local record of the player")
+                )
+                .arg(
+                    Arg::with_name("from_file")
+                        .long("from-file")
+                        .takes_value(true)
+                        .help("recall every record uuid in the file, one per line; blank lines and lines starting with # are ignored")
+                )
+                .group(
+                    ArgGroup::with_name("target")
+                        .args(&["record_uuid", "player_uuid", "from_file"])
+                        .required(true)
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .takes_value(true)
+                        .requires("from_file")
+                        .help("requset interval in milliseconds")
                 )
                 .arg(
                     Arg::with_name("comment")
@@ -160,10 +544,40 @@ fn main() {
                     Arg::with_name("force")
                         .long("force")
                 )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("sign the request but do not send it")
+                )
+                .arg(
+                    Arg::with_name("accept_records_origin")
+                        .long("accept-records-origin")
+                        .help("use the local record file even if it was created for another api_url or server_uuid")
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["plain", "json"])
+                        .default_value("plain")
+                        .help("output format; with json errors are also written to stderr as json")
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .long("yes")
+                        .short("y")
+                        .help("do not ask for confirmation; the target registry is still printed")
+                )
+                .arg(
+                    Arg::with_name("override_registry_guard")
+                        .long("override-registry-guard")
+                        .help("proceed even if api_url does not contain the marker set by `config --confirm-registry`")
+                )
         )
         .subcommand(
             SubCommand::with_name("cert")
                 .about("Management other server's public key registered in OpenMPRDB")
+                .after_help(command::examples::after_help("cert"))
                 .arg(
                     Arg::with_name("add")
                         .long("add")
@@ -174,11 +588,43 @@ fn main() {
                     Arg::with_name("remove")
                         .long("remove")
                         .takes_value(false)
-                        .help("to remove other server's public key")
+                        .help("to remove other server's public key, given by --server-uuid, --key-id or --name")
+                        .requires("remove_target")
+                )
+                .arg(
+                    Arg::with_name("update")
+                        .long("update")
+                        .takes_value(false)
+                        .help("to update settings of other server already added")
+                )
+                .arg(
+                    Arg::with_name("set_trust")
+                        .long("set-trust")
+                        .visible_alias("update-trust")
+                        .takes_value(false)
+                        .help("to change trust level (and name) of other server already added")
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .long("list")
+                        .takes_value(false)
+                        .help("to list all other servers' public keys added")
+                )
+                .arg(
+                    Arg::with_name("export")
+                        .long("export")
+                        .takes_value(false)
+                        .help("to export other server's public key, or all of them without --server-uuid")
+                )
+                .arg(
+                    Arg::with_name("import_from_api")
+                        .long("import-from-api")
+                        .takes_value(false)
+                        .help("to add servers listed in OpenMPRDB that are not added yet, with the keys they registered; asks for each one")
                 )
                 .group(
                     ArgGroup::with_name("add-remove")
-                        .args(&["add", "remove"])
+                        .args(&["add", "remove", "update", "set_trust", "list", "export", "import_from_api"])
                         .required(true)
                 )
                 .arg(
@@ -186,42 +632,158 @@ fn main() {
                         .long("server-uuid")
                         .takes_value(true)
                         .help("uuid of the target server registered in OpenMPRDB to add")
-                        .required(true)
+                        .required_unless_one(&["list", "export", "import_from_api", "remove"])
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("output file of the exported armored public key; default means stdout")
+                        .requires("export")
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .takes_value(false)
+                        .help("list in json")
+                        .requires("list")
+                )
+                .arg(
+                    Arg::with_name("min_trust")
+                        .long("min-trust")
+                        .takes_value(true)
+                        .help("list only servers with at least this trust level, in 1..5")
+                        .requires("list")
                 )
                 .arg(
                     Arg::with_name("name")
                         .long("name")
                         .takes_value(true)
                         .help("name of the target server")
-                        .requires("add")
                 )
                 .arg(
                     Arg::with_name("key_id")
                         .long("key-id")
                         .takes_value(true)
-                        .help("key-id of public key certification of the target server")
+                        .help("key-id of public key certification of the target server; with --add or --remove")
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .long("file")
+                        .takes_value(true)
+                        .help("read the public key from a file (armored or binary, may hold several certs) instead of the console")
+                        .requires("add")
+                )
+                .arg(
+                    Arg::with_name("fetch")
+                        .long("fetch")
+                        .help("take the public key and key-id the server registered in OpenMPRDB; --name defaults to its registry name")
                         .requires("add")
+                        .conflicts_with_all(&["key_id", "file"])
+                )
+                .arg(
+                    Arg::with_name("fingerprint")
+                        .long("fingerprint")
+                        .takes_value(true)
+                        .help("expected fingerprint of the fetched key, instead of confirming it on the console")
+                        .requires("fetch")
+                )
+                .arg(
+                    Arg::with_name("allow_shared_key")
+                        .long("allow-shared-key")
+                        .help("add even if another server already uses the same key-id")
+                        .requires("add")
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .long("yes")
+                        .short("y")
+                        .help("import every new server without asking")
+                        .requires("import_from_api")
                 )
                 .arg(
                     Arg::with_name("trust")
                         .long("trust")
                         .takes_value(true)
-                        .help("trust level")
-                        .requires("add")
+                        .help("trust level, in 1..5")
+                )
+                .group(
+                    ArgGroup::with_name("remove_target")
+                        .args(&["server_uuid", "key_id", "name"])
+                        .multiple(true)
+                )
+                .arg(
+                    Arg::with_name("api_url")
+                        .long("api-url")
+                        .takes_value(true)
+                        .help("api url to fetch the target server's submits from; default means use value in config; empty value to clear")
                 )
         )
         .subcommand(
             SubCommand::with_name("server")
                 .about("Get & show servers registered in remote OpenMPRDB")
+                .after_help(command::examples::after_help("server"))
                 .arg(
                     Arg::with_name("limit")
                         .long("limit")
                         .takes_value(true)
+                        .conflicts_with("uuid")
+                )
+                .arg(
+                    Arg::with_name("uuid")
+                        .long("uuid")
+                        .takes_value(true)
+                        .help("show only the server with this uuid")
+                )
+                .arg(
+                    Arg::with_name("my")
+                        .long("my")
+                        .conflicts_with_all(&["uuid", "limit", "filter", "key_id", "sort"])
+                        .help("show the registration of this server, by server_uuid in config")
+                )
+                .arg(
+                    Arg::with_name("show_key")
+                        .long("show-key")
+                        .visible_alias("show-keys")
+                        .help("also print the armored public keys of the servers")
+                )
+                .arg(
+                    Arg::with_name("filter")
+                        .long("filter")
+                        .visible_alias("search")
+                        .takes_value(true)
+                        .conflicts_with("uuid")
+                        .help("list only servers whose name contains the text, ignoring case")
+                )
+                .arg(
+                    Arg::with_name("key_id")
+                        .long("key-id")
+                        .takes_value(true)
+                        .conflicts_with("uuid")
+                        .help("list only servers registered with this key-id")
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .takes_value(true)
+                        .possible_values(&["name", "id"])
+                        .conflicts_with("uuid")
+                        .help("sort the list by server name or registry id; default is the order of the registry")
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["plain", "json", "csv"])
+                        .default_value("plain")
+                        .help("output format")
                 )
         )
         .subcommand(
             SubCommand::with_name("record")
                 .about("Acquire and verify record of records in remote OpenMPRDB with other server's public key")
+                .after_help(command::examples::after_help("record"))
                 .arg(
                     Arg::with_name("submit_uuid")
                         .long("submit-uuid")
@@ -237,44 +799,334 @@ fn main() {
                         .long("key-id")
                         .takes_value(true)
                 )
-                // .arg(
-                //     Arg::with_name("auto")
-                //         .long("auto")
-                //         .help("get record according to the servers in cert-config and merge them into a table")
-                // )
+                .arg(
+                    Arg::with_name("auto")
+                        .long("auto")
+                        .requires("output")
+                        .help("get record according to the servers in cert-config and merge them into a table")
+                )
+                .arg(
+                    Arg::with_name("verify_file")
+                        .long("verify-file")
+                        .takes_value(true)
+                        .help("verify a saved submit content offline with the added servers' certs; `-` for stdin")
+                )
+                .arg(
+                    Arg::with_name("all_servers")
+                        .long("all-servers")
+                        .requires("player")
+                        .help("ask the registry in one request for the submits of every server about --player")
+                )
                 .group(
                     ArgGroup::with_name("according")
-                        .args(&["submit_uuid", "server_uuid", "key_id", /* "auto" */])
+                        .args(&["submit_uuid", "server_uuid", "key_id", "auto", "verify_file", "all_servers"])
                         .required(true)
                 )
                 .arg(
-                    Arg::with_name("limit")
-                        .long("limit")
-                        .takes_value(true) 
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .takes_value(true)
+                        .help("submits asked for in one request; with --server-uuid or --key-id, pages are fetched until one comes back short")
+                )
+                .arg(
+                    Arg::with_name("max")
+                        .long("max")
+                        .takes_value(true)
+                        .conflicts_with_all(&["submit_uuid", "auto", "verify_file", "all_servers"])
+                        .help("stop paging after this many submits in total")
+                )
+                .arg(
+                    Arg::with_name("after")
+                        .long("after")
+                        .takes_value(true)
+                        .help("ask to show submits after a specific time, in YYYY-MM-dd HH:mm:ss")
+                )
+                .arg(
+                    Arg::with_name("before")
+                        .long("before")
+                        .takes_value(true)
+                        .help("ask to show submits before a specific time, in YYYY-MM-dd HH:mm:ss")
+                )
+                .arg(
+                    Arg::with_name("player")
+                        .long("player")
+                        .takes_value(true)
+                        .conflicts_with_all(&["submit_uuid", "auto", "verify_file"])
+                        .help("only submits about the player with this uuid: of --server-uuid or --key-id, or with --all-servers of every server")
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["plain", "json", "csv"])
+                        .default_value("plain")
+                        .help("output format")
+                )
+                .arg(
+                    Arg::with_name("resolve_names")
+                        .long("resolve-names")
+                        .help("fetch the server list when the registry name of a server is not cached")
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .takes_value(true)
+                        .conflicts_with("verify_file")
+                        .help("output file, written when complete; .csv and .json choose the format unless --format is given")
+                )
+                .arg(
+                    Arg::with_name("progress_json")
+                        .long("progress-json")
+                        .requires("auto")
+                        .help("report progress as one json object per line on stderr instead of text")
+                )
+                .arg(
+                    Arg::with_name("min_trust")
+                        .long("min-trust")
+                        .takes_value(true)
+                        .requires("auto")
+                        .help("merge only servers with at least this trust level, in 1..5")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("records")
+                .about("List records submitted by this server, from the local record file")
+                .after_help(command::examples::after_help("records"))
+                .arg(
+                    Arg::with_name("player_uuid")
+                        .long("player-uuid")
+                        .short("p")
+                        .takes_value(true)
+                        .help("only records of the player")
+                )
+                .arg(
+                    Arg::with_name("after")
+                        .long("after")
+                        .takes_value(true)
+                        .help("only records submitted after a specific time, in YYYY-MM-dd HH:mm:ss")
+                )
+                .arg(
+                    Arg::with_name("before")
+                        .long("before")
+                        .takes_value(true)
+                        .help("only records submitted before a specific time, in YYYY-MM-dd HH:mm:ss")
+                )
+                .arg(
+                    Arg::with_name("include_recalled")
+                        .long("include-recalled")
+                        .help("also list records already recalled")
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .long("count")
+                        .help("print the number of records only")
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["plain", "json", "csv"])
+                        .default_value("plain")
+                        .help("output format, ignored with --count; json and csv carry the raw timestamps")
+                )
+                .arg(
+                    Arg::with_name("accept_records_origin")
+                        .long("accept-records-origin")
+                        .help("use the local record file even if it was created for another api_url or server_uuid")
+                )
+                .subcommand(
+                    SubCommand::with_name("compact")
+                        .about("Rewrite the record file with active records only, dropping recalled submits")
+                )
+                .subcommand(
+                    SubCommand::with_name("stats")
+                        .about("Print the number of active records and of distinct players")
+                )
+                .subcommand(
+                    SubCommand::with_name("export")
+                        .about("Export active records as csv or json")
+                        .arg(
+                            Arg::with_name("output")
+                                .long("output")
+                                .short("o")
+                                .takes_value(true)
+                                .help("output file; `-` or absent for stdout")
+                        )
+                        .arg(
+                            Arg::with_name("format")
+                                .long("format")
+                                .takes_value(true)
+                                .possible_values(&["csv", "json"])
+                                .default_value("csv")
+                                .help("output format")
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("sync")
+                        .about("Compare the recall state of local records with remote OpenMPRDB")
+                        .arg(
+                            Arg::with_name("auto_heal_local")
+                                .long("auto-heal-local")
+                                .help("append local recall entries for records the registry no longer has; never writes to the registry")
+                        )
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("status")
+                .about("Check whether the client is configured, registered and able to reach remote OpenMPRDB")
+                .after_help(command::examples::after_help("status"))
+        )
+        .subcommand(
+            SubCommand::with_name("debug")
+                .about("Tools for inspecting the protocol")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("request")
+                        .about("Print the method, url, headers and body of a request without sending it")
+                        .after_help(command::examples::after_help("debug"))
+                        .arg(
+                            Arg::with_name("kind")
+                                .takes_value(true)
+                                .required(true)
+                                .possible_values(command::DEBUG_REQUEST_KINDS)
+                        )
+                        .arg(
+                            Arg::with_name("use_real_key")
+                                .long("use-real-key")
+                                .help("sign with the key in config instead of a throwaway one")
+                        )
+                        .arg(
+                            Arg::with_name("api_url")
+                                .long("api-url")
+                                .takes_value(true)
+                                .help("base url to resolve against; default is api_url in config")
+                        )
+                        .arg(
+                            Arg::with_name("server_name")
+                                .long("server-name")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("server_uuid")
+                                .long("server-uuid")
+                                .takes_value(true)
+                                .help("default is server_uuid in config")
+                        )
+                        .arg(
+                            Arg::with_name("player_uuid")
+                                .long("player-uuid")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("record_uuid")
+                                .long("record-uuid")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("points")
+                                .long("points")
+                                .takes_value(true)
+                                .allow_hyphen_values(true)
+                        )
+                        .arg(
+                            Arg::with_name("comment")
+                                .long("comment")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("key_id")
+                                .long("key-id")
+                                .takes_value(true)
+                                .help("query submits by key id instead of server uuid")
+                        )
+                        .arg(
+                            Arg::with_name("limit")
+                                .long("limit")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("after")
+                                .long("after")
+                                .takes_value(true)
+                                .help("unix timestamp")
+                        )
+                        .arg(
+                            Arg::with_name("before")
+                                .long("before")
+                                .takes_value(true)
+                                .help("unix timestamp")
+                        )
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("examples")
+                .about("Print a walkthrough from a new certification to the first record")
+        )
+        .subcommand(
+            SubCommand::with_name("ping")
+                .about("Check that remote OpenMPRDB answers, and how fast")
+                .after_help(command::examples::after_help("ping"))
+                .arg(
+                    Arg::with_name("api_url")
+                        .long("api-url")
+                        .takes_value(true)
+                        .help("api url to ping; default is api_url in config")
                 )
+        )
+        .subcommand(
+            SubCommand::with_name("version")
+                .about("Show version and build details")
+                .after_help(command::examples::after_help("version"))
                 .arg(
-                    Arg::with_name("after")
-                        .long("after")
+                    Arg::with_name("format")
+                        .long("format")
                         .takes_value(true)
-                        .help("ask to show submits after a specific time, in YYYY-MM-dd HH:mm:ss")
+                        .possible_values(&["plain", "json"])
+                        .default_value("plain")
+                        .help("output format")
                 )
-                // .arg(
-                //     Arg::with_name("output")
-                //         .long("output")
-                //         .short("o")
-                //         .takes_value(true)
-                //         .requires("auto")
-                //         .help("output file")
-                // )
         )
         .subcommand(
             SubCommand::with_name("import")
                 .about("Submit mutiple records import from banlist (banned-players.json)")
+                .after_help(command::examples::after_help("import"))
                 .arg(
                     Arg::with_name("banlist")
                         .takes_value(true)
                         .required(true)
-                        .help("banlist file (banned-players.json)") 
+                        .help("banlist file (banned-players.json or banned-ips.json)") 
+                )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("validate the banlist and preview the records to submit, without signing or sending them")
+                )
+                .arg(
+                    Arg::with_name("usercache")
+                        .long("usercache")
+                        .takes_value(true)
+                        .help("usercache.json to map entries of banned-ips.json to players; without it those entries are skipped")
+                )
+                .arg(
+                    Arg::with_name("resume")
+                        .long("resume")
+                        .conflicts_with("dry_run")
+                        .help("continue after the last successful submit of an interrupted import")
+                )
+                .arg(
+                    Arg::with_name("ignore_errors")
+                        .long("ignore-errors")
+                        .takes_value(false)
+                        .help("keep on importing when a submit fails")
+                )
+                .arg(
+                    Arg::with_name("protect_from")
+                        .long("protect-from")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("player list (ops.json, whitelist.json or one uuid per line) whose players are never submitted; can be used multiple times")
                 )
                 .arg(
                     Arg::with_name("interval")
@@ -282,21 +1134,170 @@ fn main() {
                         .takes_value(true)
                         .help("requset interval in milliseconds")
                 )
-        );
-    
+                .arg(
+                    Arg::with_name("timestamp_policy")
+                        .long("timestamp-policy")
+                        .takes_value(true)
+                        .help("content timestamp of records: created (default), now, or clamp:<days> for created but no older than <days> days")
+                )
+                .arg(
+                    Arg::with_name("progress_json")
+                        .long("progress-json")
+                        .help("report progress as one json object per line on stderr instead of text")
+                )
+                .arg(
+                    Arg::with_name("accept_records_origin")
+                        .long("accept-records-origin")
+                        .help("use the local record file even if it was created for another api_url or server_uuid")
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["plain", "json"])
+                        .default_value("plain")
+                        .help("output format; with json errors are also written to stderr as json")
+                )
+        )
+}
+
+
+/// scope of each subcommand for the error catalog; a subcommand missing here fails the tests
+fn command_scope(name: &str) -> Option<command::error::CommandScope> {
+    use command::error::CommandScope;
+
+    match name {
+        "examples" | "generate" | "version" => Some(CommandScope::Local),
+        "debug" | "keyring" => Some(CommandScope::Config),
+        "config" | "register" | "unregister" | "rotate-key" | "submit" | "recall" | "cert" | "server" | "record" | "records"
+        | "status" | "ping" | "import" => Some(CommandScope::Remote),
+        _ => None,
+    }
+}
+
+fn catalog_commands<'c>(cli: &'c App) -> Vec<(&'c str, command::error::CommandScope)> {
+    cli.p.subcommands.iter()
+        .map(|sub| (sub.get_name(), command_scope(sub.get_name()).unwrap_or(command::error::CommandScope::Remote)))
+        .collect()
+}
+
+
+fn main() {
+
+    let matches = build_cli().get_matches();
+
+    let base_dir = match matches.value_of("config_dir") {
+        Some(dir) => {
+            let dir = PathBuf::from(dir);
+            if let Err(e) = fs::create_dir_all(dir.as_path()) {
+                let e = io::Error::new(e.kind(), format!("can not create config dir {}: {}", dir.display(), e));
+                handle_err(command::error::AppError::new_other(e))
+            }
+            dir
+        }
+        None => config::config_dir().unwrap_or_else(|e| {
+            let e = io::Error::new(e.kind(), format!("can not resolve config dir: {}", e));
+            handle_err(command::error::AppError::new_other(e))
+        })
+    };
+
+    logger::set_level(logger::Level::from_flags(matches.is_present("quiet"), matches.occurrences_of("verbose")));
+
+    if matches.is_present("version") {
+        if matches.is_present("verbose") {
+            command::command_version(&base_dir, command::output::OutputFormat::Plain, &mut io::stdout()).unwrap_or_else(|e| handle_err(e));
+        } else {
+            println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        }
+        return;
+    }
+
+    if matches.is_present("list_error_codes") {
+        let json = matches.value_of("list_error_codes") == Some("json");
+        let cli = build_cli();
+        command::error::write_catalog(io::stdout(), json, catalog_commands(&cli).as_slice()).unwrap();
+        return;
+    }
+
+    if let (_, Some(sub_matches)) = matches.subcommand() {
+        if sub_matches.value_of("format") == Some("json") {
+            JSON_ERRORS.store(true, Ordering::Relaxed);
+        }
+    }
+
+    let httpc_builder = build_http_client(&matches).unwrap_or_else(|e| handle_err(e));
+
+    let policy = config::build_policy();
+    let mut session = Session::default();
+    let result = run(&matches, base_dir.as_path(), &httpc_builder, policy.as_ref(), &mut session);
+    let committed = session.commit();
+    if let Err(e) = result {
+        handle_err(e)
+    }
+    if !committed {
+        std::process::exit(1)
+    }
+}
+
+
+/**
+ * state opened by the subcommand; `commit` writes it back before the exit code is decided,
+ * so an error of the command no longer skips saving what was already done
+ */
+#[derive(Default)]
+struct Session<'p> {
+    records: Option<RecordConfig>,
+    servers: Option<ServersConfig<'p>>,
+    names: Option<command::registry::RegistryNames>,
+    namecache: Option<command::namecache::NameCache>,
+    client: Option<ClientConfig<'p>>,
+}
+
+impl<'p> Session<'p> {
+
+    /// records first, then servers, registry and player names and the client config; a failed component is reported and the others are still written
+    fn commit(&mut self) -> bool {
+        use config::Persist;
+
+        let mut components: [Option<&mut dyn Persist>; 5] = [
+            self.records.as_mut().map(|v| v as &mut dyn Persist),
+            self.servers.as_mut().map(|v| v as &mut dyn Persist),
+            self.names.as_mut().map(|v| v as &mut dyn Persist),
+            self.namecache.as_mut().map(|v| v as &mut dyn Persist),
+            self.client.as_mut().map(|v| v as &mut dyn Persist),
+        ];
+        let mut committed = true;
+        for component in components.iter_mut().flatten() {
+            if let Err(e) = component.persist() {
+                eprintln!("can not save {}: {}", component.describe(), e);
+                committed = false;
+            }
+        }
+        committed
+    }
+}
 
 
-    let matches = app.get_matches();
+fn run<'a, 'p>(
+    matches: &'a clap::ArgMatches<'a>,
+    base_dir: &Path,
+    httpc_builder: &command::http::ClientBuilder,
+    policy: &'p dyn sequoia_openpgp::policy::Policy,
+    session: &mut Session<'p>,
+) -> command::error::AppResult<'a> {
 
     match matches.subcommand() {
         ("config", Some(sub_matches)) => {
 
-            let policy = config::build_policy();
-            let mut cfg = ClientConfig::new(
-                config::current_exe_path("config").unwrap(), 
-                policy.as_ref(),
-            )
-            .unwrap();
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+
+            if sub_matches.is_present("unset") || sub_matches.is_present("unset_all") {
+                command::command_config_unset(
+                    cfg, 
+                    sub_matches.values_of("unset").map(Iterator::collect).unwrap_or_default(),
+                    sub_matches.is_present("unset_all"),
+                )?;
+            }
             
             if let Some(s) = sub_matches.value_of("cert_file") {
                 if s != "?" {
@@ -310,6 +1311,12 @@ fn main() {
                 }
                 println!("key_id = {}", OptionalKeyIDDisplay(&cfg.get_data().key_id))
             }
+            if let Some(s) = sub_matches.value_of("fingerprint") {
+                if s != "?" {
+                    cfg.set_fingerprint(s);
+                }
+                println!("fingerprint = {}", OptionalFingerprintDisplay(&cfg.get_data().fingerprint))
+            }
             if let Some(s) = sub_matches.value_of("api_url") {
                 if s != "?" {
                     cfg.set_api_url(s);
@@ -322,279 +1329,648 @@ fn main() {
                 }
                 println!("server_uuid = {}", OptionalUUIDDisplay(&cfg.get_data().server_uuid))
             }
+            if let Some(s) = sub_matches.value_of("prompt_timeout_secs") {
+                if s != "?" {
+                    cfg.set_prompt_timeout_secs(s);
+                }
+                println!("prompt_timeout_secs = {}", OptionalValueDisplay(&cfg.get_data().prompt_timeout_secs))
+            }
+
+            if let Some(s) = sub_matches.value_of("auto_refresh_certs") {
+                if s != "?" {
+                    cfg.set_auto_refresh_certs(s);
+                }
+                println!("auto_refresh_certs = {}", OptionalValueDisplay(&cfg.get_data().auto_refresh_certs))
+            }
+
+            if let Some(s) = sub_matches.value_of("confirm_registry") {
+                if s != "?" {
+                    cfg.set_confirm_registry(s);
+                }
+                println!("confirm_registry = {}", OptionalStrDisplay(&cfg.get_data().confirm_registry))
+            }
+
+            if let Some(s) = sub_matches.value_of("require_encrypted_key") {
+                if s != "?" {
+                    cfg.set_require_encrypted_key(s);
+                }
+                println!("require_encrypted_key = {}", OptionalValueDisplay(&cfg.get_data().require_encrypted_key))
+            }
+
+            if let Some(s) = sub_matches.value_of("protected_players_file") {
+                if s != "?" {
+                    cfg.set_protected_players_file(s);
+                }
+                println!("protected_players_file = {}", OptionalPathDisplay(&cfg.get_data().protected_players_file))
+            }
+
+            let specific = ["cert_file", "key_id", "fingerprint", "api_url", "server_uuid", "prompt_timeout_secs", "auto_refresh_certs", "confirm_registry", "require_encrypted_key", "protected_players_file", "unset", "unset_all"]
+                .iter()
+                .any(|name| sub_matches.is_present(name));
+            if sub_matches.is_present("validate") {
+                let httpc = if sub_matches.is_present("online") {
+                    Some(httpc_builder.build().unwrap())
+                } else {
+                    None
+                };
+                command::command_config_validate(cfg, httpc.as_ref(), &mut io::stdout())?;
+            } else if sub_matches.is_present("json") {
+                serde_json::to_writer_pretty(io::stdout(), cfg.get_data()).unwrap();
+                println!();
+            } else if !specific || sub_matches.is_present("show_all") {
+                show_config(cfg);
+            }
+        },
+        ("generate", Some(sub_matches)) => {
+
+            command::command_generate(
+                sub_matches.value_of("user_id").unwrap(),
+                sub_matches.value_of("cipher"),
+                sub_matches.value_of("expires"),
+                sub_matches.is_present("with_password"),
+                sub_matches.value_of("output"),
+            )?;
+        },
+        ("keyring", Some(sub_matches)) => {
+
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+
+            if sub_matches.is_present("protect") {
+                command::command_keyring_protect(
+                    cfg,
+                    sub_matches.value_of("cert_file"),
+                )?;
+            } else {
+                command::command_keyring(
+                    cfg,
+                    sub_matches.value_of("cert_file"),
+                    sub_matches.value_of("key_id"),
+                    sub_matches.is_present("signing_only"),
+                    &mut io::stdout(),
+                )?;
+            }
         },
-        // ("keyring", Some(sub_matches)) => {
-
-            // let policy = config::build_policy();
-            // let mut cfg = ClientConfig::new(
-            //     config::current_exe_path("config").unwrap(), 
-            //     policy.as_ref(),
-            //     &pgp::TTYPasswordProvider
-            // )
-            // .unwrap();
-
-            // if let Some(s) = sub_matches.value_of("cert_file") {
-            //     cfg.set_cert_file(s);
-            //     cfg.get_data_mut().key_id = None;
-            //     println!("update config: cert_file = {}", OptionalPathDisplay(&cfg.get_data().cert_file))
-            // }
-            // if let Some(s) = sub_matches.value_of("key_id") {
-            //     cfg.set_key_id(s);
-            //     println!("update config: key_id = {}", OptionalKeyIDDisplay(&cfg.get_data().key_id))
-            // }
-            
-            // // TODO
-        // },
         ("register", Some(sub_matches)) => {
 
-            let policy = config::build_policy();
-            let mut cfg = ClientConfig::new(
-                config::current_exe_path("config").unwrap(), 
-                policy.as_ref(),
-            )
-            .unwrap();
-            let httpc = command::http::Client::new().unwrap();
-            let mut kpg = command::SigningKeyPairGenerator::new(&pgp::TTYPasswordProvider, policy.as_ref());
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let httpc = httpc_builder.build().unwrap();
+            let password = build_password_provider(matches, cfg.get_data().prompt_timeout());
+            let mut kpg = command::SigningKeyPairGenerator::new(password.as_ref(), policy);
+
+            // kept for --unregister-old, before --cert-file and --key-id replace them
+            let previous_cert = cfg.get_data().get_cert().cloned();
+            let previous_key_id = cfg.get_data().key_id.clone();
 
             if let Some(s) = sub_matches.value_of("cert_file") {
                 cfg.set_cert_file(s);
                 cfg.get_data_mut().key_id = None;
-                println!("update config: cert_file = {}", OptionalPathDisplay(&cfg.get_data().cert_file))
+                log_info!("update config: cert_file = {}", OptionalPathDisplay(&cfg.get_data().cert_file))
             }
             if let Some(s) = sub_matches.value_of("key_id") {
                 cfg.set_key_id(s);
-                println!("update config: key_id = {}", OptionalKeyIDDisplay(&cfg.get_data().key_id))
+                log_info!("update config: key_id = {}", OptionalKeyIDDisplay(&cfg.get_data().key_id))
             }
             if let Some(s) = sub_matches.value_of("api_url") {
                 cfg.set_api_url(s);
-                println!("update config: api_url = {}", OptionalStrDisplay(&cfg.get_data().api_url))
+                log_info!("update config: api_url = {}", OptionalStrDisplay(&cfg.get_data().api_url))
             }
             command::command_register(
-                &mut cfg, 
+                cfg, 
                 &httpc,
                 &mut kpg,
                 sub_matches.value_of("server_name").unwrap(),
-            )
-            .unwrap_or_else(handle_err);
+                sub_matches.is_present("force"),
+                sub_matches.is_present("unregister_old"),
+                previous_cert.as_ref().zip(previous_key_id.as_ref()),
+                sub_matches.is_present("dry_run"),
+                sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                &mut io::stdout(),
+            )?;
         },
         ("unregister", Some(sub_matches)) => {
 
-            let policy = config::build_policy();
-            let mut cfg = ClientConfig::new(
-                config::current_exe_path("config").unwrap(), 
-                policy.as_ref(),
-            )
-            .unwrap();
-            let httpc = command::http::Client::new().unwrap();
-            let mut kpg = command::SigningKeyPairGenerator::new(&pgp::TTYPasswordProvider, policy.as_ref());
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let httpc = httpc_builder.build().unwrap();
+            let password = build_password_provider(matches, cfg.get_data().prompt_timeout());
+            let mut kpg = command::SigningKeyPairGenerator::new(password.as_ref(), policy);
+
+            let names = session.names.insert(command::registry::RegistryNames::load(base_dir.join("registry-names")));
 
             command::command_unregister(
-                &mut cfg,
+                cfg,
+                names,
                 &httpc,
                 &mut kpg,
-                sub_matches.value_of("comment").unwrap_or_default()
-            )
-            .unwrap_or_else(handle_err);
+                sub_matches.value_of("comment").unwrap_or_default(),
+                destructive_options(sub_matches),
+            )?;
         },
-        ("submit", Some(sub_matches)) => {
+        ("rotate-key", Some(sub_matches)) => {
 
-            let policy = config::build_policy();
-            let mut cfg = ClientConfig::new(
-                config::current_exe_path("config").unwrap(), 
-                policy.as_ref(),
-            )
-            .unwrap();
-            let mut records = RecordConfig::new(
-                config::current_exe_path(format!("record-{}", cfg.get_data().server_uuid.unwrap())).unwrap()
-            )
-            .unwrap();
-            let httpc = command::http::Client::new().unwrap();
-            let mut kpg = command::SigningKeyPairGenerator::new(&pgp::TTYPasswordProvider, policy.as_ref());
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let httpc = httpc_builder.build().unwrap();
+            let password = build_password_provider(matches, cfg.get_data().prompt_timeout());
+            let mut kpg = command::SigningKeyPairGenerator::new(password.as_ref(), policy);
+
+            let names = session.names.insert(command::registry::RegistryNames::load(base_dir.join("registry-names")));
 
-            command::command_submit(
-                &mut cfg,
-                &mut records,
+            command::command_rotate_key(
+                cfg,
+                names,
                 &httpc,
                 &mut kpg,
-                sub_matches.value_of("player_uuid").unwrap(),
-                sub_matches.value_of("points").unwrap(),
-                sub_matches.value_of("comment").unwrap_or_default(),
-                sub_matches.is_present("force")
+                base_dir,
+                sub_matches.value_of("cert_file"),
+                sub_matches.value_of("key_id").unwrap(),
+                sub_matches.value_of("server_name"),
+                destructive_options(sub_matches),
+            )?;
+        },
+        ("submit", Some(sub_matches)) => {
+
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let server_uuid = command::error::ConfigMissing::ok(cfg.get_data().server_uuid, "client.server_uuid")?;
+            let records = session.records.insert(RecordConfig::new(
+                base_dir.join(format!("record-{}", server_uuid)),
+                RecordHeader::new(cfg.get_data().api_url.clone(), cfg.get_data().server_uuid.clone()),
+                sub_matches.is_present("accept_records_origin")
             )
-            .unwrap_or_else(handle_err);
+            .map_err(command::error::AppError::new_other)?);
+            let httpc = httpc_builder.build().unwrap();
+            let password = build_password_provider(matches, cfg.get_data().prompt_timeout());
+            let mut kpg = command::SigningKeyPairGenerator::new(password.as_ref(), policy);
+
+            if let Some(batch) = sub_matches.value_of("batch") {
+                command::command_submit_batch(
+                    cfg,
+                    records,
+                    &httpc,
+                    &mut kpg,
+                    batch,
+                    sub_matches.value_of("interval"),
+                    sub_matches.is_present("force"),
+                    sub_matches.is_present("dry_run"),
+                    sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                    &mut io::stdout(),
+                    Some(progress_observer(sub_matches)),
+                )?;
+            } else if sub_matches.occurrences_of("player_uuid") > 1 {
+                let player_uuids: Vec<&str> = sub_matches.values_of("player_uuid").unwrap().collect();
+                command::command_submit_many(
+                    cfg,
+                    records,
+                    &httpc,
+                    &mut kpg,
+                    player_uuids.as_slice(),
+                    sub_matches.value_of("points"),
+                    sub_matches.value_of("comment"),
+                    sub_matches.value_of("timestamp"),
+                    sub_matches.is_present("force"),
+                    sub_matches.is_present("dry_run"),
+                    sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                    &mut io::stdout(),
+                )?;
+            } else {
+                let namecache = session.namecache.insert(command::namecache::NameCache::load(base_dir.join("namecache.json")));
+                command::command_submit(
+                    cfg,
+                    records,
+                    &httpc,
+                    &mut kpg,
+                    sub_matches.value_of("player_uuid"),
+                    sub_matches.value_of("player_name").map(|name| match sub_matches.value_of("usercache") {
+                        Some(path) => (name, command::NameSource::UserCache(path)),
+                        None => (name, command::NameSource::Online(namecache)),
+                    }),
+                    sub_matches.value_of("points"),
+                    sub_matches.value_of("comment"),
+                    sub_matches.value_of("timestamp"),
+                    sub_matches.is_present("force"),
+                    sub_matches.is_present("dry_run"),
+                    sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                    &mut io::stdout(),
+                )?;
+            }
         },
         ("recall", Some(sub_matches)) => {
 
-            let policy = config::build_policy();
-            let mut cfg = ClientConfig::new(
-                config::current_exe_path("config").unwrap(), 
-                policy.as_ref(),
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let server_uuid = command::error::ConfigMissing::ok(cfg.get_data().server_uuid, "client.server_uuid")?;
+            let records = session.records.insert(RecordConfig::new(
+                base_dir.join(format!("record-{}", server_uuid)),
+                RecordHeader::new(cfg.get_data().api_url.clone(), cfg.get_data().server_uuid.clone()),
+                sub_matches.is_present("accept_records_origin")
             )
-            .unwrap();
-            let mut records = RecordConfig::new(
-                config::current_exe_path(format!("record-{}", cfg.get_data().server_uuid.unwrap())).unwrap()
-            )
-            .unwrap();
-            let httpc = command::http::Client::new().unwrap();
-            let mut kpg = command::SigningKeyPairGenerator::new(&pgp::TTYPasswordProvider, policy.as_ref());
+            .map_err(command::error::AppError::new_other)?);
+            let httpc = httpc_builder.build().unwrap();
+            let password = build_password_provider(matches, cfg.get_data().prompt_timeout());
+            let mut kpg = command::SigningKeyPairGenerator::new(password.as_ref(), policy);
 
-            command::command_recall(
-                &mut cfg,
-                &mut records,
-                &httpc,
-                &mut kpg,
-                sub_matches.value_of("record_uuid").unwrap(),
-                sub_matches.value_of("comment").unwrap_or_default(),
-                sub_matches.is_present("force")
-            )
-            .unwrap_or_else(handle_err);
+            let names = session.names.insert(command::registry::RegistryNames::load(base_dir.join("registry-names")));
+
+            if let Some(file) = sub_matches.value_of("from_file") {
+                command::command_recall_batch(
+                    cfg,
+                    records,
+                    names,
+                    &httpc,
+                    &mut kpg,
+                    file,
+                    sub_matches.value_of("interval"),
+                    sub_matches.value_of("comment").unwrap_or_default(),
+                    sub_matches.is_present("force"),
+                    sub_matches.is_present("dry_run"),
+                    destructive_options(sub_matches),
+                    sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                    &mut io::stdout(),
+                )?;
+            } else {
+                command::command_recall(
+                    cfg,
+                    records,
+                    names,
+                    &httpc,
+                    &mut kpg,
+                    if let Some(s) = sub_matches.value_of("player_uuid") {
+                        command::RecallHandleWrap::PlayerUUID(s)
+                    } else {
+                        command::RecallHandleWrap::RecordUUID(sub_matches.value_of("record_uuid").unwrap())
+                    },
+                    sub_matches.value_of("comment").unwrap_or_default(),
+                    sub_matches.is_present("force"),
+                    sub_matches.is_present("dry_run"),
+                    destructive_options(sub_matches),
+                    sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                    &mut io::stdout(),
+                )?;
+            }
         }
         ("cert", Some(sub_matches)) => {
 
-            let policy = config::build_policy();
-            let mut servers = ServersConfig::new(
-                config::current_exe_path("servers").unwrap(), 
-                config::current_exe_path("serverscert.pgp").unwrap(),
-                policy.as_ref()
-            )
-            .unwrap();
+            let servers = session.servers.insert(ServersConfig::new(base_dir.join("servers"), base_dir.join("serverscert.pgp"), policy).unwrap());
 
-            if sub_matches.is_present("add") {
+            if sub_matches.is_present("export") {
+                command::command_cert_export(
+                    servers,
+                    sub_matches.value_of("server_uuid"),
+                    sub_matches.value_of("output"),
+                )?;
+            } else if sub_matches.is_present("list") {
+                command::command_cert_list(
+                    servers,
+                    sub_matches.value_of("min_trust"),
+                    sub_matches.is_present("json"),
+                    &mut io::stdout(),
+                )?;
+            } else if sub_matches.is_present("fetch") {
+                let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+                let httpc = httpc_builder.build().unwrap();
+                command::command_cert_fetch(
+                    servers,
+                    cfg,
+                    &httpc,
+                    sub_matches.value_of("server_uuid").unwrap(),
+                    sub_matches.value_of("name"),
+                    sub_matches.value_of("trust").unwrap(),
+                    sub_matches.value_of("api_url"),
+                    sub_matches.is_present("allow_shared_key"),
+                    sub_matches.value_of("fingerprint"),
+                )?;
+            } else if sub_matches.is_present("import_from_api") {
+                let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+                let httpc = httpc_builder.build().unwrap();
+                command::command_cert_import_from_api(
+                    servers,
+                    cfg,
+                    &httpc,
+                    sub_matches.value_of("trust"),
+                    sub_matches.is_present("yes"),
+                )?;
+            } else if sub_matches.is_present("add") {
                 command::command_cert_add(
-                    &mut servers,
+                    servers,
                     sub_matches.value_of("server_uuid").unwrap(),
                     sub_matches.value_of("name").unwrap(),
                     sub_matches.value_of("key_id").unwrap(),
                     sub_matches.value_of("trust").unwrap(),
-                )
-                .unwrap_or_else(handle_err);
+                    sub_matches.value_of("api_url"),
+                    sub_matches.is_present("allow_shared_key"),
+                    sub_matches.value_of("file"),
+                )?;
+            } else if sub_matches.is_present("set_trust") {
+                command::command_cert_set_trust(
+                    servers,
+                    sub_matches.value_of("server_uuid").unwrap(),
+                    sub_matches.value_of("trust"),
+                    sub_matches.value_of("name"),
+                )?;
+            } else if sub_matches.is_present("update") {
+                command::command_cert_update(
+                    servers,
+                    sub_matches.value_of("server_uuid").unwrap(),
+                    sub_matches.value_of("api_url"),
+                )?;
             } else if sub_matches.is_present("remove") {
                 command::command_cert_remove(
-                    &mut servers,
-                    sub_matches.value_of("server_uuid").unwrap(),
-                )
-                .unwrap_or_else(handle_err);
+                    servers,
+                    sub_matches.value_of("server_uuid"),
+                    sub_matches.value_of("key_id"),
+                    sub_matches.value_of("name"),
+                )?;
             }
         }
         ("server", Some(sub_matches)) => {
 
-            let policy = config::build_policy();
-            let mut cfg = ClientConfig::new(
-                config::current_exe_path("config").unwrap(), 
-                policy.as_ref(),
-            )
-            .unwrap();
-            let httpc = command::http::Client::new().unwrap();
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let httpc = httpc_builder.build().unwrap();
 
-            if sub_matches.is_present("#") {
+            let names = session.names.insert(command::registry::RegistryNames::load(base_dir.join("registry-names")));
 
+            if sub_matches.is_present("my") {
+                command::command_server_info(
+                    cfg,
+                    names,
+                    &httpc,
+                    sub_matches.is_present("show_key"),
+                    sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                    &mut io::stdout(),
+                )?;
+            } else if let Some(uuid) = sub_matches.value_of("uuid") {
+                let servers = session.servers.insert(ServersConfig::new(base_dir.join("servers"), base_dir.join("serverscert.pgp"), policy).unwrap());
+                command::command_server_get(
+                    cfg,
+                    servers,
+                    names,
+                    &httpc,
+                    uuid,
+                    sub_matches.is_present("show_key"),
+                    sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                    &mut io::stdout(),
+                )?;
             } else {
                 command::command_server_list(
-                    &cfg,
+                    cfg,
+                    names,
                     &httpc,
-                    sub_matches.value_of("limit")
-                )
-                .unwrap_or_else(handle_err);
+                    sub_matches.value_of("limit"),
+                    sub_matches.value_of("filter"),
+                    sub_matches.value_of("key_id"),
+                    sub_matches.value_of("sort"),
+                    sub_matches.is_present("show_key"),
+                    sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                    &mut io::stdout(),
+                )?;
             }
         }
-        ("record", Some(sub_matches)) => {
+        ("records", Some(sub_matches)) => {
 
-            let policy = config::build_policy();
-            let mut cfg = ClientConfig::new(
-                config::current_exe_path("config").unwrap(), 
-                policy.as_ref(),
-            )
-            .unwrap();
-            let mut servers = ServersConfig::new(
-                config::current_exe_path("servers").unwrap(), 
-                config::current_exe_path("serverscert.pgp").unwrap(),
-                policy.as_ref()
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let server_uuid = command::error::ConfigMissing::ok(cfg.get_data().server_uuid, "client.server_uuid")?;
+            let records = session.records.insert(RecordConfig::new(
+                base_dir.join(format!("record-{}", server_uuid)),
+                RecordHeader::new(cfg.get_data().api_url.clone(), cfg.get_data().server_uuid.clone()),
+                sub_matches.is_present("accept_records_origin")
             )
-            .unwrap();
-            let httpc = command::http::Client::new().unwrap();
+            .map_err(command::error::AppError::new_other)?);
+
+            match sub_matches.subcommand() {
+                ("compact", Some(_)) => {
+                    command::command_records_compact(records, &mut io::stdout())?;
+                }
+                ("stats", Some(_)) => {
+                    command::command_records_stats(records, &mut io::stdout())?;
+                }
+                ("export", Some(export_matches)) => {
+                    command::command_records_export(
+                        records,
+                        export_matches.value_of("output"),
+                        export_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                    )?;
+                }
+                ("sync", Some(sync_matches)) => {
+                    let httpc = httpc_builder.build().unwrap();
+                    command::command_records_sync(
+                        cfg,
+                        records,
+                        &httpc,
+                        sync_matches.is_present("auto_heal_local"),
+                        &mut io::stdout(),
+                    )?;
+                }
+                _ => {
+                    command::command_records(
+                        records,
+                        sub_matches.value_of("player_uuid"),
+                        sub_matches.value_of("after"),
+                        sub_matches.value_of("before"),
+                        sub_matches.is_present("include_recalled"),
+                        sub_matches.is_present("count"),
+                        sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                        &mut io::stdout(),
+                    )?;
+                }
+            }
+        }
+        ("debug", Some(sub_matches)) => {
+            if let ("request", Some(sub_matches)) = sub_matches.subcommand() {
+
+                let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+                let password = build_password_provider(matches, cfg.get_data().prompt_timeout());
+                let mut kpg = command::SigningKeyPairGenerator::new(password.as_ref(), policy);
+
+                command::command_debug_request(
+                    cfg,
+                    &mut kpg,
+                    sub_matches.value_of("kind").unwrap(),
+                    sub_matches,
+                    sub_matches.is_present("use_real_key"),
+                    &mut io::stdout(),
+                )?;
+            }
+        }
+        ("status", Some(_)) => {
+
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let servers = ServersConfig::new(
+                base_dir.join("servers"), 
+                base_dir.join("serverscert.pgp"),
+                policy
+            );
+            let records = cfg.get_data().server_uuid.map(|server_uuid| {
+                RecordConfig::open(
+                    base_dir.join(format!("record-{}", server_uuid)),
+                    RecordHeader::new(cfg.get_data().api_url.clone(), Some(server_uuid)),
+                    false
+                )
+            });
+            let httpc = httpc_builder.build().unwrap();
+
+            command::command_status(
+                cfg,
+                &servers,
+                records.as_ref(),
+                &httpc,
+                &mut io::stdout(),
+            )?;
+        }
+        ("examples", Some(_)) => {
+            command::examples::write_walkthrough(io::stdout()).unwrap();
+        }
+        ("ping", Some(sub_matches)) => {
+
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let httpc = httpc_builder.build().unwrap();
+            command::command_ping(cfg, &httpc, sub_matches.value_of("api_url"), &mut io::stdout())?;
+        }
+        ("version", Some(sub_matches)) => {
+            command::command_version(
+                base_dir,
+                sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                &mut io::stdout(),
+            )?;
+        }
+        ("record", Some(sub_matches)) => {
+
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let servers = session.servers.insert(ServersConfig::new(base_dir.join("servers"), base_dir.join("serverscert.pgp"), policy).unwrap());
+            let httpc = httpc_builder.build().unwrap();
+            let names = session.names.insert(command::registry::RegistryNames::load(base_dir.join("registry-names")));
+            let resolve_names = sub_matches.is_present("resolve_names");
+            let output = sub_matches.value_of("output");
+            let format = report_format(sub_matches, output);
 
             loop {
+                if let Some(s) = sub_matches.value_of("verify_file") {
+                    command::command_verify_file(servers, s, &mut io::stdout())?;
+                    break;
+                }
+                if sub_matches.is_present("all_servers") {
+                    command::command_get_player_submit(
+                        cfg,
+                        servers,
+                        names,
+                        resolve_names,
+                        &httpc,
+                        sub_matches.value_of("player").unwrap(),
+                        format,
+                        output,
+                    &mut io::stdout(),
+                    )?;
+                    break;
+                }
                 if let Some(s) = sub_matches.value_of("submit_uuid") {
                     command::command_get_submit(
-                        &cfg,
-                        &servers,
+                        cfg,
+                        servers,
+                        names,
+                        resolve_names,
                         &httpc,
-                        s
-                    )
-                    .unwrap_or_else(handle_err);
+                        s,
+                        format,
+                        output,
+                    &mut io::stdout(),
+                    )?;
                     break;
                 }
                 if let Some(s) = sub_matches.value_of("server_uuid") {
                     command::command_get_server_submit(
-                        &cfg,
-                        &servers,
+                        cfg,
+                        servers,
+                        names,
+                        resolve_names,
                         &httpc,
                         command::ServerHandleWrap::UUID(s),
                         sub_matches.value_of("limit"),
                         sub_matches.value_of("after"),
-                    )
-                    .unwrap_or_else(handle_err);
+                        sub_matches.value_of("before"),
+                        sub_matches.value_of("player"),
+                        sub_matches.value_of("max"),
+                        format,
+                        output,
+                    &mut io::stdout(),
+                    )?;
                     break;
                 }
                 if let Some(s) = sub_matches.value_of("key_id") {
                     command::command_get_server_submit(
-                        &cfg,
-                        &servers,
+                        cfg,
+                        servers,
+                        names,
+                        resolve_names,
                         &httpc,
                         command::ServerHandleWrap::KeyID(s),
                         sub_matches.value_of("limit"),
                         sub_matches.value_of("after"),
-                    )
-                    .unwrap_or_else(handle_err);
+                        sub_matches.value_of("before"),
+                        sub_matches.value_of("player"),
+                        sub_matches.value_of("max"),
+                        format,
+                        output,
+                    &mut io::stdout(),
+                    )?;
+                    break;
+                }
+                if sub_matches.is_present("auto") {
+                    command::command_get_server_submit_auto(
+                        cfg,
+                        servers,
+                        &httpc,
+                        sub_matches.value_of("limit"),
+                        sub_matches.value_of("after"),
+                        sub_matches.value_of("before"),
+                        sub_matches.value_of("min_trust"),
+                        sub_matches.value_of("output").unwrap(),
+                        Some(progress_observer(sub_matches)),
+                    )?;
                     break;
                 }
-                // if sub_matches.is_present("auto") {
-                //     app::command_get_server_submit_auto(
-                //         &cfg,
-                //         sub_matches.value_of("limit"),
-                //         sub_matches.value_of("after"),
-                //         sub_matches.value_of("output").unwrap()
-                //     )
-                //     .unwrap();
-                //     break;
-                // }
-                // break;
+                break;
             }
         },
         ("import", Some(sub_matches)) => {
 
-            let policy = config::build_policy();
-            let mut cfg = ClientConfig::new(
-                config::current_exe_path("config").unwrap(), 
-                policy.as_ref(),
-            )
-            .unwrap();
-            let mut records = RecordConfig::new(
-                config::current_exe_path(format!("record-{}", cfg.get_data().server_uuid.unwrap())).unwrap()
+            let cfg = session.client.insert(load_client_config(base_dir, policy)?);
+            let server_uuid = command::error::ConfigMissing::ok(cfg.get_data().server_uuid, "client.server_uuid")?;
+            let records = session.records.insert(RecordConfig::new(
+                base_dir.join(format!("record-{}", server_uuid)),
+                RecordHeader::new(cfg.get_data().api_url.clone(), cfg.get_data().server_uuid.clone()),
+                sub_matches.is_present("accept_records_origin")
             )
-            .unwrap();
-            let httpc = command::http::Client::new().unwrap();
-            let mut kpg = command::SigningKeyPairGenerator::new(&pgp::TTYPasswordProvider, policy.as_ref());
+            .map_err(command::error::AppError::new_other)?);
+            let httpc = httpc_builder.build().unwrap();
+            let password = build_password_provider(matches, cfg.get_data().prompt_timeout());
+            let mut kpg = command::SigningKeyPairGenerator::new(password.as_ref(), policy);
 
             let rules = command::banlist::BasicGeneratePoints;
+            let checkpoint = base_dir.join(format!("import-{}.state", server_uuid));
 
             command::command_import(
-                &mut cfg, 
-                &mut records, 
+                cfg, 
+                records, 
                 &httpc, 
                 &mut kpg, 
                 sub_matches.value_of("banlist").unwrap(),
                 sub_matches.value_of("interval"),
-                &rules
-            )
-            .unwrap_or_else(handle_err);
+                sub_matches.values_of("protect_from").map(Iterator::collect).unwrap_or_default(),
+                sub_matches.is_present("ignore_errors"),
+                sub_matches.is_present("dry_run"),
+                sub_matches.value_of("timestamp_policy"),
+                sub_matches.value_of("usercache"),
+                checkpoint.as_path(),
+                sub_matches.is_present("resume"),
+                &rules,
+                sub_matches.value_of("format").unwrap().parse().unwrap_or_default(),
+                &mut io::stdout(),
+                Some(progress_observer(sub_matches)),
+            )?;
         },
         _ => {
             
         },
     }
-}
 
+    Ok(())
+}
 
 struct OptionalKeyIDDisplay<'a>(&'a Option<KeyID>);
 
@@ -663,19 +2039,207 @@ impl<'a, S: AsRef<str>> fmt::Display for OptionalStrDisplay<'a, S> {
 }
 
 
-fn handle_err(e: command::error::AppError) {
-    match e {
-        command::error::AppError::Args(a) => {
-            eprintln!("{}", a);
+struct OptionalValueDisplay<'a, T: fmt::Display>(&'a Option<T>);
+
+impl<'a, T: fmt::Display> fmt::Display for OptionalValueDisplay<'a, T> {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(value) = self.0 {
+            value.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+
+fn build_http_client<'a>(matches: &'a clap::ArgMatches) -> Result<command::http::ClientBuilder, command::error::AppError<'a>> {
+    use std::time::Duration;
+    use command::error::ArgsError;
+
+    let mut builder = command::http::ClientBuilder::new();
+    if let Some(n) = ArgsError::parse_matches_optional(matches, "retries", "unsigned integer")? {
+        builder = builder.max_retries(n);
+    }
+    if let Some(ms) = ArgsError::parse_matches_optional(matches, "retry_delay", "integer of milliseconds")? {
+        builder = builder.retry_delay(Duration::from_millis(ms));
+    }
+    if let Some(path) = matches.value_of("http_log") {
+        builder = builder.log_file(Path::new(path)).map_err(command::error::AppError::new_other)?;
+    }
+    if let Some(user_agent) = matches.value_of("user_agent") {
+        builder = builder.user_agent(user_agent);
+    }
+    Ok(builder)
+}
+
+
+/// an explicit `--format` wins over the extension of `-o`
+fn report_format(sub_matches: &clap::ArgMatches, output: Option<&str>) -> command::output::OutputFormat {
+    use command::output::OutputFormat;
+
+    if sub_matches.occurrences_of("format") == 0 {
+        match output.and_then(|path| Path::new(path).extension()).and_then(|ext| ext.to_str()) {
+            Some("csv") => return OutputFormat::Csv,
+            Some("json") => return OutputFormat::Json,
+            _ => {}
+        }
+    }
+    sub_matches.value_of("format").unwrap().parse().unwrap_or_default()
+}
+
+fn destructive_options(sub_matches: &clap::ArgMatches) -> command::DestructiveOptions {
+    command::DestructiveOptions {
+        yes: sub_matches.is_present("yes"),
+        override_registry_guard: sub_matches.is_present("override_registry_guard"),
+    }
+}
+
+fn progress_observer(sub_matches: &clap::ArgMatches) -> &'static dyn command::observer::Observer {
+    if sub_matches.is_present("progress_json") {
+        &command::observer::JsonProgress
+    } else {
+        &command::observer::ProgressPrinter
+    }
+}
+
+/// environment variable, then `--passphrase-file`, then the terminal
+fn build_password_provider(matches: &clap::ArgMatches, timeout: Option<std::time::Duration>) -> Box<dyn pgp::PasswordProvider> {
+    let var = matches.value_of("password_env").unwrap_or("OPENMPRDB_PASSPHRASE");
+    let mut providers: Vec<Box<dyn pgp::PasswordProvider>> = vec![Box::new(pgp::EnvPasswordProvider::new(var.to_owned()))];
+    if let Some(path) = matches.value_of("passphrase_file") {
+        providers.push(Box::new(pgp::FilePasswordProvider::new(PathBuf::from(path))));
+    }
+    providers.push(Box::new(pgp::TTYPasswordProvider::new(timeout)));
+    Box::new(pgp::ChainPasswordProvider::new(providers))
+}
+
+
+fn show_config(cfg: &mut ClientConfig) {
+    let loaded = cfg.require_cert();
+    let data = cfg.get_data();
+    println!("cert_file = {}", OptionalPathDisplay(&data.cert_file));
+    println!("key_id = {}", OptionalKeyIDDisplay(&data.key_id));
+    println!("fingerprint = {}", OptionalFingerprintDisplay(&data.fingerprint));
+    println!("api_url = {}", OptionalStrDisplay(&data.api_url));
+    println!("server_uuid = {}", OptionalUUIDDisplay(&data.server_uuid));
+    println!("prompt_timeout_secs = {}", OptionalValueDisplay(&data.prompt_timeout_secs));
+    println!("auto_refresh_certs = {}", OptionalValueDisplay(&data.auto_refresh_certs));
+    println!("confirm_registry = {}", OptionalStrDisplay(&data.confirm_registry));
+    println!("require_encrypted_key = {}", OptionalValueDisplay(&data.require_encrypted_key));
+    println!("protected_players_file = {}", OptionalPathDisplay(&data.protected_players_file));
+    if data.cert_file.is_some() {
+        match data.get_cert() {
+            Some(cert) => {
+                println!("# cert_file loaded: {}", cert.fingerprint());
+                for ua in cert.userids() {
+                    println!("# user_id: {}", String::from_utf8_lossy(ua.value()));
+                }
+                if let Some(ref key_id) = data.key_id {
+                    if pgp::check_secret_key(cert, cfg.policy(), None, key_id) {
+                        println!("# key_id found in cert_file");
+                    } else {
+                        println!("# key_id NOT found in cert_file");
+                    }
+                }
+            }
+            None => {
+                match loaded {
+                    Err(e) => println!("# cert_file can not be loaded: {}", e),
+                    Ok(_) => println!("# cert_file can not be loaded"),
+                }
+            }
+        }
+    }
+}
+
+
+/// set when the subcommand runs with `--format json`, so failures are reported as json too
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// a config file that can not be loaded is reported like a missing field (exit code 3), not as a panic
+fn load_client_config<'p>(base_dir: &Path, policy: &'p dyn sequoia_openpgp::policy::Policy) -> Result<ClientConfig<'p>, command::error::ConfigMissing> {
+    let path = base_dir.join("config");
+    ClientConfig::new(path.clone(), policy)
+        .map_err(|e| command::error::ConfigMissing::unloadable("client", format!("{}: {}", path.display(), e)))
+}
+
+fn handle_err(e: command::error::AppError) -> ! {
+    let code = e.code();
+    let (exit_code, message) = match e {
+        command::error::AppError::Args(a) => (2, a.to_string()),
+        command::error::AppError::Config(c) => (3, c.to_string()),
+        command::error::AppError::Response(r) => (4, r.to_string()),
+        command::error::AppError::Other(m) => (1, m.to_string()),
+    };
+    if JSON_ERRORS.load(Ordering::Relaxed) {
+        let v = command::output::ErrorJson::new(code.code(), exit_code, message.as_str());
+        let _ = command::output::write_json(&mut io::stderr(), &v);
+    } else {
+        eprintln!("[{}] {}", code, message);
+    }
+    std::process::exit(exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn examples_parse() {
+        for example in command::examples::EXAMPLES {
+            let args = std::iter::once("openmprdbc-cli").chain(example.args.iter().copied());
+            if let Err(e) = build_cli().get_matches_from_safe(args) {
+                panic!("example `{}` does not parse: {}", example.args.join(" "), e.message);
+            }
+            // without the slash `v1/...` would replace the last path segment when joined
+            for pair in example.args.windows(2).filter(|pair| pair[0] == "--api-url") {
+                assert!(pair[1].ends_with('/'), "example `{}` has an api url without trailing slash", example.args.join(" "));
+            }
         }
-        command::error::AppError::Config(c) => {
-            eprintln!("{}", c);
+    }
+
+    #[test]
+    fn every_subcommand_has_a_scope() {
+        let cli = build_cli();
+        let names: Vec<_> = cli.p.subcommands.iter().map(|sub| sub.get_name()).collect();
+        assert!(names.contains(&"submit") && names.contains(&"examples"));
+        for name in names {
+            assert!(command_scope(name).is_some(), "{} has no scope in the error catalog", name);
         }
-        command::error::AppError::Response(r) => {
-            eprintln!("{}", r);
+    }
+
+    #[test]
+    fn records_without_server_uuid_is_missing_config() {
+        let base_dir = std::env::temp_dir().join(format!("openmprdbc-main-{}-no-server-uuid", std::process::id()));
+        fs::create_dir_all(&base_dir).unwrap();
+        let matches = build_cli().get_matches_from(&["openmprdbc-cli", "records"]);
+        let policy = config::build_policy();
+        let mut session = Session::default();
+        let result = run(&matches, base_dir.as_path(), &command::http::ClientBuilder::new(), policy.as_ref(), &mut session);
+        match result {
+            Err(e) => assert_eq!(e.code(), command::error::ErrorCode::MissingConfig),
+            Ok(_) => panic!("records ran without server_uuid"),
         }
-        command::error::AppError::Other(m) => {
-            eprintln!("{}", m);
+        drop(session);
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn broken_client_config_is_missing_config() {
+        let base_dir = std::env::temp_dir().join(format!("openmprdbc-main-{}-broken-config", std::process::id()));
+        fs::create_dir_all(&base_dir).unwrap();
+        fs::write(base_dir.join("config"), "{ not json").unwrap();
+        let matches = build_cli().get_matches_from(&["openmprdbc-cli", "records"]);
+        let policy = config::build_policy();
+        let mut session = Session::default();
+        let result = run(&matches, base_dir.as_path(), &command::http::ClientBuilder::new(), policy.as_ref(), &mut session);
+        match result {
+            Err(e) => assert_eq!(e.code(), command::error::ErrorCode::MissingConfig),
+            Ok(_) => panic!("records ran with a broken config"),
         }
+        drop(session);
+        assert_eq!(fs::read_to_string(base_dir.join("config")).unwrap(), "{ not json");
+        fs::remove_dir_all(&base_dir).unwrap();
     }
-}
\ No newline at end of file
+}
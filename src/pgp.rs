@@ -13,6 +13,7 @@ use std::hash::Hasher;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::rc::Rc;
+use std::cell::RefCell;
 use std::rc::Weak;
 
 use anyhow::anyhow;
@@ -39,7 +40,9 @@ use sequoia_openpgp::serialize::stream::Message;
 use sequoia_openpgp::serialize::stream::Signer;
 use sequoia_openpgp::types::SignatureType;
 use sequoia_openpgp::types::SymmetricAlgorithm;
+use sequoia_openpgp::types::RevocationStatus;
 use sequoia_openpgp::crypto::KeyPair;
+use sequoia_openpgp::crypto::Password;
 use sequoia_openpgp::Fingerprint;
 use sequoia_openpgp::packet::key::SecretKeyMaterial;
 use sequoia_openpgp::packet::signature::SignatureBuilder;
@@ -94,8 +97,28 @@ impl <'a> GenerateConfig<'a> {
         Ok(())
     }
 
+    /**
+     * accept `<n>d`, `<n>w`, `<n>m` or `<n>y`; a month counts 30 days and a year 365 days
+     */
     pub fn set_expires(&mut self, expires: &str) -> GeneralResult<()> {
-        self.expires = Some(Duration::from_secs(60 * 60 * 24 * 365));
+        const DAY: u64 = 60 * 60 * 24;
+        let expires = expires.trim();
+        let (n, unit) = match expires.char_indices().last() {
+            Some((i, c)) => (&expires[..i], c),
+            None => return Err(anyhow!("empty expiration"))
+        };
+        let unit = match unit.to_ascii_lowercase() {
+            'd' => DAY,
+            'w' => DAY * 7,
+            'm' => DAY * 30,
+            'y' => DAY * 365,
+            _ => return Err(anyhow!("unrecognize expiration unit: {}", expires))
+        };
+        let n: u64 = n.parse().map_err(|_| anyhow!("unrecognize expiration: {}", expires))?;
+        if n == 0 {
+            return Err(anyhow!("expiration must be positive: {}", expires))
+        }
+        self.expires = Some(Duration::from_secs(n * unit));
         Ok(())
     }
 
@@ -126,14 +149,7 @@ pub fn generate(cfg: GenerateConfig<'_>) -> GeneralResult<()> {
     }
     builder = builder.add_signing_subkey();
     if cfg.with_password {
-        let p0 = rpassword::read_password_from_tty(Some("Enter password to protect the key: "))?.into();
-        let p1 = rpassword::read_password_from_tty(Some("Repeat the password once more: "))?.into();
-
-        if p0 == p1 {
-            builder = builder.set_password(Some(p0));
-        } else {
-            return Err(anyhow::anyhow!("Passwords do not match."));
-        }
+        builder = builder.set_password(Some(read_new_password()?));
     }
 
     let (cert, rev) = builder.generate()?;
@@ -164,6 +180,24 @@ pub fn generate(cfg: GenerateConfig<'_>) -> GeneralResult<()> {
 }
 
 
+/**
+ * throwaway unencrypted cert with a signing subkey, to show signed requests without the real key
+ */
+pub fn generate_dummy_cert(p: &dyn Policy) -> GeneralResult<(Cert, KeyID)> {
+    let (cert, _) = CertBuilder::new()
+        .add_userid("openmprdbc-cli debug <debug@example.invalid>")
+        .add_signing_subkey()
+        .generate()?;
+    let key_id = cert.keys()
+        .with_policy(p, None)
+        .for_signing()
+        .map(|ka| ka.key().keyid())
+        .next()
+        .ok_or_else(|| anyhow!("Found no signing key on the dummy cert"))?;
+    Ok((cert, key_id))
+}
+
+
 /**
  * basic
  */
@@ -323,9 +357,129 @@ pub fn check_secret_key(cert: &Cert, p: &dyn Policy, timestamp: Option<SystemTim
 }
 
 
+/**
+ * key-id of the secret signing key matching the fingerprint
+ */
+pub fn find_secret_key_id(cert: &Cert, p: &dyn Policy, timestamp: Option<SystemTime>, fingerprint: &Fingerprint) -> Option<KeyID> {
+    for key in cert.keys()
+            .with_policy(p, timestamp)
+            .alive()
+            .revoked(false)
+            .secret()
+            .for_signing()
+            .supported()
+            .map(|ka| ka.key())
+            .filter(|key| key.fingerprint() == *fingerprint)
+    {
+        return Some(key.keyid());
+    }
+
+    None
+}
+
 
 pub trait PasswordProvider {
     fn provide(&self, cert: Fingerprint, key: Fingerprint) -> io::Result<String>;
+
+    /// where the last provided password came from, named in the error of a wrong password
+    fn source(&self) -> &'static str;
+}
+
+/// expiration time of key `key_id`; `None` if it never expires or is not found under the policy
+pub fn key_expiration_time(cert: &Cert, p: &dyn Policy, key_id: &KeyID) -> Option<SystemTime> {
+    cert.keys()
+        .with_policy(p, None)
+        .find(|ka| ka.key().keyid() == *key_id)
+        .and_then(|ka| ka.key_expiration_time())
+}
+
+/// keys expiring sooner than this are warned about before signing
+pub const EXPIRY_WARNING: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// valid signing keys of the cert that expire within `within` after `now`, with the time left
+pub fn expiring_signing_keys(cert: &Cert, p: &dyn Policy, now: SystemTime, within: Duration) -> Vec<(KeyID, Duration)> {
+    cert.keys()
+        .with_policy(p, now)
+        .alive()
+        .revoked(false)
+        .for_signing()
+        .supported()
+        .filter_map(|ka| {
+            let left = ka.key_expiration_time()?.duration_since(now).ok()?;
+            if left < within {
+                Some((ka.keyid(), left))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn warn_expiring_key(cert: &Cert, key_id: &KeyID, left: Duration) {
+    eprintln!("Warning: signing key {} on cert {} expires in {} days", key_id, cert.fingerprint(), left.as_secs() / (24 * 3600));
+}
+
+/// whether the secret of signing key `key_id` is protected by a passphrase; `None` if the cert has no such secret key
+pub fn is_secret_key_encrypted(cert: &Cert, p: &dyn Policy, timestamp: Option<SystemTime>, key_id: &KeyID) -> Option<bool> {
+    cert.keys()
+        .with_policy(p, timestamp)
+        .alive()
+        .revoked(false)
+        .secret()
+        .for_signing()
+        .supported()
+        .map(|ka| ka.key())
+        .find(|key| key.keyid() == *key_id)
+        .map(|key| !key.has_unencrypted_secret())
+}
+
+/**
+ * read a new password twice from tty
+ */
+pub fn read_new_password() -> GeneralResult<Password> {
+    let p0: Password = rpassword::read_password_from_tty(Some("Enter password to protect the key: "))?.into();
+    let p1: Password = rpassword::read_password_from_tty(Some("Repeat the password once more: "))?.into();
+
+    if p0 == p1 {
+        Ok(p0)
+    } else {
+        Err(anyhow::anyhow!("Passwords do not match."))
+    }
+}
+
+/**
+ * encrypt every unencrypted secret key of the cert with `password`;
+ * returns the updated cert and the number of keys newly protected
+ */
+pub fn protect_secret_keys(cert: Cert, password: &Password) -> GeneralResult<(Cert, usize)> {
+    let mut packets: Vec<Packet> = Vec::new();
+    for ka in cert.keys().unencrypted_secret() {
+        let key = ka.key().clone().parts_into_secret()?.encrypt_secret(password)?;
+        if ka.primary() {
+            packets.push(key.role_into_primary().into());
+        } else {
+            packets.push(key.role_into_subordinate().into());
+        }
+    }
+    let n = packets.len();
+    if n == 0 {
+        return Ok((cert, 0))
+    }
+    Ok((cert.insert_packets(packets)?, n))
+}
+
+/**
+ * write the cert with its secret keys, armored
+ */
+pub fn export_secret_key<W: Write + Sync + Send>(cert: &Cert, w: W) -> GeneralResult<()> {
+    let headers = cert.armor_headers();
+    let headers: Vec<_> = headers.iter()
+        .map(|value| ("Comment", value.as_str()))
+        .collect();
+    let mut w = Writer::with_headers(w, Kind::SecretKey, headers)?;
+    cert.as_tsk().serialize(&mut w)?;
+    w.finalize()?;
+    Ok(())
 }
 
 pub fn get_signing_key(cert: &Cert, p: &dyn Policy, timestamp: Option<SystemTime>, key_id: &KeyID, password: &dyn PasswordProvider) -> GeneralResult<KeyPair> {
@@ -346,9 +500,11 @@ pub fn get_signing_key(cert: &Cert, p: &dyn Policy, timestamp: Option<SystemTime
                     if e.algo() == SymmetricAlgorithm::Unencrypted {
                         continue;
                     }
+                    let source = password.source();
                     let password = password.provide(cert.fingerprint(), key.fingerprint())?;
                     
-                    e.decrypt(key.pk_algo(), &password.into())?
+                    e.decrypt(key.pk_algo(), &password.into())
+                        .map_err(|e| anyhow::anyhow!("can not decrypt {} with the password from {}: {}", key.fingerprint(), source, e))?
                 },
                 SecretKeyMaterial::Unencrypted(ref u) => u.clone(),
             };
@@ -360,6 +516,39 @@ pub fn get_signing_key(cert: &Cert, p: &dyn Policy, timestamp: Option<SystemTime
     Err(anyhow::anyhow!("Found no suitable signing key on {}", cert))
 }
 
+pub fn get_signing_key_by_fingerprint(cert: &Cert, p: &dyn Policy, timestamp: Option<SystemTime>, fingerprint: &Fingerprint, password: &dyn PasswordProvider) -> GeneralResult<KeyPair> {
+    for key in cert.keys()
+            .with_policy(p, timestamp)
+            .alive()
+            .revoked(false)
+            .for_signing()
+            .supported()
+            .map(|ka| ka.key())
+            .filter(|key| key.fingerprint() == *fingerprint)
+    {
+        if let Some(secret) = key.optional_secret() {
+            let unencrypted = match secret {
+                SecretKeyMaterial::Encrypted(ref e) => {
+
+                    if e.algo() == SymmetricAlgorithm::Unencrypted {
+                        continue;
+                    }
+                    let source = password.source();
+                    let password = password.provide(cert.fingerprint(), key.fingerprint())?;
+                    
+                    e.decrypt(key.pk_algo(), &password.into())
+                        .map_err(|e| anyhow::anyhow!("can not decrypt {} with the password from {}: {}", key.fingerprint(), source, e))?
+                },
+                SecretKeyMaterial::Unencrypted(ref u) => u.clone(),
+            };
+
+            return Ok(KeyPair::new(key.clone(), unencrypted).unwrap());
+        }
+    }
+
+    Err(anyhow::anyhow!("Found no suitable signing key {} on {}", fingerprint, cert))
+}
+
 pub fn build_signer<'a, W: 'a + Write + Sync + Send>(w: W, mut keypairs: Vec<KeyPair>) -> GeneralResult<Message<'a>> {
     if keypairs.is_empty() {
         return Err(anyhow::anyhow!("No signing keys found"));
@@ -394,13 +583,92 @@ pub fn export_publickey<W: Write + Sync + Send>(cert: &Cert, w: &mut W) -> Gener
     Ok(())
 }
 
+pub fn export_keyring<'c, I, W>(certs: I, w: &mut W) -> GeneralResult<()> 
+where
+    I: IntoIterator<Item = &'c Cert>,
+    W: Write + Sync + Send,
+{
+    let mut w = Writer::new(w, Kind::PublicKey)?;
+    for cert in certs {
+        cert.serialize(&mut w)?;
+    }
+    w.finalize()?;
+    Ok(())
+}
+
 pub fn export_publickey_raw<W: Write + Sync + Send>(cert: &Cert, w: &mut W) -> GeneralResult<()> {
     cert.serialize(w)
 }
 
 
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnverifiedReason {
+    MissingCert,
+    BadSignature,
+    PolicyRejection,
+    ContentMismatch,
+}
+
+impl UnverifiedReason {
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MissingCert => "missing cert",
+            Self::BadSignature => "bad signature",
+            Self::PolicyRejection => "policy rejection",
+            Self::ContentMismatch => "content mismatch",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VerifyError {
+    pub reason: UnverifiedReason,
+    pub error: anyhow::Error,
+}
+
+impl fmt::Display for VerifyError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("{}: {}", self.reason.as_str(), self.error))
+    }
+}
+
+impl std::error::Error for VerifyError {
+
+}
+
+impl VerifyError {
+
+    pub fn new(reason: UnverifiedReason, error: anyhow::Error) -> Self {
+        VerifyError { reason, error }
+    }
+}
+
+/**
+ * verified value along with the key that verified it
+ */
+#[derive(Debug)]
+pub struct Verified<V> {
+    pub value: V,
+    pub fingerprint: Fingerprint,
+    pub key_id: KeyID,
+    pub signature_time: Option<SystemTime>,
+}
+
 pub fn verify<'a, R, T, F, V>(cert: &'a Cert, key_id: &'a KeyID, policy: &dyn Policy, timestamp: T, signed: R, f: F) -> GeneralResult<V> 
+where
+    R: 'a + Read + Sync + Send,
+    T: Into<Option<SystemTime>>,
+    F: FnOnce(&mut dyn Read) -> GeneralResult<V>,
+{
+    verify_detailed(cert, key_id, policy, timestamp, signed, f)
+        .map(|verified| verified.value)
+        .map_err(anyhow::Error::from)
+}
+
+pub fn verify_detailed<'a, R, T, F, V>(cert: &'a Cert, key_id: &'a KeyID, policy: &dyn Policy, timestamp: T, signed: R, f: F) -> Result<Verified<V>, VerifyError> 
 where
     R: 'a + Read + Sync + Send,
     T: Into<Option<SystemTime>>,
@@ -420,17 +688,87 @@ where
         fingerprint = Some(key.fingerprint());
     }
 
-    let fingerprint = fingerprint.ok_or_else(|| anyhow::anyhow!("invalid keyid for cert: {}", key_id))?;
+    let fingerprint = fingerprint.ok_or_else(|| {
+        VerifyError::new(UnverifiedReason::PolicyRejection, anyhow::anyhow!("invalid keyid for cert: {}", key_id))
+    })?;
 
     let h = SpecificVerifyHelper {
         cert,
         key_id,
-        fingerprint: &fingerprint
+        fingerprint: &fingerprint,
+        signature_time: None,
+        failure: None,
     };
 
-    let mut v = VerifierBuilder::from_reader(signed)?.with_policy(policy, timestamp, h)?;
-    let value = f(&mut v)?;
-    Ok(value)
+    let mut v = VerifierBuilder::from_reader(signed)
+        .and_then(|b| b.with_policy(policy, timestamp, h))
+        .map_err(|e| VerifyError::new(UnverifiedReason::BadSignature, e))?;
+    match f(&mut v) {
+        Ok(value) => {
+            let h = v.helper_ref();
+            Ok(Verified {
+                value,
+                key_id: key_id.clone(),
+                signature_time: h.signature_time,
+                fingerprint,
+            })
+        }
+        Err(e) => {
+            let reason = v.helper_ref().failure.unwrap_or(UnverifiedReason::ContentMismatch);
+            Err(VerifyError::new(reason, e))
+        }
+    }
+}
+
+
+/**
+ * key ids of the issuers named by the signatures of a message; nothing is verified
+ */
+pub fn signature_issuers<'a, R>(signed: R, policy: &'a dyn Policy) -> GeneralResult<Vec<KeyID>> 
+where
+    R: 'a + Read + Sync + Send,
+{
+    let h = IssuerCollector { ids: Vec::new() };
+    let mut v = VerifierBuilder::from_reader(signed)?.with_policy(policy, None, h)?;
+    io::copy(&mut v, &mut io::sink())?;
+    let ids = v.helper_ref().ids.iter()
+        .map(|id| match id {
+            KeyHandle::KeyID(key_id) => key_id.clone(),
+            KeyHandle::Fingerprint(fingerprint) => KeyID::from(fingerprint),
+        })
+        .collect();
+    Ok(ids)
+}
+
+/**
+ * the payload of a signed message, read by `f` without verifying the signature; only for what needs no trust,
+ * like ordering messages
+ */
+pub fn read_unverified<'a, R, F, V>(signed: R, policy: &'a dyn Policy, f: F) -> GeneralResult<V> 
+where
+    R: 'a + Read + Sync + Send,
+    F: FnOnce(&mut dyn Read) -> GeneralResult<V>,
+{
+    let h = IssuerCollector { ids: Vec::new() };
+    let mut v = VerifierBuilder::from_reader(signed)?.with_policy(policy, None, h)?;
+    f(&mut v)
+}
+
+struct IssuerCollector {
+    ids: Vec<KeyHandle>,
+}
+
+impl VerificationHelper for IssuerCollector {
+
+    fn get_certs(&mut self, ids: &[KeyHandle]) -> GeneralResult<Vec<Cert>> {
+        self.ids.extend_from_slice(ids);
+        Ok(Vec::new())
+    }
+
+    fn check(&mut self, _structure: MessageStructure) -> GeneralResult<()> {
+        // without certs every signature is a missing key; only the issuers are wanted
+        Ok(())
+    }
 }
 
 
@@ -438,6 +776,8 @@ struct SpecificVerifyHelper<'a> {
     cert: &'a Cert,
     key_id: &'a KeyID,
     fingerprint: &'a Fingerprint,
+    signature_time: Option<SystemTime>,
+    failure: Option<UnverifiedReason>,
 }
 
 impl<'a> VerificationHelper for SpecificVerifyHelper<'a> {
@@ -482,15 +822,24 @@ impl<'a> VerificationHelper for SpecificVerifyHelper<'a> {
                     // whether the signature checks out mathematically, we apply
                     // our policy.
                     match results.into_iter().next() {
-                        Some(Ok(_)) =>
-                            good = true,
-                        Some(Err(e)) =>
-                            return Err(sequoia_openpgp::Error::from(e).into()),
-                        None =>
-                            return Err(anyhow!("No signature")),
+                        Some(Ok(good_checksum)) => {
+                            self.signature_time = good_checksum.sig.signature_creation_time();
+                            good = true
+                        }
+                        Some(Err(e)) => {
+                            self.failure = Some(UnverifiedReason::BadSignature);
+                            return Err(sequoia_openpgp::Error::from(e).into())
+                        }
+                        None => {
+                            self.failure = Some(UnverifiedReason::BadSignature);
+                            return Err(anyhow!("No signature"))
+                        }
                     }
                 },
-                _ => return Err(anyhow!("Unexpected message structure")),
+                _ => {
+                    self.failure = Some(UnverifiedReason::BadSignature);
+                    return Err(anyhow!("Unexpected message structure"))
+                }
             }
         }
  
@@ -524,26 +873,7 @@ impl<'a> VerificationHelper for VerifyHelper<'a> {
                     certs.push(self.cmgr.get(key_id).ok_or_else(|| anyhow!("cert not find: {}", key_id))?.as_ref().clone())
                 }
                 KeyHandle::Fingerprint(fingerprint) => {
-                    let mut find = false;
-                    'loop2:
-                    for cert in self.cmgr.iter() {
-                        for key in cert.keys()
-                            .with_policy(self.cmgr.policy, self.cmgr.timestamp)
-                            .alive()
-                            .revoked(false)
-                            .for_signing()
-                            .supported()
-                            .map(|ka| ka.key())
-                            .filter(|key| key.fingerprint() == *fingerprint) {
-                            certs.push(cert.as_ref().clone());
-                            find = true;
-                            break 'loop2;
-                        }
-                    }
-
-                    if !find {
-                        return Err(anyhow!("cert not find: {}", fingerprint))
-                    }
+                    certs.push(self.cmgr.get_by_fingerprint(fingerprint).ok_or_else(|| anyhow!("cert not find: {}", fingerprint))?.as_ref().clone())
                 }
             }
         }
@@ -600,7 +930,8 @@ pub fn read_cert_from_console() -> GeneralResult<Cert> {
 pub struct CertsInfo<'a> {
     certs: &'a [Cert],
     key_id: Option<&'a KeyID>,
-    p: &'a dyn Policy
+    p: &'a dyn Policy,
+    signing_only: bool,
 }
 
 impl<'a> CertsInfo<'a> {
@@ -609,9 +940,16 @@ impl<'a> CertsInfo<'a> {
         CertsInfo {
             certs,
             key_id,
-            p
+            p,
+            signing_only: false,
         }
     }
+
+    /// list only the keys that can sign right now: signing-capable, alive, not revoked and with secret material
+    pub fn signing_only(mut self, signing_only: bool) -> Self {
+        self.signing_only = signing_only;
+        self
+    }
 }
 
 impl<'a> fmt::Display for CertsInfo<'a> {
@@ -651,6 +989,16 @@ impl<'a> fmt::Display for CertsInfo<'a> {
 
         for cert in self.certs {
 
+            let usable = |ka: &ValidErasedKeyAmalgamation<_>| {
+                ka.for_signing()
+                    && ka.key().optional_secret().is_some()
+                    && ka.alive().is_ok()
+                    && !matches!(ka.revocation_status(), RevocationStatus::Revoked(_))
+            };
+            if self.signing_only && !cert.keys().with_policy(self.p, None).supported().any(|ka| usable(&ka)) {
+                continue;
+            }
+
             if cert.is_tsk() {
                 f.write_fmt(format_args!("* {} [Transferable Secret Key]\n", cert.fingerprint()))?;
             } else {
@@ -660,17 +1008,28 @@ impl<'a> fmt::Display for CertsInfo<'a> {
             
             for ka in cert.keys().with_policy(self.p, None).supported() {
                 let ka: ValidErasedKeyAmalgamation<_> = ka;
+                if self.signing_only && !usable(&ka) {
+                    continue;
+                }
                 let key = ka.key();
                 if let Some(key_id) = self.key_id {
                     if *key_id == key.keyid() {
                         f.write_str("->")?;
                     }
                 }
+                let mut marks = String::new();
+                if key.optional_secret().is_some() {
+                    marks.push_str(" [secret]");
+                }
+                if ka.for_signing() {
+                    marks.push_str(" [signing]");
+                }
                 f.write_fmt(format_args!(
-                    "\t{}/{} {}\n\t create: {}, expires: {}\n", 
+                    "\t{}/{} {}{}\n\t create: {}, expires: {}\n", 
                     key.pk_algo(),
                     key.keyid(),
                     key.fingerprint(),
+                    marks,
                     SystemTimeDisplay::Some(key.creation_time()),
                     SystemTimeDisplay::from(ka.key_expiration_time())
                 ))?;
@@ -726,7 +1085,9 @@ pub struct CertificationManager<'a> {
     policy: &'a dyn Policy,
     path: PathBuf,
     certs: HashSet<CertWrap>,
-    indexs: HashMap<KeyID, Weak<Cert>>,
+    // lookups drop entries whose cert is gone, hence the cells
+    indexs: RefCell<HashMap<KeyID, Weak<Cert>>>,
+    fingerprint_indexs: RefCell<HashMap<Fingerprint, Weak<Cert>>>,
     changed: bool,
     timestamp: SystemTime,
 }
@@ -739,6 +1100,7 @@ impl<'a> CertificationManager<'a> {
         let mut changed = false;
         let mut certs = HashSet::new();
         let mut indexs = HashMap::new();
+        let mut fingerprint_indexs = HashMap::new();
         
         match File::open(path.as_path()) {
             Ok(ifile) => {
@@ -755,6 +1117,7 @@ impl<'a> CertificationManager<'a> {
                             
                             let key_id = ka.keyid();
                             indexs.insert(key_id, Rc::downgrade(&cert));
+                            fingerprint_indexs.insert(ka.fingerprint(), Rc::downgrade(&cert));
                         }
                     }
                     changed = !insert;
@@ -771,21 +1134,31 @@ impl<'a> CertificationManager<'a> {
         
         
 
+        for CertWrap(cert) in certs.iter() {
+            for (key_id, left) in expiring_signing_keys(cert, p, timestamp, EXPIRY_WARNING) {
+                warn_expiring_key(cert, &key_id, left);
+            }
+        }
+
         Ok(CertificationManager {
             policy: p,
             path,
             certs,
-            indexs,
+            indexs: RefCell::new(indexs),
+            fingerprint_indexs: RefCell::new(fingerprint_indexs),
             changed,
             timestamp
         })
     }
 
     pub fn save(&mut self) -> GeneralResult<()> {
-        let mut ofile = File::create(self.path.as_path())?;
-        for CertWrap(cert) in self.certs.iter() {
-            cert.serialize(&mut ofile)?;
-        }
+        let certs = &self.certs;
+        crate::config::atomic_write(self.path.as_path(), |ofile| {
+            for CertWrap(cert) in certs.iter() {
+                cert.serialize(&mut *ofile).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            }
+            Ok(())
+        })?;
         Ok(())
     }
 
@@ -802,7 +1175,8 @@ impl<'a> CertificationManager<'a> {
                 .supported() {
                 
                 let key_id = ka.keyid();
-                self.indexs.insert(key_id, Rc::downgrade(&cert));
+                self.indexs.get_mut().insert(key_id, Rc::downgrade(&cert));
+                self.fingerprint_indexs.get_mut().insert(ka.fingerprint(), Rc::downgrade(&cert));
             }
             self.changed = true;
         }
@@ -822,7 +1196,8 @@ impl<'a> CertificationManager<'a> {
                 .supported() {
                 
                 let key_id = ka.keyid();
-                self.indexs.remove(&key_id);
+                self.indexs.get_mut().remove(&key_id);
+                self.fingerprint_indexs.get_mut().remove(&ka.fingerprint());
             }
             self.changed = true;
         }
@@ -831,10 +1206,40 @@ impl<'a> CertificationManager<'a> {
     }
 
     pub fn get(&self, key_id: &KeyID) -> Option<Rc<Cert>> {
-        if let Some(cert) = self.indexs.get(key_id) {
-            return Weak::upgrade(cert);
+        let mut indexs = self.indexs.borrow_mut();
+        let cert = Weak::upgrade(indexs.get(key_id)?);
+        if cert.is_none() {
+            // `certs` holds a strong reference to every indexed cert; a dangling entry means a bug elsewhere
+            indexs.remove(key_id);
         }
-        None
+        cert
+    }
+
+    /// drop index entries whose cert is no longer held; returns the number removed
+    pub fn gc_stale_index(&mut self) -> usize {
+        let indexs = self.indexs.get_mut();
+        let fingerprint_indexs = self.fingerprint_indexs.get_mut();
+        let before = indexs.len() + fingerprint_indexs.len();
+        indexs.retain(|_, cert| cert.strong_count() > 0);
+        fingerprint_indexs.retain(|_, cert| cert.strong_count() > 0);
+        before - indexs.len() - fingerprint_indexs.len()
+    }
+
+    /// certs with a signing key expiring within `within`
+    pub fn expiring_soon(&self, within: Duration) -> Vec<Rc<Cert>> {
+        self.iter()
+            .filter(|cert| !expiring_signing_keys(cert, self.policy, self.timestamp, within).is_empty())
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_by_fingerprint(&self, fingerprint: &Fingerprint) -> Option<Rc<Cert>> {
+        let mut fingerprint_indexs = self.fingerprint_indexs.borrow_mut();
+        let cert = Weak::upgrade(fingerprint_indexs.get(fingerprint)?);
+        if cert.is_none() {
+            fingerprint_indexs.remove(fingerprint);
+        }
+        cert
     }
 
     pub fn iter<'b>(&'b self) -> impl Iterator<Item = &'b Rc<Cert>> {
@@ -856,19 +1261,187 @@ impl<'a> Drop for CertificationManager<'a> {
 
 
 /**
- * 
+ * ask on the controlling terminal, which is there even when stdin is redirected
  */
 
-pub struct TTYPasswordProvider;
+#[cfg(unix)]
+const TTY_PATH: &str = "/dev/tty";
+
+#[cfg(windows)]
+const TTY_PATH: &str = "CONIN$";
+
+pub struct TTYPasswordProvider {
+    timeout: Option<Duration>,
+}
+
+impl TTYPasswordProvider {
+
+    pub fn new(timeout: Option<Duration>) -> Self {
+        TTYPasswordProvider {
+            timeout
+        }
+    }
+}
 
 impl PasswordProvider for TTYPasswordProvider {
 
+    fn source(&self) -> &'static str {
+        "terminal"
+    }
+
     fn provide(&self, cert: Fingerprint, key: Fingerprint) -> io::Result<String> {
-        rpassword::read_password_from_tty(Some(&format!("Please enter password to decrypt {}/{}: ", cert, key)))
+        let prompt = format!("Please enter password to decrypt {}/{}: ", cert, key);
+        let read = move |_: File| rpassword::read_password_from_tty(Some(&prompt));
+        read_password_from(Path::new(TTY_PATH), read, self.timeout)
+            .map_err(|e| io::Error::new(e.kind(), format!("{} (password for {}/{})", e, cert, key)))
+    }
+}
+
+/**
+ * read a password in a worker thread, as the read of rpassword can not be interrupted; `tty` is opened first, so a
+ * missing terminal fails at once instead of in the worker, and on a timeout the worker is left blocked in its read
+ */
+fn read_password_from<F>(tty: &Path, read: F, timeout: Option<Duration>) -> io::Result<String>
+where
+    F: FnOnce(File) -> io::Result<String> + Send + 'static
+{
+    use std::sync::mpsc;
+    use std::thread;
+
+    let input = std::fs::OpenOptions::new().read(true).open(tty)
+        .map_err(|e| io::Error::new(e.kind(), format!("no interactive terminal to enter password: {}", e)))?;
+    let password = match timeout {
+        Some(timeout) => {
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || {
+                let _ = tx.send(read(input));
+            });
+            rx.recv_timeout(timeout).unwrap_or_else(|_| {
+                eprintln!();
+                Err(io::Error::new(io::ErrorKind::TimedOut, format!("password prompt timed out after {}s", timeout.as_secs())))
+            })
+        }
+        None => read(input),
+    }?;
+    // rpassword gives an empty line for end of input too; no key is protected with an empty password
+    if password.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no password entered"))
     }
+    Ok(password)
 }
 
 
+/**
+ * read the password from an environment variable, for non-interactive runs
+ */
+pub struct EnvPasswordProvider {
+    env_var: String,
+}
+
+impl EnvPasswordProvider {
+
+    pub fn new(env_var: String) -> Self {
+        EnvPasswordProvider {
+            env_var
+        }
+    }
+}
+
+impl PasswordProvider for EnvPasswordProvider {
+
+    fn provide(&self, cert: Fingerprint, key: Fingerprint) -> io::Result<String> {
+        use std::env::VarError;
+
+        // an unset variable passes on to the next provider of a chain, one that is not unicode does not
+        std::env::var(self.env_var.as_str()).map_err(|e| match e {
+            VarError::NotPresent => io::Error::new(io::ErrorKind::NotFound, format!("env var {} not set (needed for key {}/{})", self.env_var, cert, key)),
+            VarError::NotUnicode(_) => io::Error::new(io::ErrorKind::InvalidData, format!("env var {} is not valid unicode (needed for key {}/{})", self.env_var, cert, key)),
+        })
+    }
+
+    fn source(&self) -> &'static str {
+        "environment"
+    }
+}
+
+
+/**
+ * read the password from the first line of a file, for non-interactive runs
+ */
+pub struct FilePasswordProvider {
+    path: PathBuf,
+}
+
+impl FilePasswordProvider {
+
+    pub fn new(path: PathBuf) -> Self {
+        FilePasswordProvider {
+            path
+        }
+    }
+}
+
+impl PasswordProvider for FilePasswordProvider {
+
+    fn provide(&self, cert: Fingerprint, key: Fingerprint) -> io::Result<String> {
+        use std::io::BufRead;
+
+        let mut line = String::new();
+        io::BufReader::new(File::open(self.path.as_path())?).read_line(&mut line)?;
+        let password = line.trim_end_matches(&['\r', '\n'][..]);
+        if password.is_empty() {
+            // an explicitly given file never falls through to the next provider
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("password file {} for {}/{} is empty", self.path.display(), cert, key)));
+        }
+        Ok(password.to_owned())
+    }
+
+    fn source(&self) -> &'static str {
+        "password file"
+    }
+}
+
+
+/**
+ * try each provider in turn; only a provider that has no password (`NotFound`) passes on to the next
+ */
+pub struct ChainPasswordProvider {
+    providers: Vec<Box<dyn PasswordProvider>>,
+    last: std::cell::Cell<&'static str>,
+}
+
+impl ChainPasswordProvider {
+
+    pub fn new(providers: Vec<Box<dyn PasswordProvider>>) -> Self {
+        ChainPasswordProvider {
+            providers,
+            last: std::cell::Cell::new("terminal"),
+        }
+    }
+}
+
+impl PasswordProvider for ChainPasswordProvider {
+
+    fn provide(&self, cert: Fingerprint, key: Fingerprint) -> io::Result<String> {
+        let mut not_found = None;
+        for provider in self.providers.iter() {
+            match provider.provide(cert.clone(), key.clone()) {
+                Ok(password) => {
+                    self.last.set(provider.source());
+                    return Ok(password)
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => not_found = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(not_found.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no password for {}/{}", cert, key))))
+    }
+
+    fn source(&self) -> &'static str {
+        self.last.get()
+    }
+}
+
 // Joins certificates and keyrings into a keyring, applying a filter.
 // fn filter<F>(inputs: Option<clap::Values>, output: &mut dyn io::Write, mut filter: F, to_certificate: bool) -> Result<()>
 // where F: FnMut(Cert) -> Option<Cert>,
@@ -900,3 +1473,62 @@ impl PasswordProvider for TTYPasswordProvider {
 //     }
 //     Ok(())
 // }
+
+
+#[cfg(all(test, unix))]
+mod tests {
+
+    use std::fs;
+    use std::time::Instant;
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("openmprdbc-pgp-{}-{}", std::process::id(), name))
+    }
+
+    /// what rpassword does with input that is not a terminal
+    fn piped(input: File) -> io::Result<String> {
+        rpassword::read_password_with_reader(Some(&mut io::BufReader::new(input)))
+    }
+
+    #[test]
+    fn password_from_piped_input() {
+        let path = temp_path("piped");
+        fs::write(&path, "secret\r\nnext line\n").unwrap();
+        assert_eq!(read_password_from(&path, piped, None).unwrap(), "secret");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn no_input_fails_at_once() {
+        let path = temp_path("empty");
+        fs::write(&path, "").unwrap();
+        let e = read_password_from(&path, piped, None).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn no_terminal_fails_at_once() {
+        let e = read_password_from(&temp_path("missing"), |_| panic!("read without a terminal"), Some(Duration::from_secs(1))).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+        assert!(e.to_string().contains("no interactive terminal"));
+    }
+
+    #[test]
+    fn unanswered_prompt_times_out() {
+        let path = temp_path("fifo");
+        let status = std::process::Command::new("mkfifo").arg(&path).status().unwrap();
+        assert!(status.success());
+
+        // keep a writer open, so the read blocks instead of seeing end of input
+        let writer = fs::OpenOptions::new().read(true).write(true).open(&path).unwrap();
+        let start = Instant::now();
+        let e = read_password_from(&path, piped, Some(Duration::from_millis(200))).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        drop(writer);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,358 @@
+//! every subcommand run as a process against a mock registry: stdout carries only the data a script
+//! would read, status, warnings and errors go to stderr
+
+use std::fs;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Output;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+const SERVER_UUID: &str = "3f2b4a9e-7c1d-4e8a-9b6f-0d5c2e1a8b74";
+const PLAYER_UUID: &str = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+
+/**
+ * a registry on a local port answering every request from `route`; the requests are kept in order
+ */
+struct Registry {
+    api_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl Registry {
+
+    fn start(route: fn(&str, &str) -> (u16, String)) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let api_url = format!("http://{}/api/", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_owned();
+                let target = parts.next().unwrap_or_default().to_owned();
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    if reader.read_line(&mut header).unwrap() == 0 || header == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let path = target.split('?').next().unwrap_or_default();
+                let (status, body) = route(method.as_str(), path);
+                seen.lock().unwrap().push(format!("{} {}", method, target));
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, body.len(), body
+                );
+            }
+        });
+        Registry { api_url, requests }
+    }
+
+    fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// a registry that knows no server and no submit
+fn empty_registry(method: &str, path: &str) -> (u16, String) {
+    match (method, path) {
+        ("GET", "/api/ping") => (200, String::from(r#"{"status":"OK"}"#)),
+        ("GET", "/api/v1/server/list") => (200, String::from(r#"{"status":"OK","servers":[]}"#)),
+        ("GET", p) if p.starts_with("/api/v1/submit/server/") => (200, String::from(r#"{"status":"OK","submits":[]}"#)),
+        _ => (404, String::from(r#"{"status":"NG","reason":"not found"}"#)),
+    }
+}
+
+fn failing_registry(_: &str, _: &str) -> (u16, String) {
+    (503, String::from(r#"{"status":"NG","reason":"maintenance"}"#))
+}
+
+/**
+ * a config dir of its own for each test
+ */
+struct Cli {
+    dir: PathBuf,
+}
+
+impl Cli {
+
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("openmprdbc-stdout-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Cli { dir }
+    }
+
+    /// registered as SERVER_UUID at the registry, without a key
+    fn configured(name: &str, registry: &Registry) -> Self {
+        let cli = Cli::new(name);
+        let config = format!(r#"{{"api_url":"{}","server_uuid":"{}"}}"#, registry.api_url, SERVER_UUID);
+        fs::write(cli.dir.join("config"), config).unwrap();
+        cli
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_openmprdbc-cli"));
+        for var in &["OPENMPRDB_CERT_FILE", "OPENMPRDB_KEY_ID", "OPENMPRDB_API_URL", "OPENMPRDB_SERVER_UUID", "OPENMPRDB_CONFIG_DIR", "OPENMPRDB_PASSPHRASE"] {
+            command.env_remove(var);
+        }
+        command.arg("--config-dir").arg(&self.dir).args(args).output().unwrap()
+    }
+}
+
+impl Drop for Cli {
+
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+/// a failed command leaves stdout empty and reports the code on stderr as json
+fn assert_json_error(output: &Output, code: &str) {
+    assert!(!output.status.success());
+    assert_eq!(stdout(output), "", "stdout of a failed command");
+    let error: serde_json::Value = serde_json::from_str(stderr(output)).unwrap();
+    assert_eq!(error["status"], "error");
+    assert_eq!(error["code"], code);
+}
+
+fn assert_error(output: &Output, code: &str) {
+    assert!(!output.status.success());
+    assert_eq!(stdout(output), "", "stdout of a failed command");
+    assert!(stderr(output).contains(&format!("[{}]", code)), "stderr: {}", stderr(output));
+}
+
+
+#[test]
+fn version() {
+    let output = Cli::new("version").run(&["version"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!("openmprdbc-cli {}\n", env!("CARGO_PKG_VERSION")));
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn examples() {
+    let output = Cli::new("examples").run(&["examples"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("# 1. "));
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn config() {
+    let cli = Cli::new("config");
+    let output = cli.run(&["config", "--api-url", "http://127.0.0.1:9/api"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "api_url = http://127.0.0.1:9/api/\n");
+    assert_eq!(stderr(&output), "");
+
+    let output = cli.run(&["config", "--json"]);
+    let config: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(config["api_url"], "http://127.0.0.1:9/api/");
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn error_catalog() {
+    let output = Cli::new("catalog").run(&["--list-error-codes", "json"]);
+    assert!(output.status.success());
+    let catalog: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(catalog[0]["code"], "E001");
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn ping() {
+    let registry = Registry::start(empty_registry);
+    let output = Cli::configured("ping", &registry).run(&["ping"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with(&format!("OK: {} responded in ", registry.api_url)));
+    assert_eq!(stderr(&output), "");
+    assert_eq!(registry.requests(), vec!["GET /api/ping"]);
+
+    let registry = Registry::start(failing_registry);
+    assert_error(&Cli::configured("ping-fails", &registry).run(&["ping"]), "E006");
+}
+
+#[test]
+fn status() {
+    let registry = Registry::start(empty_registry);
+    let output = Cli::configured("status", &registry).run(&["status"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).starts_with("[FAIL] cert_file is not set\n"));
+    assert!(stdout(&output).contains(&format!("[ ok ] registered as server {}\n", SERVER_UUID)));
+    assert_eq!(stderr(&output), "[E004] client is not fully operational\n");
+}
+
+#[test]
+fn server() {
+    let registry = Registry::start(empty_registry);
+    let output = Cli::configured("server", &registry).run(&["server", "--format", "json"]);
+    assert!(output.status.success());
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap(), serde_json::json!([]));
+    assert_eq!(stderr(&output), "");
+
+    let registry = Registry::start(failing_registry);
+    assert_json_error(&Cli::configured("server-fails", &registry).run(&["server", "--format", "json"]), "E006");
+}
+
+#[test]
+fn server_my() {
+    let registry = Registry::start(empty_registry);
+    let output = Cli::configured("server-my", &registry).run(&["server", "--my"]);
+    assert_error(&output, "E002");
+    assert!(stderr(&output).starts_with("server not registered or was unregistered\n"));
+    assert_eq!(registry.requests(), vec![format!("GET /api/v1/server/uuid/{}", SERVER_UUID)]);
+}
+
+#[test]
+fn cert() {
+    let output = Cli::new("cert").run(&["cert", "--list", "--json"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "[]\n");
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn records() {
+    let registry = Registry::start(empty_registry);
+    let cli = Cli::configured("records", &registry);
+
+    let output = cli.run(&["records", "--format", "json"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "[]\n");
+    assert_eq!(stderr(&output), "");
+
+    let output = cli.run(&["records", "--count"]);
+    assert_eq!(stdout(&output), "0\n");
+    assert_eq!(stderr(&output), "");
+
+    let output = cli.run(&["records", "export"]);
+    assert_eq!(stdout(&output), "record_uuid,player_uuid,timestamp\n");
+    assert_eq!(stderr(&output), "");
+
+    let output = cli.run(&["records", "sync"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!("local records agree with {}\n", registry.api_url));
+    assert_eq!(stderr(&output), "");
+    assert!(registry.requests().iter().all(|r| r.starts_with(&format!("GET /api/v1/submit/server/{}", SERVER_UUID))));
+}
+
+#[test]
+fn record() {
+    let registry = Registry::start(empty_registry);
+    let cli = Cli::configured("record", &registry);
+    // no cert of the server is known yet
+    let output = cli.run(&["record", "--server-uuid", SERVER_UUID, "--format", "json"]);
+    assert_json_error(&output, "E004");
+    assert!(registry.requests().is_empty());
+}
+
+#[test]
+fn debug_request() {
+    let registry = Registry::start(empty_registry);
+    let output = Cli::configured("debug", &registry).run(&["debug", "request", "get-submit", "--record-uuid", PLAYER_UUID]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!("GET {}v1/submit/uuid/{}\n", registry.api_url, PLAYER_UUID));
+    assert_eq!(stderr(&output), "");
+    assert!(registry.requests().is_empty());
+}
+
+// the commands below sign with the configured key; without one they fail before any request
+
+#[test]
+fn keyring() {
+    assert_error(&Cli::new("keyring").run(&["keyring"]), "E002");
+}
+
+#[test]
+fn register() {
+    let registry = Registry::start(empty_registry);
+    let cli = Cli::new("register");
+    fs::write(cli.dir.join("config"), format!(r#"{{"api_url":"{}"}}"#, registry.api_url)).unwrap();
+    assert_json_error(&cli.run(&["register", "--server-name", "survival", "--format", "json"]), "E002");
+    assert!(registry.requests().is_empty());
+}
+
+#[test]
+fn unregister() {
+    let registry = Registry::start(empty_registry);
+    let output = Cli::configured("unregister", &registry).run(&["unregister", "-y"]);
+    assert_error(&output, "E002");
+    // the target is shown before confirming, on stderr
+    assert!(stderr(&output).contains(SERVER_UUID));
+    assert!(registry.requests().is_empty());
+}
+
+#[test]
+fn rotate_key() {
+    let registry = Registry::start(empty_registry);
+    let output = Cli::configured("rotate-key", &registry).run(&["rotate-key", "--key-id", "0123456789ABCDEF", "-y"]);
+    assert_error(&output, "E002");
+    assert!(registry.requests().is_empty());
+}
+
+#[test]
+fn submit() {
+    let registry = Registry::start(empty_registry);
+    let output = Cli::configured("submit", &registry).run(&["submit", "-p", PLAYER_UUID, "-s", "-0.5", "--format", "json"]);
+    assert_json_error(&output, "E002");
+    assert!(registry.requests().is_empty());
+}
+
+#[test]
+fn recall() {
+    let registry = Registry::start(empty_registry);
+    let output = Cli::configured("recall", &registry).run(&["recall", "-r", PLAYER_UUID, "-y", "--format", "json"]);
+    assert_json_error(&output, "E004");
+    assert!(registry.requests().is_empty());
+}
+
+#[test]
+fn import() {
+    let registry = Registry::start(empty_registry);
+    let cli = Cli::configured("import", &registry);
+    let missing = cli.dir.join("banned-players.json");
+    assert_json_error(&cli.run(&["import", missing.to_str().unwrap(), "--format", "json"]), "E010");
+    assert!(registry.requests().is_empty());
+}
+
+#[test]
+fn generate() {
+    // rejected before asking for a passphrase
+    let output = Cli::new("generate").run(&["generate", "--user-id", "Server <admin@example.com>", "--expires", "soon"]);
+    assert_error(&output, "E001");
+}